@@ -350,7 +350,7 @@ mod grammar {
 
     decl_node!(struct Import: IMPORT_ITEM {
         (import_kw : Token<ImportKw >)
-        (ident     : Token<Ident    >)
+        (name_ref  : Node <NameRef  >)
         (semi      : Token<SemiColon>)
     });
 
@@ -364,6 +364,7 @@ mod grammar {
         (semicolon : Token  <SemiColon>)
     });
     decl_node!(struct FnDef: FN_ITEM {
+        (attrs      : NodeList<Attr>  )
         (fn_kw      : Token<FnKw>     )
         (name       : Node <Name>     )
         (param_list : Node <ParamList>)
@@ -373,6 +374,12 @@ mod grammar {
         (block      : Node <Block>    )
         (semicolon  : Token<SemiColon>)
     });
+    decl_node!(struct Attr: ATTR {
+        (hash     : Token<Hash       >)
+        (l_square : Token<LeftSquare >)
+        (name     : Node <Name       >)
+        (r_square : Token<RightSquare>)
+    });
     decl_node!(struct ExprItem: EXPR_ITEM {
         (expr      : Node <Expr     >)
         (semicolon : Token<SemiColon>)
@@ -428,6 +435,8 @@ mod grammar {
     decl_node_enum!(enum Type {
         BasicType(basic_type),
         PointerType(pointer_type),
+        FnType(fn_type),
+        NeverType(never_type),
     });
 
     decl_node!(struct BasicType: BASIC_TYPE {
@@ -436,6 +445,21 @@ mod grammar {
     decl_node!(struct PointerType: POINTER_TYPE {
         (pointee: Node<Type>)
     });
+    decl_node!(struct FnType: FN_TYPE {
+        (fn_kw       : Token<FnKw>         )
+        (param_types : Node <ParamTypeList>)
+        (arrow       : Token<DashArrow>    )
+        (return_ty   : Node <Type>         )
+    });
+    decl_node!(struct NeverType: NEVER_TYPE {
+        (bang: Token<Bang>)
+    });
+    decl_node!(struct ParamTypeList: PARAM_TYPE_LIST {
+        (l_paren  : Token   <LeftParen> )
+        (types    : NodeList<Type>      )
+        (va_param : Node    <VaParam>   )
+        (r_paren  : Token   <RightParen>)
+    });
 
     decl_node_enum!(enum Expr {
         Literal(literal),
@@ -463,6 +487,7 @@ mod grammar {
     decl_token_enum!(enum LiteralValue {
         Number(number),
         Str(string),
+        Null(null),
     });
 
     decl_node!(struct StructLiteral: STRUCT_LITERAL {
@@ -539,6 +564,9 @@ mod grammar {
     decl_token_enum!(enum PrefixOp {
         Plus(plus),
         Minus(minus),
+        Star(star),
+        Ampersand(ampersand),
+        Bang(bang),
     });
 
     decl_token_enum!(enum BinOp {
@@ -562,13 +590,15 @@ mod grammar {
     decl_token!(struct Minus      : T![-]);
     decl_token!(struct Star       : T![*]);
     decl_token!(struct Slash      : T![/]);
+    decl_token!(struct Ampersand  : T![&]);
+    decl_token!(struct Bang       : T![!]);
     decl_token!(struct Dot        : T![.]);
     decl_token!(struct Gt         : T![>]);
     decl_token!(struct Lt         : T![<]);
     decl_token!(struct Eq         : T![==]);
     decl_token!(struct Ne         : T![!=]);
-    decl_token!(struct Lte        : T![>=]);
-    decl_token!(struct Gte        : T![<=]);
+    decl_token!(struct Lte        : T![<=]);
+    decl_token!(struct Gte        : T![>=]);
     decl_token!(struct And        : T![&&]);
     decl_token!(struct Assign     : T![=]);
     decl_token!(struct ColonColon : T![::]);
@@ -576,10 +606,14 @@ mod grammar {
     decl_token!(struct Ident      : IDENT);
     decl_token!(struct Number     : NUMBER);
     decl_token!(struct Str        : STRING);
+    decl_token!(struct Null       : T![null]);
     decl_token!(struct LeftParen  : T!['(']);
     decl_token!(struct RightParen : T![')']);
     decl_token!(struct LeftCurly  : T!['{']);
     decl_token!(struct RightCurly : T!['}']);
+    decl_token!(struct LeftSquare : T!['[']);
+    decl_token!(struct RightSquare: T![']']);
+    decl_token!(struct Hash       : T![#]);
     decl_token!(struct DashArrow  : T![->]);
     decl_token!(struct Ellipsis   : T![...]);
     decl_token!(struct SemiColon  : T![;]);
@@ -748,7 +782,7 @@ fn bar() {}
                         NameRef:
                           BasicName:
                             Ident: n
-                        Gte: <=
+                        Lte: <=
                         Literal:
                           Number: 1
                       Block:
@@ -18,7 +18,7 @@ pub struct Builder {
     scope_stack: ScopeStack,
 
     string_literals: HashMap<String, ID>,
-    number_literals: HashMap<usize, ID>,
+    number_literals: HashMap<String, ID>,
 }
 
 struct ScopeStack {
@@ -72,7 +72,8 @@ impl Builder {
         }
     }
 
-    pub fn finish(self) -> Map {
+    pub fn finish(mut self) -> Map {
+        self.map.link_enclosing_functions();
         self.map
     }
 
@@ -243,10 +244,11 @@ impl Builder {
         if let ItemKind::Let(id) = kind {
             self.current_scope().lets.push(id);
         }
+        let span = ast.as_ref().map(|ast| ast.syntax().range());
         if let Some(ast) = ast {
             self.set_ast(id, ast);
         }
-        self.map.items.insert(id, Item { id, kind });
+        self.map.items.insert(id, Item { id, kind, span });
         id
     }
 
@@ -276,7 +278,8 @@ impl Builder {
         ast: Option<Arc<dyn ast::Node>>,
     ) -> ID {
         let id = self.new_node(Kind::Expr);
-        self.map.exprs.insert(id, Expr { id, kind });
+        let span = ast.as_ref().map(|ast| ast.syntax().range());
+        self.map.exprs.insert(id, Expr { id, kind, span });
         if let Some(ast) = ast {
             self.set_ast(id, ast);
         }
@@ -308,16 +311,16 @@ impl Builder {
                     id
                 }
             }
-            Literal::Number(n) => {
-                if let Some(&id) = self.number_literals.get(n) {
+            Literal::Number { source_repr, .. } => {
+                if let Some(&id) = self.number_literals.get(source_repr) {
                     return id;
                 } else {
                     let id = self.new_node(Kind::Literal);
-                    self.number_literals.insert(*n, id);
+                    self.number_literals.insert(source_repr.clone(), id);
                     id
                 }
             }
-            Literal::Struct(..) => self.new_node(Kind::Literal),
+            Literal::Null | Literal::Struct(..) => self.new_node(Kind::Literal),
         };
         self.map.literals.insert(id, literal);
         if let Some(ast) = ast {
@@ -367,3 +370,270 @@ impl Builder {
         self.current_function = Some(id);
     }
 }
+
+type Ast = Option<Arc<dyn ast::Node>>;
+
+impl Builder {
+    /// Start a function, which stays current until the next one is started.
+    pub fn function(
+        &mut self,
+        identifier: &str,
+        return_type: ID,
+        ast: Ast,
+    ) -> FnBuilder<'_> {
+        assert!(!identifier.is_empty(), "functions must be named");
+        let id = self.new_function(identifier, return_type, ast);
+        self.set_current_function(id);
+        FnBuilder { builder: self, id }
+    }
+
+    /// A new block nested in the current one, with its items added by `f`.
+    pub fn block(
+        &mut self,
+        kind: BlockKind,
+        label: Option<String>,
+        ast: Ast,
+        f: impl FnOnce(&mut BlockBuilder),
+    ) -> ID {
+        assert!(self.current_function.is_some(), "blocks need a function");
+        let id = self.in_new_scope(label, kind, |builder| {
+            f(&mut BlockBuilder { builder })
+        });
+        if let Some(ast) = ast {
+            self.set_ast(id, ast);
+        }
+        id
+    }
+
+    /// An expression, whose operands must already have been built.
+    pub fn expr(&mut self, ast: Ast) -> ExprBuilder<'_> {
+        ExprBuilder { builder: self, ast }
+    }
+}
+
+pub struct FnBuilder<'b> {
+    builder: &'b mut Builder,
+    id: ID,
+}
+
+impl FnBuilder<'_> {
+    pub fn param(&mut self, identifier: String, ty: ID, ast: Ast) -> ID {
+        assert!(!identifier.is_empty(), "parameters must be named");
+        self.builder.new_param(identifier, ty, ast)
+    }
+
+    pub fn var_args(&mut self) {
+        self.builder.current_function().is_var_args = true;
+    }
+
+    pub fn extern_(&mut self) {
+        self.builder.current_function().is_extern = true;
+    }
+
+    pub fn test(&mut self) {
+        self.builder.current_function().is_test = true;
+    }
+
+    pub fn body(&mut self, body: ID) {
+        assert_eq!(self.builder.map.block(&body).kind, BlockKind::Function);
+        let fn_ = self.builder.current_function();
+        assert!(
+            fn_.body.is_none(),
+            "`{}` already has a body",
+            fn_.identifier
+        );
+        fn_.body = Some(body);
+    }
+
+    pub fn finish(self) -> ID {
+        self.id
+    }
+}
+
+impl std::ops::Deref for FnBuilder<'_> {
+    type Target = Builder;
+    fn deref(&self) -> &Builder {
+        self.builder
+    }
+}
+
+impl std::ops::DerefMut for FnBuilder<'_> {
+    fn deref_mut(&mut self) -> &mut Builder {
+        self.builder
+    }
+}
+
+pub struct BlockBuilder<'b> {
+    builder: &'b mut Builder,
+}
+
+impl BlockBuilder<'_> {
+    pub fn let_(
+        &mut self,
+        name: String,
+        ty: Option<ID>,
+        expr: Option<ID>,
+        ast: Ast,
+    ) -> ID {
+        assert!(!name.is_empty(), "`let`s must be named");
+        ty.iter().for_each(|&ty| self.check_kind(ty, Kind::TypeRef));
+        expr.iter()
+            .for_each(|&expr| self.check_kind(expr, Kind::Expr));
+        self.builder.new_let_item(name, ty, expr, ast)
+    }
+
+    pub fn expr_item(&mut self, expr: ID, ast: Ast) -> ID {
+        self.check_kind(expr, Kind::Expr);
+        self.builder.new_item(ItemKind::Expr(expr), ast)
+    }
+
+    /// The value of the block, after all of its items.
+    pub fn return_expr(&mut self, expr: ID) {
+        self.check_kind(expr, Kind::Expr);
+        let scope = self.builder.current_scope();
+        assert!(scope.return_expr.is_none(), "block already has a value");
+        scope.return_expr = Some(expr);
+    }
+
+    fn check_kind(&self, id: ID, kind: Kind) {
+        assert_eq!(self.builder.map.kind(&id), kind);
+    }
+}
+
+impl std::ops::Deref for BlockBuilder<'_> {
+    type Target = Builder;
+    fn deref(&self) -> &Builder {
+        self.builder
+    }
+}
+
+impl std::ops::DerefMut for BlockBuilder<'_> {
+    fn deref_mut(&mut self) -> &mut Builder {
+        self.builder
+    }
+}
+
+pub struct ExprBuilder<'b> {
+    builder: &'b mut Builder,
+    ast: Ast,
+}
+
+impl ExprBuilder<'_> {
+    pub fn literal(self, lit: ID) -> ID {
+        self.check_kind(lit, Kind::Literal);
+        self.finish(ExprKind::Literal(lit))
+    }
+
+    pub fn name_ref(self, name: ID) -> ID {
+        self.check_name(name);
+        self.finish(ExprKind::NameRef { id: name })
+    }
+
+    pub fn prefix(self, kind: OpKind, operand: ID) -> ID {
+        self.op(OpFixity::Prefix, kind, vec![operand])
+    }
+
+    pub fn infix(self, kind: OpKind, lhs: ID, rhs: ID) -> ID {
+        self.op(OpFixity::Infix, kind, vec![lhs, rhs])
+    }
+
+    fn op(self, fixity: OpFixity, kind: OpKind, operands: Vec<ID>) -> ID {
+        let arity = match fixity {
+            OpFixity::Prefix | OpFixity::Postfix => 1,
+            OpFixity::Infix => 2,
+        };
+        assert_eq!(operands.len(), arity, "{fixity:?} {kind:?}");
+        operands
+            .iter()
+            .for_each(|&id| self.check_kind(id, Kind::Expr));
+        self.finish(ExprKind::Op(Op {
+            fixity,
+            kind,
+            operands,
+        }))
+    }
+
+    pub fn block(self, scope: ID) -> ID {
+        self.check_kind(scope, Kind::Block);
+        self.finish(ExprKind::Block { scope })
+    }
+
+    pub fn call(self, receiver: ID, operands: Vec<ID>) -> ID {
+        self.check_kind(receiver, Kind::Expr);
+        if let ExprKind::NameRef { id } = self.builder.map.expr(&receiver).kind
+        {
+            self.check_name(id);
+        }
+        operands
+            .iter()
+            .for_each(|&id| self.check_kind(id, Kind::Expr));
+        self.finish(ExprKind::Call { receiver, operands })
+    }
+
+    pub fn return_(self, expr: Option<ID>) -> ID {
+        expr.iter().for_each(|&id| self.check_kind(id, Kind::Expr));
+        self.finish(ExprKind::Return { expr })
+    }
+
+    pub fn index(self, receiver: ID, index: ID) -> ID {
+        self.check_kind(receiver, Kind::Expr);
+        self.check_kind(index, Kind::Expr);
+        self.finish(ExprKind::Index { receiver, index })
+    }
+
+    /// An `if`, or an `if`/`else` if there's a `right` block.
+    pub fn branch(self, condition: ID, left: ID, right: Option<ID>) -> ID {
+        self.check_kind(condition, Kind::Expr);
+        self.check_kind(left, Kind::Block);
+        right
+            .iter()
+            .for_each(|&id| self.check_kind(id, Kind::Block));
+        let kind = match right {
+            Some(_) => BranchKind::IfElse,
+            None => BranchKind::If,
+        };
+        self.finish(ExprKind::Branch {
+            condition,
+            kind,
+            left,
+            right,
+        })
+    }
+
+    pub fn loop_(self, kind: LoopKind, body: ID) -> ID {
+        assert_eq!(self.builder.map.block(&body).kind, BlockKind::Loop);
+        self.finish(ExprKind::Loop { kind, body })
+    }
+
+    pub fn break_(self, label: String) -> ID {
+        assert!(!label.is_empty(), "`break` needs a loop label");
+        self.finish(ExprKind::Break { label })
+    }
+
+    pub fn continue_(self, label: String) -> ID {
+        assert!(!label.is_empty(), "`continue` needs a loop label");
+        self.finish(ExprKind::Continue { label })
+    }
+
+    pub fn cast(self, val: ID, to: ID) -> ID {
+        self.check_kind(val, Kind::Expr);
+        self.check_kind(to, Kind::TypeRef);
+        self.finish(ExprKind::Cast { val, to })
+    }
+
+    fn check_kind(&self, id: ID, kind: Kind) {
+        assert_eq!(self.builder.map.kind(&id), kind);
+    }
+
+    fn check_name(&self, name: ID) {
+        let segments = &self.builder.map.name(&name).segments;
+        assert!(
+            !segments.is_empty() && segments.iter().all(|s| !s.is_empty()),
+            "names can't be empty"
+        );
+    }
+
+    fn finish(self, kind: ExprKind) -> ID {
+        self.builder.new_expr(kind, self.ast)
+    }
+}
@@ -0,0 +1,374 @@
+use crate::types::*;
+use std::collections::HashMap;
+
+/// A reference between BIR nodes that doesn't hold up, usually left behind
+/// by a bug in translation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityError {
+    /// `node` refers to `id`, which isn't in the map
+    Missing { node: ID, id: ID },
+    /// `node` refers to `id` as a node of kind `expected`, but it's `found`
+    WrongKind {
+        node: ID,
+        id: ID,
+        expected: Kind,
+        found: Kind,
+    },
+    /// `node` lists `id` as a child, but `id` records `found` as its parent
+    WrongParent { node: ID, id: ID, found: ID },
+    /// `id` is listed as a child by both `first` and `second`
+    Duplicate { id: ID, first: ID, second: ID },
+}
+
+impl std::fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing { node, id } => {
+                write!(f, "{node:?} refers to missing node {id:?}")
+            }
+            Self::WrongKind {
+                node,
+                id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{node:?} refers to {id:?} as a {expected:?}, but it's a \
+                 {found:?}"
+            ),
+            Self::WrongParent { node, id, found } => write!(
+                f,
+                "{node:?} lists {id:?} as a child, but its parent is \
+                 {found:?}"
+            ),
+            Self::Duplicate { id, first, second } => write!(
+                f,
+                "{id:?} is listed as a child by both {first:?} and {second:?}"
+            ),
+        }
+    }
+}
+
+impl Map {
+    /// Check that every ID stored in a node refers to a node that exists,
+    /// and that it has the kind the referring node expects. Each child node
+    /// must also be listed by exactly one parent, which is the parent it
+    /// records for itself.
+    pub fn check_structural_integrity(&self) -> Vec<IntegrityError> {
+        let mut ck = Checker {
+            map: self,
+            errors: Vec::new(),
+            parents: HashMap::new(),
+        };
+        if let Some(root) = self.root_module {
+            ck.expect(root, root, Kind::Module);
+        }
+        for m in self.modules.values() {
+            ck.expect_children(m.id, &m.functions, Kind::Function);
+            ck.expect_children(m.id, &m.typedefs, Kind::TypeDef);
+            ck.expect_children(m.id, &m.modules, Kind::Module);
+            ck.expect_children(m.id, &m.imports, Kind::Import);
+            ck.expect_all(m.id, &m.parent, Kind::Module);
+        }
+        for import in self.imports.values() {
+            ck.expect(import.id, import.parent, Kind::Module);
+        }
+        for ty in self.typerefs.values() {
+            match &ty.kind {
+                TypeRefKind::Void | TypeRefKind::Never => {}
+                TypeRefKind::Named { name } => {
+                    ck.expect(ty.id, *name, Kind::Name)
+                }
+                TypeRefKind::Pointer { pointee } => {
+                    ck.expect(ty.id, *pointee, Kind::TypeRef)
+                }
+                TypeRefKind::Function {
+                    return_ty, params, ..
+                } => {
+                    ck.expect(ty.id, *return_ty, Kind::TypeRef);
+                    ck.expect_all(ty.id, params, Kind::TypeRef);
+                }
+            }
+        }
+        for ty in self.typedefs.values() {
+            ck.expect(ty.id, ty.mod_, Kind::Module);
+            for member in &ty.members {
+                ck.expect(ty.id, member.ty, Kind::TypeRef);
+            }
+        }
+        for f in self.functions.values() {
+            ck.expect(f.id, f.mod_, Kind::Module);
+            ck.expect_children(f.id, &f.parameters, Kind::Parameter);
+            ck.expect_children(f.id, &f.body, Kind::Block);
+            ck.expect(f.id, f.return_type, Kind::TypeRef);
+        }
+        for param in self.params.values() {
+            ck.expect(param.id, param.function, Kind::Function);
+            ck.expect(param.id, param.ty, Kind::TypeRef);
+        }
+        for block in self.blocks.values() {
+            ck.expect_all(block.id, &block.parent, Kind::Block);
+            ck.expect(block.id, block.function, Kind::Function);
+            ck.expect_children(block.id, &block.lets, Kind::Let);
+            ck.expect_children(block.id, &block.items, Kind::Item);
+            ck.expect_all(block.id, &block.return_expr, Kind::Expr);
+        }
+        for item in self.items.values() {
+            match item.kind {
+                ItemKind::Let(id) => ck.expect(item.id, id, Kind::Let),
+                ItemKind::Expr(id) | ItemKind::MacroExpansion(id) => {
+                    ck.expect(item.id, id, Kind::Expr)
+                }
+            }
+        }
+        for let_ in self.lets.values() {
+            ck.expect_all(let_.id, &let_.ty, Kind::TypeRef);
+            ck.expect_all(let_.id, &let_.expr, Kind::Expr);
+        }
+        for expr in self.exprs.values() {
+            check_expr(&mut ck, expr);
+        }
+        ck.errors
+    }
+
+    /// Whether `id` is a node of kind `kind`, with its data in the map.
+    fn contains(&self, id: ID, kind: &Kind) -> bool {
+        match kind {
+            Kind::Module => self.modules.contains_key(&id),
+            Kind::Import => self.imports.contains_key(&id),
+            Kind::Function => self.functions.contains_key(&id),
+            Kind::Parameter => self.params.contains_key(&id),
+            Kind::Name => self.names.contains_key(&id),
+            Kind::TypeRef => self.typerefs.contains_key(&id),
+            Kind::TypeDef => self.typedefs.contains_key(&id),
+            Kind::Block => self.blocks.contains_key(&id),
+            Kind::Item => self.items.contains_key(&id),
+            Kind::Let => self.lets.contains_key(&id),
+            Kind::Expr => self.exprs.contains_key(&id),
+            Kind::Literal => self.literals.contains_key(&id),
+        }
+    }
+}
+
+fn check_expr(ck: &mut Checker, expr: &Expr) {
+    let map = ck.map;
+    let node = expr.id;
+    match &expr.kind {
+        ExprKind::Literal(id) => {
+            ck.expect(node, *id, Kind::Literal);
+            if map.contains(*id, &Kind::Literal) {
+                if let Literal::Struct(lit) = map.lit(id) {
+                    ck.expect(*id, lit.name, Kind::Name);
+                    ck.expect_all(*id, &lit.members, Kind::Expr);
+                }
+            }
+        }
+        ExprKind::NameRef { id } => ck.expect(node, *id, Kind::Name),
+        ExprKind::Cast { val, to } => {
+            ck.expect(node, *val, Kind::Expr);
+            ck.expect(node, *to, Kind::TypeRef);
+        }
+        ExprKind::Call { receiver, operands } => {
+            ck.expect(node, *receiver, Kind::Expr);
+            ck.expect_all(node, operands, Kind::Expr);
+        }
+        ExprKind::Index { receiver, index } => {
+            ck.expect(node, *receiver, Kind::Expr);
+            ck.expect(node, *index, Kind::Expr);
+        }
+        ExprKind::Op(op) => ck.expect_all(node, &op.operands, Kind::Expr),
+        ExprKind::Block { scope } => ck.expect(node, *scope, Kind::Block),
+        ExprKind::Return { expr } => ck.expect_all(node, expr, Kind::Expr),
+        ExprKind::Break { .. } | ExprKind::Continue { .. } => {}
+        ExprKind::Branch {
+            condition,
+            left,
+            right,
+            ..
+        } => {
+            ck.expect(node, *condition, Kind::Expr);
+            ck.expect(node, *left, Kind::Block);
+            ck.expect_all(node, right, Kind::Block);
+        }
+        ExprKind::Loop { body, .. } => ck.expect(node, *body, Kind::Block),
+        ExprKind::MacroCall { operands, .. } => {
+            ck.expect_all(node, operands, Kind::Expr)
+        }
+    }
+}
+
+struct Checker<'map> {
+    map: &'map Map,
+    errors: Vec<IntegrityError>,
+    /// The node that first listed each child
+    parents: HashMap<ID, ID>,
+}
+
+impl Checker<'_> {
+    /// Check that `node` refers to a node `id` of kind `expected`.
+    fn expect(&mut self, node: ID, id: ID, expected: Kind) {
+        let Some(found) = self.map.nodes.get(id.0) else {
+            self.errors.push(IntegrityError::Missing { node, id });
+            return;
+        };
+        if *found != expected {
+            self.errors.push(IntegrityError::WrongKind {
+                node,
+                id,
+                expected,
+                found: found.clone(),
+            });
+        } else if !self.map.contains(id, found) {
+            self.errors.push(IntegrityError::Missing { node, id });
+        }
+    }
+
+    /// Like `expect_all`, where `node` is the parent of each of `ids`.
+    fn expect_children<'a>(
+        &mut self,
+        node: ID,
+        ids: impl IntoIterator<Item = &'a ID>,
+        expected: Kind,
+    ) {
+        for &id in ids {
+            self.expect(node, id, expected.clone());
+            if let Some(first) = self.parents.insert(id, node) {
+                self.errors.push(IntegrityError::Duplicate {
+                    id,
+                    first,
+                    second: node,
+                });
+            }
+            match self.recorded_parent(id) {
+                Some(found) if found != node => self
+                    .errors
+                    .push(IntegrityError::WrongParent { node, id, found }),
+                _ => {}
+            }
+        }
+    }
+
+    /// The parent `id` records for itself, if its kind records one. A
+    /// function's body block has no parent block, so it records the
+    /// function.
+    fn recorded_parent(&self, id: ID) -> Option<ID> {
+        let map = self.map;
+        match map.nodes.get(id.0)? {
+            Kind::Module => map.modules.get(&id)?.parent,
+            Kind::Import => Some(map.imports.get(&id)?.parent),
+            Kind::Function => Some(map.functions.get(&id)?.mod_),
+            Kind::TypeDef => Some(map.typedefs.get(&id)?.mod_),
+            Kind::Parameter => Some(map.params.get(&id)?.function),
+            Kind::Block => {
+                let block = map.blocks.get(&id)?;
+                Some(block.parent.unwrap_or(block.function))
+            }
+            _ => None,
+        }
+    }
+
+    fn expect_all<'a>(
+        &mut self,
+        node: ID,
+        ids: impl IntoIterator<Item = &'a ID>,
+        expected: Kind,
+    ) {
+        for id in ids {
+            self.expect(node, *id, expected.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use ast::Node;
+    use std::sync::Arc;
+
+    pub(crate) fn translate(text: &str) -> Map {
+        struct NoImports;
+        impl crate::translate::AstBuilder for NoImports {
+            type Error = ();
+            fn build(&mut self, _: &str) -> Result<Arc<ast::Module>, ()> {
+                Err(())
+            }
+        }
+        let cst = parser::parse_str(text);
+        assert!(cst.errors.is_empty());
+        let ast = ast::Module::cast(cst.root).unwrap();
+        let map = crate::translate::ast(&ast, &mut NoImports);
+        assert_eq!(map.check_structural_integrity(), []);
+        map
+    }
+
+    pub(crate) fn function(map: &Map, ident: &str) -> Function {
+        map.functions
+            .values()
+            .find(|f| f.identifier == ident)
+            .unwrap()
+            .clone()
+    }
+
+    const TEXT: &str = "
+        fn f(x: i32) -> i32 { let y = x; { y } }
+        fn g(x: i32) {}
+    ";
+
+    #[test]
+    fn translation_is_well_formed() {
+        assert_eq!(translate(TEXT).check_structural_integrity(), []);
+    }
+
+    #[test]
+    fn dangling_child() {
+        let mut map = translate(TEXT);
+        let f = function(&map, "f");
+        let body = f.body.unwrap();
+        map.blocks.remove(&body);
+        // The block nested in the body refers to it too
+        let errors = map.check_structural_integrity();
+        assert!(
+            errors.contains(&IntegrityError::Missing {
+                node: f.id,
+                id: body
+            }),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn wrong_parent() {
+        let mut map = translate(TEXT);
+        let (f, g) = (function(&map, "f"), function(&map, "g"));
+        let x = f.parameters[0];
+        map.params.get_mut(&x).unwrap().function = g.id;
+        assert_eq!(
+            map.check_structural_integrity(),
+            [IntegrityError::WrongParent {
+                node: f.id,
+                id: x,
+                found: g.id
+            }]
+        );
+    }
+
+    #[test]
+    fn duplicate_child() {
+        let mut map = translate(TEXT);
+        let (f, g) = (function(&map, "f"), function(&map, "g"));
+        let x = f.parameters[0];
+        map.functions.get_mut(&g.id).unwrap().parameters.push(x);
+        let errors = map.check_structural_integrity();
+        let duplicate = |first, second| IntegrityError::Duplicate {
+            id: x,
+            first,
+            second,
+        };
+        // Whichever function is checked second lists `x` as a duplicate
+        assert!(
+            errors.contains(&duplicate(f.id, g.id))
+                || errors.contains(&duplicate(g.id, f.id)),
+            "{errors:?}"
+        );
+    }
+}
@@ -9,5 +9,8 @@ pub use visit::Visitor;
 
 pub mod translate;
 
+pub mod integrity;
+pub use integrity::IntegrityError;
+
 mod print;
-pub use print::print;
+pub use print::{diff_against, print};
@@ -5,22 +5,70 @@ use std::fmt::Write;
 const DEBUG_IDS: bool = false;
 
 pub fn print<'bir>(map: &'bir Map) {
-    let mut p = Printer {
-        map,
-        buf: String::new(),
-        indent: 0,
-    };
+    let mut p = Printer::new(map);
     p.visit_root();
     print!("{}", p.buf);
 }
 
+impl Map {
+    /// Print a single function (its parameters, return type and body), for
+    /// debugging without wading through a full module dump.
+    pub fn pretty_print_function(&self, fn_id: ID) -> String {
+        let mut p = Printer::new(self);
+        p.print_function(self.fn_(&fn_id));
+        p.buf
+    }
+
+    /// Every type and function signature in the modules that weren't
+    /// imported, without function bodies.
+    pub fn outline(&self) -> String {
+        let mut p = Printer::new(self);
+        p.signatures_only = true;
+        p.visit_root();
+        p.buf
+    }
+
+    /// A unified diff between the printed forms of `a` and `b`, which is
+    /// empty if they print the same.
+    pub fn pretty_diff(a: &Map, b: &Map) -> String {
+        diff_against(&a.to_pretty_string(), b)
+    }
+
+    fn to_pretty_string(&self) -> String {
+        let mut p = Printer::new(self);
+        p.visit_root();
+        p.buf
+    }
+}
+
+/// A unified diff from `old`, a map as printed by [`print`], to `new`, which
+/// is empty if `new` still prints the same.
+pub fn diff_against(old: &str, new: &Map) -> String {
+    let new = new.to_pretty_string();
+    let diff = similar::TextDiff::from_lines(old, &new);
+    if diff.ratio() == 1.0 {
+        return String::new();
+    }
+    diff.unified_diff().header("old", "new").to_string()
+}
+
 pub struct Printer<'bir> {
     map: &'bir Map,
     buf: String,
     indent: usize,
+    signatures_only: bool,
 }
 
-impl Printer<'_> {
+impl<'bir> Printer<'bir> {
+    pub fn new(map: &'bir Map) -> Self {
+        Self {
+            map,
+            buf: String::new(),
+            indent: 0,
+            signatures_only: false,
+        }
+    }
+
     pub fn indent_up(&mut self) -> usize {
         self.indent += 2;
         self.indent
@@ -40,10 +88,13 @@ impl Printer<'_> {
 
 impl std::fmt::Write for Printer<'_> {
     fn write_str(&mut self, s: &str) -> std::fmt::Result {
-        if self.buf.ends_with("\n") {
-            self.buf.push_str(&" ".repeat(self.indent));
+        // `s` can be several lines, e.g. a function printed on its own
+        for line in s.split_inclusive('\n') {
+            if self.buf.ends_with("\n") {
+                self.buf.push_str(&" ".repeat(self.indent));
+            }
+            self.buf.push_str(line);
         }
-        self.buf.push_str(s);
         Ok(())
     }
 }
@@ -57,6 +108,9 @@ impl<'bir> Visitor<'bir> for Printer<'bir> {
     }
 
     fn visit_module(&mut self, mod_: &Module) {
+        if self.signatures_only && mod_.imported {
+            return;
+        }
         if DEBUG_IDS {
             w!(self, "{:?} ", mod_.id);
         }
@@ -79,6 +133,9 @@ impl<'bir> Visitor<'bir> for Printer<'bir> {
     }
 
     fn visit_import(&mut self, import: &Import) {
+        if self.signatures_only {
+            return;
+        }
         if DEBUG_IDS {
             w!(self, "{:?} ", import.id);
         }
@@ -100,31 +157,13 @@ impl<'bir> Visitor<'bir> for Printer<'bir> {
     }
 
     fn visit_function(&mut self, fn_: &Function) {
-        if DEBUG_IDS {
-            w!(self, "{:?} ", fn_.id);
-        }
-        w!(self, "fn {}", fn_.identifier);
-        w!(self, "(");
-        let ls = utils::ListSeparator::comma_space();
-        for param in fn_.parameters(self.map) {
-            w!(self, "{ls}");
-            self.visit_param(param);
-        }
-        if fn_.is_var_args {
-            w!(self, ", ...");
-        }
-        w!(self, ") -> ");
-        self.visit_typeref(fn_.return_type(self.map));
-        if fn_.is_extern {
-            w!(self, " extern");
-        }
-        if let Some(body) = fn_.body(self.map) {
-            w!(self, " ");
-            self.visit_block(body);
+        if self.signatures_only {
+            self.print_signature(fn_);
+            wln!(self, ";");
         } else {
-            w!(self, ";");
+            let function = self.map.pretty_print_function(fn_.id);
+            w!(self, "{function}");
         }
-        wln!(self);
     }
 
     fn visit_param(&mut self, param: &Parameter) {
@@ -144,6 +183,9 @@ impl<'bir> Visitor<'bir> for Printer<'bir> {
             Void => {
                 w!(self, "void");
             }
+            Never => {
+                w!(self, "!");
+            }
             Named { name } => {
                 self.visit_name(self.map.name(name));
             }
@@ -151,6 +193,23 @@ impl<'bir> Visitor<'bir> for Printer<'bir> {
                 w!(self, "*");
                 self.visit_typeref(self.map.typeref(&pointee));
             }
+            Function {
+                return_ty,
+                params,
+                is_var_args,
+            } => {
+                w!(self, "fn(");
+                let ls = utils::ListSeparator::comma_space();
+                for param in params {
+                    w!(self, "{ls}");
+                    self.visit_typeref(self.map.typeref(param));
+                }
+                if *is_var_args {
+                    w!(self, "{ls}...");
+                }
+                w!(self, ") -> ");
+                self.visit_typeref(self.map.typeref(return_ty));
+            }
         };
     }
 
@@ -162,7 +221,7 @@ impl<'bir> Visitor<'bir> for Printer<'bir> {
             w!(self, "['{label}]: ");
         }
         w!(self, "{{");
-        if scope.items.is_empty() && scope.return_expr.is_none() {
+        if scope.is_empty() {
             wln!(self, "}}");
         } else {
             self.indented(|this| {
@@ -188,7 +247,7 @@ impl<'bir> Visitor<'bir> for Printer<'bir> {
             ItemKind::Let(id) => {
                 self.visit_let(self.map.let_(id));
             }
-            ItemKind::Expr(id) => {
+            ItemKind::Expr(id) | ItemKind::MacroExpansion(id) => {
                 self.visit_expr(self.map.expr(id));
             }
         }
@@ -220,8 +279,11 @@ impl<'bir> Visitor<'bir> for Printer<'bir> {
                     w!(self, "{:?} ", id);
                 }
                 match self.map.lit(id) {
-                    Literal::Number(n) => w!(self, "{n}"),
-                    Literal::Str(s) => w!(self, "{s:?}"),
+                    Literal::Number { source_repr, .. } => {
+                        w!(self, "{source_repr}")
+                    }
+                    Literal::Str(s) => w!(self, "\"{}\"", utils::escape(s)),
+                    Literal::Null => w!(self, "null"),
                     Literal::Struct(lit) => {
                         self.visit_name(self.map.name(&lit.name));
                         w!(self, "{{}}")
@@ -298,6 +360,15 @@ impl<'bir> Visitor<'bir> for Printer<'bir> {
                 w!(self, "loop ");
                 self.visit_block(self.map.block(body));
             }
+            ExprKind::MacroCall { name, operands } => {
+                w!(self, "{name}!(");
+                let ls = utils::ListSeparator::comma_space();
+                for arg in operands {
+                    w!(self, "{ls}");
+                    self.visit_expr(self.map.expr(arg));
+                }
+                w!(self, ")");
+            }
         };
         w!(self, ")");
     }
@@ -308,6 +379,42 @@ impl<'bir> Visitor<'bir> for Printer<'bir> {
 }
 
 impl Printer<'_> {
+    fn print_function(&mut self, fn_: &Function) {
+        self.print_signature(fn_);
+        match fn_.body(self.map) {
+            Some(body) => {
+                w!(self, " ");
+                self.visit_block(body);
+            }
+            None => w!(self, ";"),
+        }
+        wln!(self);
+    }
+
+    fn print_signature(&mut self, fn_: &Function) {
+        if DEBUG_IDS {
+            w!(self, "{:?} ", fn_.id);
+        }
+        if fn_.is_test {
+            w!(self, "#[test] ");
+        }
+        w!(self, "fn {}", fn_.identifier);
+        w!(self, "(");
+        let ls = utils::ListSeparator::comma_space();
+        for param in fn_.parameters(self.map) {
+            w!(self, "{ls}");
+            self.visit_param(param);
+        }
+        if fn_.is_var_args {
+            w!(self, ", ...");
+        }
+        w!(self, ") -> ");
+        self.visit_typeref(fn_.return_type(self.map));
+        if fn_.is_extern {
+            w!(self, " extern");
+        }
+    }
+
     fn visit_op(&mut self, op: &Op) {
         match (&op.fixity, &op.kind) {
             (OpFixity::Infix, OpKind::Plus) => {
@@ -394,7 +501,49 @@ impl Printer<'_> {
                 w!(self, " != ");
                 self.visit_expr(rhs);
             }
+            (OpFixity::Prefix, kind) => {
+                let operand = self.map.expr(&op.operands[0]);
+                let symbol = match kind {
+                    OpKind::Plus => "+",
+                    OpKind::Minus => "-",
+                    OpKind::Deref => "*",
+                    OpKind::AddressOf => "&",
+                    OpKind::Not => "!",
+                    _ => unreachable!(),
+                };
+                w!(self, "{symbol}");
+                self.visit_expr(operand);
+            }
             _ => unreachable!(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::integrity::tests::{function, translate};
+
+    #[test]
+    fn module_prints_each_function_indented() {
+        let map = translate(
+            "mod m { fn f(x: i32) -> i32 { let y = x; y } } fn g() {}",
+        );
+        let f = function(&map, "f");
+        assert_eq!(
+            map.pretty_print_function(f.id),
+            "fn f(x: i32) -> i32 {\n  let y = (x);\n  (y)\n}\n"
+        );
+        assert_eq!(
+            map.to_pretty_string(),
+            "mod m {
+  fn f(x: i32) -> i32 {
+    let y = (x);
+    (y)
+  }
+}
+fn g() -> void {}
+
+"
+        );
+    }
+}
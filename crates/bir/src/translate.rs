@@ -12,6 +12,12 @@ pub trait AstBuilder {
         &mut self,
         module_name: &str,
     ) -> Result<Arc<ast::Module>, Self::Error>;
+
+    /// A module whose items are merged into the root module, as though it
+    /// were imported by it.
+    fn prelude(&mut self) -> Option<Arc<ast::Module>> {
+        None
+    }
 }
 
 struct AstCacher<'builder, Builder: AstBuilder> {
@@ -102,7 +108,16 @@ fn build_module_tree<B: AstBuilder>(
         }
         module_inner(builder, &ast);
 
-        for mod_ in ast.inner_mods() {
+        let prelude = match parent {
+            None => ast_cacher.ast_builder.prelude(),
+            Some(_) => None,
+        };
+        if let Some(prelude) = &prelude {
+            module_inner(builder, prelude);
+        }
+
+        let asts = std::iter::once(&ast).chain(prelude.as_ref());
+        for mod_ in asts.clone().flat_map(|ast| ast.inner_mods()) {
             worklist.push(WorkItem {
                 parent: Some(module),
                 ast: mod_.clone(),
@@ -111,12 +126,14 @@ fn build_module_tree<B: AstBuilder>(
             });
         }
 
-        for import in ast.imports() {
-            let ident = import.ident().unwrap();
+        for import in asts.flat_map(|ast| ast.imports()) {
+            let name = import.name_ref().unwrap().text();
+            let id = builder.new_import(name.clone());
+            builder.set_ast(id, import.clone());
             worklist.push(WorkItem {
                 parent: Some(module),
-                ast: ast_cacher.get(ident.text()).unwrap(),
-                name: Some(ident.text().to_string()),
+                ast: ast_cacher.get(&name).unwrap(),
+                name: Some(name),
                 imported: true,
             });
         }
@@ -137,30 +154,39 @@ fn module_inner(builder: &mut Builder, mod_: &Arc<ast::Module>) {
             builder.new_typeref(TypeRefKind::Void, None)
         };
 
-        let fn_id =
-            builder.new_function(&identifier, return_type, Some(fn_.clone()));
-        builder.set_current_function(fn_id);
+        let mut fn_builder =
+            builder.function(&identifier, return_type, Some(fn_.clone()));
 
         for param in fn_.param_list().unwrap().params() {
             match param.as_ref() {
                 ast::Param::NamedParam(param) => {
                     let name = param.name().unwrap().text();
-                    let ty = typeref_(builder, &param.type_().unwrap());
-                    builder.new_param(name, ty, Some(param.clone()));
-                }
-                ast::Param::VaParam(_) => {
-                    builder.current_function().is_var_args = true;
+                    let ty = typeref_(&mut fn_builder, &param.type_().unwrap());
+                    fn_builder.param(name, ty, Some(param.clone()));
                 }
+                ast::Param::VaParam(_) => fn_builder.var_args(),
             }
         }
 
         if fn_.extern_().is_some() {
-            builder.current_function().is_extern = true;
+            fn_builder.extern_();
+        }
+
+        // TODO: report unknown attributes
+        for attr in fn_.attrs() {
+            match attr.name().map(|name| name.text()).as_deref() {
+                Some("test") => fn_builder.test(),
+                // Shorthand for an `extern` declaration; the C calling
+                // convention is what external functions get anyway.
+                Some("extern_c") => fn_builder.extern_(),
+                _ => {}
+            }
         }
 
         if let Some(body) = fn_.block() {
-            builder.current_function().body =
-                Some(block_(builder, BlockKind::Function, None, &body));
+            let body =
+                block_(&mut fn_builder, BlockKind::Function, None, &body);
+            fn_builder.body(body);
         }
     }
 }
@@ -213,6 +239,24 @@ fn typeref_(builder: &mut Builder, ty: &Arc<ast::Type>) -> ID {
         ast::Type::PointerType(ty) => TypeRefKind::Pointer {
             pointee: typeref_(builder, &ty.pointee().unwrap()),
         },
+        ast::Type::NeverType(_) => TypeRefKind::Never,
+        ast::Type::FnType(ty) => {
+            let param_types = ty.param_types().unwrap();
+            let params = param_types
+                .types()
+                .map(|param| typeref_(builder, &param))
+                .collect();
+            let return_ty = if let Some(ty) = ty.return_ty() {
+                typeref_(builder, &ty)
+            } else {
+                builder.new_typeref(TypeRefKind::Void, None)
+            };
+            TypeRefKind::Function {
+                return_ty,
+                params,
+                is_var_args: param_types.va_param().is_some(),
+            }
+        }
     };
     builder.new_typeref(kind, Some(ty.clone()))
 }
@@ -223,19 +267,18 @@ fn block_(
     label: Option<String>,
     block: &Arc<ast::Block>,
 ) -> ID {
-    let id = builder.in_new_scope(label, kind, |builder| {
+    builder.block(kind, label, Some(block.clone()), |builder| {
         for item in block.items() {
             item_(builder, &item);
         }
         if let Some(expr) = block.expr() {
-            builder.current_scope().return_expr = Some(expr_(builder, &expr));
+            let expr = expr_(builder, &expr);
+            builder.return_expr(expr);
         }
-    });
-    builder.set_ast(id, block.clone());
-    id
+    })
 }
 
-fn item_(builder: &mut Builder, item: &Arc<ast::Item>) -> ID {
+fn item_(builder: &mut BlockBuilder, item: &Arc<ast::Item>) -> ID {
     match item.as_ref() {
         ast::Item::Let(item) => let_(builder, item),
         ast::Item::ExprItem(expr) => expr_item(builder, expr),
@@ -243,77 +286,97 @@ fn item_(builder: &mut Builder, item: &Arc<ast::Item>) -> ID {
     }
 }
 
-fn let_(builder: &mut Builder, item: &Arc<ast::Let>) -> ID {
+fn let_(builder: &mut BlockBuilder, item: &Arc<ast::Let>) -> ID {
     let name = item.name().unwrap().text().to_string();
     let ty = item.type_().map(|ty| typeref_(builder, &ty));
     let expr = item.expr().map(|ex| expr_(builder, &ex));
-    builder.new_let_item(name, ty, expr, Some(item.clone()))
+    builder.let_(name, ty, expr, Some(item.clone()))
 }
 
-fn expr_item(builder: &mut Builder, expr: &Arc<ast::ExprItem>) -> ID {
+fn expr_item(builder: &mut BlockBuilder, expr: &Arc<ast::ExprItem>) -> ID {
     let id = expr_(builder, &expr.expr().unwrap());
-    builder.new_item(ItemKind::Expr(id), Some(expr.clone()))
+    builder.expr_item(id, Some(expr.clone()))
 }
 
 fn expr_(builder: &mut Builder, expr: &Arc<ast::Expr>) -> ID {
-    let kind = match expr.as_ref() {
-        ast::Expr::Group(expr) => return group_expr(builder, &expr),
-        ast::Expr::Literal(lit) => literal_expr(builder, &lit),
-        ast::Expr::StructLiteral(lit) => struct_literal_expr(builder, &lit),
-        ast::Expr::NameRef(name) => name_ref(builder, &name),
-        ast::Expr::PrefixExpr(expr) => prefix_expr(builder, &expr),
-        ast::Expr::BinExpr(expr) => binary_expr(builder, &expr),
-        ast::Expr::Block(expr) => block_expr(builder, &expr),
-        ast::Expr::CallExpr(expr) => call_expr(builder, &expr),
-        ast::Expr::Return(expr) => return_expr(builder, &expr),
-        ast::Expr::IndexExpr(expr) => index_expr(builder, &expr),
-        ast::Expr::IfExpr(expr) => if_expr(builder, &expr),
-        ast::Expr::LoopExpr(expr) => loop_expr(builder, &expr),
-        ast::Expr::WhileExpr(expr) => while_expr(builder, &expr),
-        ast::Expr::Break(expr) => break_expr(builder, &expr),
-        ast::Expr::Continue(expr) => continue_expr(builder, &expr),
-        ast::Expr::Cast(expr) => cast_expr(builder, &expr),
-    };
-    builder.new_expr(kind, Some(expr.clone()))
+    let node = expr.clone();
+    match expr.as_ref() {
+        ast::Expr::Group(expr) => group_expr(builder, &expr),
+        ast::Expr::Literal(lit) => literal_expr(builder, node, &lit),
+        ast::Expr::StructLiteral(lit) => {
+            struct_literal_expr(builder, node, &lit)
+        }
+        ast::Expr::NameRef(name) => name_ref(builder, node, &name),
+        ast::Expr::PrefixExpr(expr) => prefix_expr(builder, node, &expr),
+        ast::Expr::BinExpr(expr) => binary_expr(builder, node, &expr),
+        ast::Expr::Block(expr) => block_expr(builder, node, &expr),
+        ast::Expr::CallExpr(expr) => call_expr(builder, node, &expr),
+        ast::Expr::Return(expr) => return_expr(builder, node, &expr),
+        ast::Expr::IndexExpr(expr) => index_expr(builder, node, &expr),
+        ast::Expr::IfExpr(expr) => if_expr(builder, node, &expr),
+        ast::Expr::LoopExpr(expr) => loop_expr(builder, node, &expr),
+        ast::Expr::WhileExpr(expr) => while_expr(builder, node, &expr),
+        ast::Expr::Break(_) => break_expr(builder, node),
+        ast::Expr::Continue(_) => continue_expr(builder, node),
+        ast::Expr::Cast(expr) => cast_expr(builder, node, &expr),
+    }
 }
 
-fn literal_expr(builder: &mut Builder, lit: &Arc<ast::Literal>) -> ExprKind {
-    ExprKind::Literal(literal(builder, lit))
+fn literal_expr(
+    builder: &mut Builder,
+    node: Arc<ast::Expr>,
+    lit: &Arc<ast::Literal>,
+) -> ID {
+    let lit = literal(builder, lit);
+    builder.expr(Some(node)).literal(lit)
 }
 
 fn struct_literal_expr(
     builder: &mut Builder,
+    node: Arc<ast::Expr>,
     struct_: &Arc<ast::StructLiteral>,
-) -> ExprKind {
+) -> ID {
     let name = name(builder, &struct_.name().unwrap());
+    // Only `S {}` parses, so there are never any members to translate
     let lit = Literal::Struct(StructLiteral {
         name,
         members: Vec::new(),
     });
-    ExprKind::Literal(builder.new_literal(lit, Some(struct_.clone())))
+    let lit = builder.new_literal(lit, Some(struct_.clone()));
+    builder.expr(Some(node)).literal(lit)
 }
 
-fn name_ref(builder: &mut Builder, ref_: &Arc<ast::NameRef>) -> ExprKind {
-    ExprKind::NameRef {
-        id: name(builder, &ref_.name().unwrap()),
-    }
+fn name_ref(
+    builder: &mut Builder,
+    node: Arc<ast::Expr>,
+    ref_: &Arc<ast::NameRef>,
+) -> ID {
+    let name = name(builder, &ref_.name().unwrap());
+    builder.expr(Some(node)).name_ref(name)
 }
 
-fn prefix_expr(builder: &mut Builder, expr: &Arc<ast::PrefixExpr>) -> ExprKind {
+fn prefix_expr(
+    builder: &mut Builder,
+    node: Arc<ast::Expr>,
+    expr: &Arc<ast::PrefixExpr>,
+) -> ID {
     let kind = match expr.op().unwrap().text() {
         "+" => OpKind::Plus,
         "-" => OpKind::Minus,
+        "*" => OpKind::Deref,
+        "&" => OpKind::AddressOf,
+        "!" => OpKind::Not,
         _ => unreachable!(),
     };
     let operand = expr_(builder, &expr.operand().unwrap());
-    ExprKind::Op(Op {
-        fixity: OpFixity::Prefix,
-        kind,
-        operands: vec![operand],
-    })
+    builder.expr(Some(node)).prefix(kind, operand)
 }
 
-fn binary_expr(builder: &mut Builder, expr: &Arc<ast::BinExpr>) -> ExprKind {
+fn binary_expr(
+    builder: &mut Builder,
+    node: Arc<ast::Expr>,
+    expr: &Arc<ast::BinExpr>,
+) -> ID {
     let kind = match expr.op().unwrap().text() {
         "+" => OpKind::Plus,
         "-" => OpKind::Minus,
@@ -331,11 +394,7 @@ fn binary_expr(builder: &mut Builder, expr: &Arc<ast::BinExpr>) -> ExprKind {
     };
     let lhs = expr_(builder, &expr.lhs().unwrap());
     let rhs = expr_(builder, &expr.rhs().unwrap());
-    ExprKind::Op(Op {
-        fixity: OpFixity::Infix,
-        kind,
-        operands: vec![lhs, rhs],
-    })
+    builder.expr(Some(node)).infix(kind, lhs, rhs)
 }
 
 fn group_expr(builder: &mut Builder, group: &Arc<ast::Group>) -> ID {
@@ -343,132 +402,148 @@ fn group_expr(builder: &mut Builder, group: &Arc<ast::Group>) -> ID {
     expr_(builder, &group.inner().unwrap())
 }
 
-fn block_expr(builder: &mut Builder, block: &Arc<ast::Block>) -> ExprKind {
-    ExprKind::Block {
-        scope: block_(builder, BlockKind::Expr, None, block),
-    }
+fn block_expr(
+    builder: &mut Builder,
+    node: Arc<ast::Expr>,
+    block: &Arc<ast::Block>,
+) -> ID {
+    let scope = block_(builder, BlockKind::Expr, None, block);
+    builder.expr(Some(node)).block(scope)
 }
 
-fn call_expr(builder: &mut Builder, expr: &Arc<ast::CallExpr>) -> ExprKind {
+fn call_expr(
+    builder: &mut Builder,
+    node: Arc<ast::Expr>,
+    expr: &Arc<ast::CallExpr>,
+) -> ID {
     let receiver = expr_(builder, &expr.receiver().unwrap());
     let mut operands = Vec::new();
     for arg in expr.arguments().by_ref() {
         operands.push(expr_(builder, &arg));
     }
-    ExprKind::Call { receiver, operands }
+    builder.expr(Some(node)).call(receiver, operands)
 }
 
-fn return_expr(builder: &mut Builder, ret: &Arc<ast::Return>) -> ExprKind {
-    ExprKind::Return {
-        expr: ret.expr().map(|e| expr_(builder, &e)),
-    }
+fn return_expr(
+    builder: &mut Builder,
+    node: Arc<ast::Expr>,
+    ret: &Arc<ast::Return>,
+) -> ID {
+    let expr = ret.expr().map(|e| expr_(builder, &e));
+    builder.expr(Some(node)).return_(expr)
 }
 
-fn index_expr(builder: &mut Builder, expr: &Arc<ast::IndexExpr>) -> ExprKind {
-    ExprKind::Index {
-        receiver: expr_(builder, &expr.receiver().unwrap()),
-        index: expr_(builder, &expr.index().unwrap()),
-    }
+fn index_expr(
+    builder: &mut Builder,
+    node: Arc<ast::Expr>,
+    expr: &Arc<ast::IndexExpr>,
+) -> ID {
+    let receiver = expr_(builder, &expr.receiver().unwrap());
+    let index = expr_(builder, &expr.index().unwrap());
+    builder.expr(Some(node)).index(receiver, index)
 }
 
-fn if_expr(builder: &mut Builder, expr: &Arc<ast::IfExpr>) -> ExprKind {
+fn if_expr(
+    builder: &mut Builder,
+    node: Arc<ast::Expr>,
+    expr: &Arc<ast::IfExpr>,
+) -> ID {
     let condition = expr_(builder, &expr.condition().unwrap());
     let left = block_(builder, BlockKind::Expr, None, &expr.then().unwrap());
-    let (kind, right) = if let Some(alt) = expr.alternate() {
-        let right = block_(builder, BlockKind::Expr, None, &alt);
-        (BranchKind::IfElse, Some(right))
-    } else {
-        (BranchKind::If, None)
-    };
-    ExprKind::Branch {
-        condition,
-        kind,
-        left,
-        right,
-    }
+    let right = expr
+        .alternate()
+        .map(|alt| block_(builder, BlockKind::Expr, None, &alt));
+    builder.expr(Some(node)).branch(condition, left, right)
 }
 
-fn loop_expr(builder: &mut Builder, loop_: &Arc<ast::LoopExpr>) -> ExprKind {
+fn loop_expr(
+    builder: &mut Builder,
+    node: Arc<ast::Expr>,
+    loop_: &Arc<ast::LoopExpr>,
+) -> ID {
     // this is an extremely suspect method to obtain semi-unique loop labels
     let id = loop_.deref() as *const ast::LoopExpr as usize;
     let label = format!("loop{}", (id & 0xFF0000) >> 16);
-    ExprKind::Loop {
-        kind: LoopKind::Loop,
-        body: block_(
-            builder,
-            BlockKind::Loop,
-            Some(label),
-            &loop_.body().unwrap(),
-        ),
-    }
+    let body = block_(
+        builder,
+        BlockKind::Loop,
+        Some(label),
+        &loop_.body().unwrap(),
+    );
+    builder.expr(Some(node)).loop_(LoopKind::Loop, body)
 }
 
-fn while_expr(builder: &mut Builder, while_: &Arc<ast::WhileExpr>) -> ExprKind {
-    ExprKind::Loop {
-        kind: LoopKind::While,
-        body: builder.in_new_scope(
-            Some("while.latch".to_string()),
+fn while_expr(
+    builder: &mut Builder,
+    node: Arc<ast::Expr>,
+    while_: &Arc<ast::WhileExpr>,
+) -> ID {
+    let latch = Some("while.latch".to_string());
+    let body = builder.block(BlockKind::Loop, latch, None, |builder| {
+        let condition = expr_(builder, &while_.condition().unwrap());
+        let body = block_(
+            builder,
             BlockKind::Loop,
-            |builder| {
-                let condition = expr_(builder, &while_.condition().unwrap());
-                let body = block_(
-                    builder,
-                    BlockKind::Loop,
-                    Some("while.body".to_string()),
-                    &while_.body().unwrap(),
-                );
-                let exit_block =
-                    builder.in_new_scope(None, BlockKind::Expr, |builder| {
-                        builder.new_expr_item(
-                            ExprKind::Break {
-                                label: builder.last_loop_label(),
-                            },
-                            None,
-                        );
-                    });
-                builder.new_expr_item(
-                    ExprKind::Branch {
-                        condition,
-                        kind: BranchKind::IfElse,
-                        left: body,
-                        right: Some(exit_block),
-                    },
-                    Some(while_.clone()),
-                );
-            },
-        ),
-    }
+            Some("while.body".to_string()),
+            &while_.body().unwrap(),
+        );
+        let exit_block =
+            builder.block(BlockKind::Expr, None, None, |builder| {
+                let label = builder.last_loop_label();
+                let break_ = builder.expr(None).break_(label);
+                builder.expr_item(break_, None);
+            });
+        let branch = builder.expr(Some(while_.clone())).branch(
+            condition,
+            body,
+            Some(exit_block),
+        );
+        builder.expr_item(branch, Some(while_.clone()));
+    });
+    builder.expr(Some(node)).loop_(LoopKind::While, body)
 }
 
-fn break_expr(builder: &mut Builder, _: &Arc<ast::Break>) -> ExprKind {
-    ExprKind::Break {
-        label: builder.last_loop_label(),
-    }
+fn break_expr(builder: &mut Builder, node: Arc<ast::Expr>) -> ID {
+    let label = builder.last_loop_label();
+    builder.expr(Some(node)).break_(label)
 }
 
-fn continue_expr(builder: &mut Builder, _: &Arc<ast::Continue>) -> ExprKind {
-    ExprKind::Continue {
-        label: builder.last_loop_label(),
-    }
+fn continue_expr(builder: &mut Builder, node: Arc<ast::Expr>) -> ID {
+    let label = builder.last_loop_label();
+    builder.expr(Some(node)).continue_(label)
 }
 
-fn cast_expr(builder: &mut Builder, cast: &Arc<ast::Cast>) -> ExprKind {
-    ExprKind::Cast {
-        val: expr_(builder, &cast.expr().unwrap()),
-        to: typeref_(builder, &cast.ty().unwrap()),
-    }
+fn cast_expr(
+    builder: &mut Builder,
+    node: Arc<ast::Expr>,
+    cast: &Arc<ast::Cast>,
+) -> ID {
+    let val = expr_(builder, &cast.expr().unwrap());
+    let to = typeref_(builder, &cast.ty().unwrap());
+    builder.expr(Some(node)).cast(val, to)
 }
 
 fn literal(builder: &mut Builder, lit: &Arc<ast::Literal>) -> ID {
-    use utils::string_utils::trim_and_unescape;
     builder.new_literal(
         match lit.value().unwrap() {
+            // Too large a literal is only an error once its type is known
             ast::LiteralValue::Number(n) => {
-                Literal::Number(n.text().parse().unwrap())
+                let text = n.text();
+                let value = match text.strip_prefix("0x") {
+                    Some(hex) => utils::ap::Int::from_str_radix(hex, 16),
+                    None => utils::ap::Int::from_str_radix(text, 10),
+                };
+                Literal::Number {
+                    value: value.unwrap(),
+                    source_repr: text.to_string(),
+                }
             }
             ast::LiteralValue::Str(s) => {
-                Literal::Str(trim_and_unescape(s.text()))
+                // The lexer has already rejected any invalid escapes
+                let text = s.text();
+                Literal::Str(utils::unescape(&text[1..text.len() - 1]).unwrap())
             }
+            ast::LiteralValue::Null(_) => Literal::Null,
         },
         Some(lit.clone()),
     )
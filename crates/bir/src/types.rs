@@ -1,3 +1,4 @@
+use std::ops::Range;
 use std::sync::Arc;
 
 #[repr(transparent)]
@@ -27,6 +28,9 @@ pub struct Map {
     pub(crate) items: IDMap<Item>,
     pub(crate) params: IDMap<Parameter>,
 
+    /// The function each node in a function's signature or body is part of.
+    pub(crate) enclosing_functions: IDMap<ID>,
+
     pub(crate) root_module: Option<ID>,
 }
 
@@ -39,9 +43,122 @@ impl Map {
         self.ast.get(id).cloned()
     }
 
+    /// The byte range `id` was translated from in its module's text, if any.
+    ///
+    /// Expressions and items keep their own span, so this doesn't need to
+    /// go back to the AST for them. A `let` spans the item that declares it.
+    pub fn span(&self, id: &ID) -> Option<Range<usize>> {
+        match self.kind(id) {
+            Kind::Expr => self.expr(id).span.clone(),
+            Kind::Item => self.item(id).span.clone(),
+            Kind::Let => self
+                .items
+                .values()
+                .find(|item| {
+                    matches!(item.kind, ItemKind::Let(let_) if let_ == *id)
+                })
+                .and_then(|item| item.span.clone()),
+            _ => self.ast(id).map(|ast| ast.syntax().range()),
+        }
+    }
+
+    pub fn ast_nodes(
+        &self,
+    ) -> impl Iterator<Item = (ID, &Arc<dyn ast::Node>)> + '_ {
+        self.ast.iter().map(|(id, ast)| (*id, ast))
+    }
+
     pub fn root_module(&self) -> &Module {
         self.mod_(&self.root_module.unwrap())
     }
+
+    /// Functions declared to return `!`, e.g. `fn abort() -> ! extern;`.
+    pub fn functions_that_return_never(
+        &self,
+    ) -> impl Iterator<Item = &Function> + '_ {
+        self.functions.values().filter(|f| {
+            matches!(self.typeref(&f.return_type).kind, TypeRefKind::Never)
+        })
+    }
+
+    /// Every function, ordered so that callees come before their callers.
+    ///
+    /// Only sema knows what a call resolves to, so `callees` gives the
+    /// functions each function calls. Functions in a cycle are ordered by
+    /// when they were declared.
+    pub fn topological_sort_functions<I: IntoIterator<Item = ID>>(
+        &self,
+        callees: impl Fn(&ID) -> I,
+    ) -> Vec<ID> {
+        // Popped from the back, so visited in declaration order
+        let sorted_callees = |id: &ID| {
+            let mut callees: Vec<_> = callees(id).into_iter().collect();
+            callees.sort_by_key(|id| std::cmp::Reverse(id.0));
+            callees
+        };
+
+        let mut visited = std::collections::HashSet::new();
+        let mut order = Vec::new();
+        let fns = self.nodes.iter().enumerate().filter_map(|(idx, kind)| {
+            (*kind == Kind::Function).then_some(ID(idx))
+        });
+        for root in fns {
+            if !visited.insert(root) {
+                continue;
+            }
+            let mut stack = vec![(root, sorted_callees(&root))];
+            while let Some((id, pending)) = stack.last_mut() {
+                let id = *id;
+                match pending.pop() {
+                    Some(callee) => {
+                        if visited.insert(callee) {
+                            stack.push((callee, sorted_callees(&callee)));
+                        }
+                    }
+                    None => {
+                        order.push(id);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        order
+    }
+
+    /// The function, type or module `name` refers to, when used in `scope`.
+    ///
+    /// This is purely syntactic, so it works before (or without) sema: the
+    /// first segment is looked up in `scope` and then each enclosing module,
+    /// and the rest are looked up in the module named by the segment before.
+    /// Locals and builtins aren't items, so they don't resolve.
+    pub fn resolve_name_to_definition(
+        &self,
+        name: &Name,
+        scope: &ID,
+    ) -> Option<ID> {
+        let item_in = |mod_: &Module, ident: &str| {
+            let fn_ = mod_.functions(self).find(|f| f.identifier == ident);
+            let ty = mod_.typedefs(self).find(|ty| ty.identifier == ident);
+            let submod = mod_
+                .modules(self)
+                .find(|m| m.ident.as_deref() == Some(ident));
+            fn_.map(|f| f.id)
+                .or(ty.map(|ty| ty.id))
+                .or(submod.map(|m| m.id))
+        };
+        let (first, rest) = name.segments.split_first()?;
+        let mut enclosing = std::iter::successors(Some(self.mod_(scope)), |m| {
+            m.parent.map(|parent| self.mod_(&parent))
+        });
+        let mut id = enclosing.find_map(|m| item_in(m, first))?;
+        for ident in rest {
+            if self.kind(&id) != Kind::Module {
+                return None;
+            }
+            id = item_in(self.mod_(&id), ident)?;
+        }
+        Some(id)
+    }
 }
 
 macro_rules! impl_map_lookup_fns {
@@ -201,8 +318,19 @@ impl TypeMember {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TypeRefKind {
     Void,
-    Named { name: ID },
-    Pointer { pointee: ID },
+    /// `!`, for functions that never return
+    Never,
+    Named {
+        name: ID,
+    },
+    Pointer {
+        pointee: ID,
+    },
+    Function {
+        return_ty: ID,
+        params: Vec<ID>,
+        is_var_args: bool,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -215,6 +343,7 @@ pub struct Function {
     pub return_type: ID,
     pub is_var_args: bool,
     pub is_extern: bool,
+    pub is_test: bool,
 }
 
 impl Function {
@@ -233,6 +362,7 @@ impl Function {
             return_type,
             is_var_args: false,
             is_extern: false,
+            is_test: false,
         }
     }
 
@@ -254,6 +384,12 @@ impl Function {
     pub fn body<'map>(&self, map: &'map Map) -> Option<&'map Block> {
         self.body.map(|id| map.block(&id))
     }
+
+    /// Whether the function is defined here, rather than only declared. An
+    /// empty body, as in `fn f() {}`, still counts.
+    pub fn has_body(&self) -> bool {
+        self.body.is_some()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -318,6 +454,11 @@ impl Block {
     pub fn return_expr<'map>(&self, map: &'map Map) -> Option<&'map Expr> {
         self.return_expr.map(|id| map.expr(&id))
     }
+
+    /// Whether the block has nothing in it, as in `{}`.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty() && self.return_expr.is_none()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -352,12 +493,17 @@ impl Block {
 pub struct Item {
     pub id: ID,
     pub kind: ItemKind,
+    /// The byte range this was translated from in its module's text, if any
+    pub span: Option<Range<usize>>,
 }
 
 #[derive(Debug, Clone)]
 pub enum ItemKind {
     Let(ID),
     Expr(ID),
+    /// A macro invoked in item position, whose call is the given
+    /// [`ExprKind::MacroCall`]. Nothing produces these yet.
+    MacroExpansion(ID),
 }
 
 #[derive(Debug, Clone)]
@@ -382,6 +528,8 @@ impl Let {
 pub struct Expr {
     pub id: ID,
     pub kind: ExprKind,
+    /// The byte range this was translated from in its module's text, if any
+    pub span: Option<Range<usize>>,
 }
 
 impl Expr {
@@ -435,6 +583,12 @@ pub enum ExprKind {
         kind: LoopKind,
         body: ID,
     },
+    /// A call to the macro `name`, to be replaced by its expansion before
+    /// sema. Nothing produces these yet.
+    MacroCall {
+        name: String,
+        operands: Vec<ID>,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -451,8 +605,13 @@ pub enum LoopKind {
 
 #[derive(Debug, Clone)]
 pub enum Literal {
-    Number(usize),
+    /// `source_repr` is the literal as written, e.g. `0xFF` for 255
+    Number {
+        value: utils::ap::Int,
+        source_repr: String,
+    },
     Str(String),
+    Null,
     Struct(StructLiteral),
 }
 
@@ -501,4 +660,38 @@ pub enum OpKind {
     NotEquals,
     Equals,
     Assignment,
+    Deref,
+    AddressOf,
+    Not,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrity::tests::{function, translate};
+
+    #[test]
+    fn span_of_nested_nodes() {
+        let text = "fn f(x: i32) -> i32 { let y = x; { y * 2 } }";
+        let map = translate(text);
+        let span = |id: &ID| &text[map.span(id).unwrap()];
+
+        let f = function(&map, "f");
+        assert_eq!(span(&f.parameters[0]), "x: i32");
+        let item = map.items().next().unwrap();
+        assert_eq!(span(&item.id), "let y = x;");
+        let ItemKind::Let(let_) = item.kind else {
+            panic!("{item:?}");
+        };
+        assert_eq!(span(&let_), "let y = x;");
+        // The block nested in the body, and the expression nested in that
+        let block = f.body(&map).unwrap().return_expr(&map).unwrap();
+        assert_eq!(span(&block.id), "{ y * 2 }");
+        let ExprKind::Block { scope } = block.kind else {
+            panic!("{block:?}");
+        };
+        let product = map.block(&scope).return_expr(&map).unwrap();
+        // Trivia before the `}` is part of the last expression in the block
+        assert_eq!(span(&product.id), "y * 2 ");
+    }
 }
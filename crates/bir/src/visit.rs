@@ -10,7 +10,7 @@ pub trait Visitor<'bir>: Sized {
     }
     fn visit_import(&mut self, _: &Import) {}
     fn visit_function(&mut self, fn_: &Function) {
-        walk_param_list(self, fn_);
+        walk_function(self, fn_);
     }
     fn visit_param(&mut self, param: &Parameter) {
         walk_param(self, param);
@@ -21,12 +21,18 @@ pub trait Visitor<'bir>: Sized {
     fn visit_item(&mut self, item: &Item) {
         walk_item(self, item);
     }
-    fn visit_let(&mut self, _let: &Let) {}
+    fn visit_let(&mut self, let_: &Let) {
+        walk_let(self, let_);
+    }
     fn visit_expr(&mut self, expr: &Expr) {
         walk_expr(self, expr);
     }
-    fn visit_typeref(&mut self, _: &TypeRef) {}
-    fn visit_typedef(&mut self, _: &TypeDef) {}
+    fn visit_typeref(&mut self, typeref: &TypeRef) {
+        walk_typeref(self, typeref);
+    }
+    fn visit_typedef(&mut self, typedef: &TypeDef) {
+        walk_typedef(self, typedef);
+    }
     fn visit_name(&mut self, _: &Name) {}
 }
 
@@ -61,6 +67,38 @@ pub fn walk_functions<'bir>(v: &mut impl Visitor<'bir>, mod_: &Module) {
     }
 }
 
+pub fn walk_function<'bir>(v: &mut impl Visitor<'bir>, fn_: &Function) {
+    walk_param_list(v, fn_);
+    v.visit_typeref(fn_.return_type(v.map()));
+    if let Some(body) = fn_.body(v.map()) {
+        v.visit_block(body);
+    }
+}
+
+pub fn walk_typedef<'bir>(v: &mut impl Visitor<'bir>, typedef: &TypeDef) {
+    for member in typedef.members.iter() {
+        v.visit_typeref(member.ty(v.map()));
+    }
+}
+
+pub fn walk_typeref<'bir>(v: &mut impl Visitor<'bir>, typeref: &TypeRef) {
+    match &typeref.kind {
+        TypeRefKind::Void | TypeRefKind::Never => {}
+        TypeRefKind::Named { name } => v.visit_name(v.map().name(name)),
+        TypeRefKind::Pointer { pointee } => {
+            v.visit_typeref(v.map().typeref(pointee))
+        }
+        TypeRefKind::Function {
+            return_ty, params, ..
+        } => {
+            for param in params {
+                v.visit_typeref(v.map().typeref(param));
+            }
+            v.visit_typeref(v.map().typeref(return_ty))
+        }
+    }
+}
+
 pub fn walk_param_list<'bir>(v: &mut impl Visitor<'bir>, fn_: &Function) {
     for param in fn_.parameters(v.map()) {
         v.visit_param(param);
@@ -74,14 +112,74 @@ pub fn walk_param<'bir>(v: &mut impl Visitor<'bir>, param: &Parameter) {
 pub fn walk_item<'bir>(v: &mut impl Visitor<'bir>, item: &Item) {
     match item.kind {
         ItemKind::Let(id) => v.visit_let(v.map().let_(&id)),
-        ItemKind::Expr(id) => v.visit_expr(v.map().expr(&id)),
+        ItemKind::Expr(id) | ItemKind::MacroExpansion(id) => {
+            v.visit_expr(v.map().expr(&id))
+        }
+    }
+}
+
+pub fn walk_let<'bir>(v: &mut impl Visitor<'bir>, let_: &Let) {
+    if let Some(ty) = let_.ty(v.map()) {
+        v.visit_typeref(ty);
+    }
+    if let Some(expr) = let_.expr(v.map()) {
+        v.visit_expr(expr);
     }
 }
 
 pub fn walk_expr<'bir>(v: &mut impl Visitor<'bir>, expr: &Expr) {
-    match expr.kind {
-        ExprKind::NameRef { id: name } => v.visit_name(v.map().name(&name)),
-        _ => {}
+    let map = v.map();
+    match &expr.kind {
+        ExprKind::Literal(id) => {
+            if let Literal::Struct(lit) = map.lit(id) {
+                v.visit_name(map.name(&lit.name));
+            }
+        }
+        ExprKind::NameRef { id: name } => v.visit_name(map.name(name)),
+        ExprKind::Cast { val, to } => {
+            v.visit_expr(map.expr(val));
+            v.visit_typeref(map.typeref(to));
+        }
+        ExprKind::Call { receiver, operands } => {
+            v.visit_expr(map.expr(receiver));
+            for op in operands {
+                v.visit_expr(map.expr(op));
+            }
+        }
+        ExprKind::Index { receiver, index } => {
+            v.visit_expr(map.expr(receiver));
+            v.visit_expr(map.expr(index));
+        }
+        ExprKind::Op(op) => {
+            for op in op.operands.iter() {
+                v.visit_expr(map.expr(op));
+            }
+        }
+        ExprKind::Block { scope } => v.visit_block(map.block(scope)),
+        ExprKind::Return { expr } => {
+            if let Some(expr) = expr {
+                v.visit_expr(map.expr(expr));
+            }
+        }
+        ExprKind::Break { .. } | ExprKind::Continue { .. } => {}
+        ExprKind::Branch {
+            condition,
+            left,
+            right,
+            ..
+        } => {
+            v.visit_expr(map.expr(condition));
+            v.visit_block(map.block(left));
+            if let Some(right) = right {
+                v.visit_block(map.block(right));
+            }
+        }
+        ExprKind::Loop { body, .. } => v.visit_block(map.block(body)),
+        ExprKind::MacroCall { operands, .. } => {
+            for op in operands {
+                v.visit_expr(map.expr(op));
+            }
+        }
     }
 }
 
@@ -93,3 +191,101 @@ pub fn walk_block<'bir>(v: &mut impl Visitor<'bir>, scope: &Block) {
         v.visit_expr(expr);
     }
 }
+
+impl Map {
+    /// The function `id` is part of, e.g. the function whose body contains
+    /// an expression. A function is its own enclosing function, and items
+    /// outside of any function (modules, typedefs, ...) have none.
+    ///
+    /// Literals have none either: numbers and strings are interned, so the
+    /// same literal can be part of any number of functions.
+    pub fn find_enclosing_function(&self, id: ID) -> Option<ID> {
+        match self.kind(&id) {
+            Kind::Function => Some(id),
+            Kind::Parameter => Some(self.param(&id).function),
+            Kind::Block => Some(self.block(&id).function),
+            Kind::Literal => None,
+            _ => self.enclosing_functions.get(&id).copied(),
+        }
+    }
+
+    /// Link each node in a function's signature or body back to the
+    /// function, for [`Map::find_enclosing_function`].
+    pub(crate) fn link_enclosing_functions(&mut self) {
+        struct Linker<'bir> {
+            map: &'bir Map,
+            function: ID,
+            links: Vec<(ID, ID)>,
+        }
+        impl<'bir> Visitor<'bir> for Linker<'bir> {
+            fn map(&self) -> &'bir Map {
+                self.map
+            }
+            fn visit_item(&mut self, item: &Item) {
+                self.links.push((item.id, self.function));
+                walk_item(self, item);
+            }
+            fn visit_let(&mut self, let_: &Let) {
+                self.links.push((let_.id, self.function));
+                walk_let(self, let_);
+            }
+            fn visit_expr(&mut self, expr: &Expr) {
+                self.links.push((expr.id, self.function));
+                walk_expr(self, expr);
+            }
+            fn visit_typeref(&mut self, typeref: &TypeRef) {
+                self.links.push((typeref.id, self.function));
+                walk_typeref(self, typeref);
+            }
+            fn visit_name(&mut self, name: &Name) {
+                self.links.push((name.id, self.function));
+            }
+        }
+
+        let mut links = Vec::new();
+        for fn_ in self.functions() {
+            let mut linker = Linker {
+                map: self,
+                function: fn_.id,
+                links,
+            };
+            linker.visit_function(fn_);
+            links = linker.links;
+        }
+        self.enclosing_functions.extend(links);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::integrity::tests::{function, translate};
+
+    #[test]
+    fn enclosing_function() {
+        let map = translate(
+            "
+            type T { a: i32 }
+            fn f(x: i32) -> i32 { let y = x; { y + 1 } }
+            fn g() {}
+            ",
+        );
+        let (f, g) = (function(&map, "f"), function(&map, "g"));
+        assert_eq!(map.find_enclosing_function(f.id), Some(f.id));
+        assert_eq!(map.find_enclosing_function(g.id), Some(g.id));
+        assert_eq!(map.find_enclosing_function(f.parameters[0]), Some(f.id));
+        // Only reachable by searching f's body
+        let let_ = map.lets().next().unwrap().id;
+        assert_eq!(map.find_enclosing_function(let_), Some(f.id));
+        assert_eq!(map.find_enclosing_function(f.return_type), Some(f.id));
+        // `1` could be in any function, since literals are interned
+        let one = *map.literals.keys().next().unwrap();
+        assert_eq!(map.find_enclosing_function(one), None);
+
+        let ty = map.typedefs().next().unwrap().id;
+        assert_eq!(map.find_enclosing_function(ty), None);
+        let member = map.typedef(&ty).members[0].ty;
+        assert_eq!(map.find_enclosing_function(member), None);
+        let root = map.root_module().id;
+        assert_eq!(map.find_enclosing_function(root), None);
+    }
+}
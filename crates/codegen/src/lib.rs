@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
 };
 
@@ -8,6 +8,8 @@ use inkwell::{types::BasicType, values::BasicValue};
 mod types;
 pub use types::*;
 
+mod wasm;
+
 struct CG<'ctx> {
     lir: &'ctx lir::Module,
     context: &'ctx llvm::Context,
@@ -17,8 +19,12 @@ struct CG<'ctx> {
     builder: llvm::Builder<'ctx>,
     target_machine: llvm::TargetMachine,
     optimize: bool,
+    profile: bool,
 
     values: HashMap<lir::ValueID, Value<'ctx>>,
+    /// Phis in the current function, whose incoming values are added once
+    /// every block has been emitted
+    phis: Vec<(llvm::PhiValue<'ctx>, lir::ValueID)>,
 }
 
 impl<'ctx> CG<'ctx> {
@@ -29,6 +35,9 @@ impl<'ctx> CG<'ctx> {
         module: llvm::Module<'ctx>,
         builder: llvm::Builder<'ctx>,
         optimize: bool,
+        pic: bool,
+        profile: bool,
+        wasm: bool,
     ) -> Self {
         use llvm::*;
 
@@ -38,17 +47,33 @@ impl<'ctx> CG<'ctx> {
             OptimizationLevel::None
         };
 
-        let target = Target::from_name("x86-64").unwrap();
+        // Shared libraries can be loaded anywhere, so need PIC
+        let (reloc_mode, code_model) = if pic {
+            (RelocMode::PIC, CodeModel::Small)
+        } else {
+            (RelocMode::Default, CodeModel::Default)
+        };
+
+        let (target, triple, cpu, features) = if wasm {
+            Target::initialize_webassembly(&InitializationConfig::default());
+            let target = Target::from_name("wasm32").unwrap();
+            (target, "wasm32-unknown-unknown", "generic", "")
+        } else {
+            let target = Target::from_name("x86-64").unwrap();
+            (target, "x86_64-pc-linux-gnu", "x86-64", "+avx2")
+        };
+        let triple = TargetTriple::create(triple);
         let target_machine = target
             .create_target_machine(
-                &TargetTriple::create("x86_64-pc-linux-gnu"),
-                "x86-64",
-                "+avx2",
-                opt_level,
-                RelocMode::Default,
-                CodeModel::Default,
+                &triple, cpu, features, opt_level, reloc_mode, code_model,
             )
             .unwrap();
+        // Pointers are only 32-bit on wasm32, so the module has to be laid
+        // out for the target rather than LLVM's default
+        module.set_triple(&triple);
+        module.set_data_layout(
+            &target_machine.get_target_data().get_data_layout(),
+        );
         Self {
             lir,
             source_file: source_file.to_string(),
@@ -58,12 +83,17 @@ impl<'ctx> CG<'ctx> {
             builder,
             target_machine,
             optimize,
+            profile,
             values: Default::default(),
+            phis: Default::default(),
         }
     }
 
     fn compile(&mut self) {
         visit_module(self, &self.lir);
+        if self.profile {
+            self.instrument_blocks();
+        }
         if self.optimize {
             let pass_manager_builder = llvm::PassManagerBuilder::create();
             pass_manager_builder
@@ -76,6 +106,74 @@ impl<'ctx> CG<'ctx> {
         }
     }
 
+    /// Count each run of each basic block with `llvm.instrprof.increment`,
+    /// and lower the counters with LLVM's `instrprof` pass, as clang does
+    /// for `-fprofile-instr-generate`.
+    fn instrument_blocks(&self) {
+        let i32_ty = self.context.i32_type();
+        let i64_ty = self.context.i64_type();
+        let ptr_ty =
+            self.context.i8_type().ptr_type(llvm::AddressSpace::from(0));
+        let increment = self.module.add_function(
+            "llvm.instrprof.increment",
+            self.context.void_type().fn_type(
+                &[ptr_ty.into(), i64_ty.into(), i32_ty.into(), i32_ty.into()],
+                false,
+            ),
+            None,
+        );
+        let functions: Vec<_> = self
+            .module
+            .get_functions()
+            .filter(|f| f.count_basic_blocks() > 0)
+            .collect();
+        for function in functions {
+            let name = function.get_name().to_str().unwrap();
+            let name_str = self.context.const_string(name.as_bytes(), false);
+            let name_var = self.module.add_global(
+                name_str.get_type(),
+                None,
+                &format!("__profn_{name}"),
+            );
+            name_var.set_initializer(&name_str);
+            name_var.set_linkage(llvm::Linkage::Private);
+            name_var.set_constant(true);
+
+            let blocks = function.get_basic_blocks();
+            // Only used to check that a profile matches the code it's
+            // applied to, so the number of counters will do.
+            let hash = i64_ty.const_int(blocks.len() as u64, false);
+            let num_counters = i32_ty.const_int(blocks.len() as u64, false);
+            for (idx, block) in blocks.into_iter().enumerate() {
+                // Phis have to stay at the start of the block
+                let first_non_phi = std::iter::successors(
+                    block.get_first_instruction(),
+                    |inst| inst.get_next_instruction(),
+                )
+                .find(|inst| inst.get_opcode() != llvm::InstructionOpcode::Phi)
+                .expect("unterminated block!");
+                self.builder.position_before(&first_non_phi);
+                self.builder.build_call(
+                    increment,
+                    &[
+                        name_var.as_pointer_value().into(),
+                        hash.into(),
+                        num_counters.into(),
+                        i32_ty.const_int(idx as u64, false).into(),
+                    ],
+                    "",
+                );
+            }
+        }
+        self.module
+            .run_passes(
+                "instrprof",
+                &self.target_machine,
+                llvm::PassBuilderOptions::create(),
+            )
+            .expect("Error lowering profile counters!");
+    }
+
     fn write_ir(&self, output_path: Option<PathBuf>) {
         let ir_file = output_path.unwrap_or_else(|| {
             PathBuf::from(&self.source_file).with_extension("ll")
@@ -99,43 +197,194 @@ impl<'ctx> CG<'ctx> {
             .expect("Error writing object file!");
     }
 
-    fn write_executable(&self, output_path: Option<PathBuf>) {
-        fn print_if_nonempty(stream_name: &str, bytes: Vec<u8>) {
-            if !bytes.is_empty() {
-                println!(
-                    "{stream_name}:\n{}",
-                    String::from_utf8(bytes).unwrap()
-                );
-            }
-        }
-
+    fn write_executable(
+        &self,
+        output_path: Option<PathBuf>,
+        extra_link_args: &[String],
+    ) {
         let _main_fn = self
             .module
             .get_function("main")
             .expect("No 'main' function!");
 
+        let output_path = output_path.unwrap_or_else(|| PathBuf::from("a.out"));
+        self.link(&output_path, "-no-pie", extra_link_args);
+    }
+
+    fn write_shared_library(
+        &self,
+        output_path: Option<PathBuf>,
+        extra_link_args: &[String],
+    ) {
+        let output_path = output_path.unwrap_or_else(|| {
+            PathBuf::from(&self.source_file).with_extension("so")
+        });
+        self.link(&output_path, "-shared", extra_link_args);
+    }
+
+    /// Like `write_executable`, but linked against LLVM's profiling
+    /// runtime, which writes out the counters added by `instrument_blocks`
+    /// when the program exits.
+    fn write_profiled_executable(
+        &self,
+        output_path: Option<PathBuf>,
+        extra_link_args: &[String],
+    ) {
+        let output_path = output_path.unwrap_or_else(|| PathBuf::from("a.out"));
+        let object_file = self.write_temp_object_file();
+        // Only clang knows where to find the runtime
+        self.run_tool(
+            std::process::Command::new("clang")
+                .args([
+                    "-fprofile-instr-generate",
+                    "-no-pie",
+                    object_file.to_str().unwrap(),
+                    "-o",
+                    output_path.to_str().unwrap(),
+                ])
+                .args(extra_link_args),
+        );
+    }
+
+    /// An archive of the object file, for linking into other programs
+    /// without having to recompile.
+    fn write_static_library(&self, output_path: Option<PathBuf>) {
+        let output_path = output_path.unwrap_or_else(|| {
+            PathBuf::from(&self.source_file).with_extension("a")
+        });
+        let object_file = self.write_temp_object_file();
+        // `ar` adds to an existing archive, rather than replacing it
+        if output_path.exists() {
+            std::fs::remove_file(&output_path)
+                .expect("Error removing old archive!");
+        }
+        self.run_tool(std::process::Command::new("ar").args([
+            "rcs",
+            output_path.to_str().unwrap(),
+            object_file.to_str().unwrap(),
+        ]));
+    }
+
+    /// A `.wasm` file exporting every non-internal function, and a `.mjs`
+    /// module next to it that loads it. Functions that are declared but not
+    /// defined are imported from `env`, and have to be provided when the
+    /// module is instantiated.
+    fn write_wasm_js(&self, output_path: Option<PathBuf>) {
+        let output_path = output_path.unwrap_or_else(|| {
+            PathBuf::from(&self.source_file).with_extension("wasm")
+        });
+        let object_file = self.write_temp_object_file();
+        self.run_tool(std::process::Command::new("wasm-ld").args([
+            "--no-entry",
+            "--export-dynamic",
+            "--allow-undefined",
+            object_file.to_str().unwrap(),
+            "-o",
+            output_path.to_str().unwrap(),
+        ]));
+
+        let js_file = output_path.with_extension("mjs");
+        log::debug!("Writing JS module to {}", js_file.to_str().unwrap());
+        let wasm_file = output_path.file_name().unwrap().to_str().unwrap();
+        std::fs::write(&js_file, wasm::js_module(self.lir, wasm_file))
+            .expect("Error writing JS module!");
+    }
+
+    /// List each function that made it into the LLVM module (i.e. survived
+    /// optimization), with its section, whether it's exported, and its size
+    /// in LIR instructions. Addresses aren't known until link time, so
+    /// aren't included.
+    fn write_map(&self, map_path: &Path) {
+        log::debug!("Writing map to {}", map_path.to_str().unwrap());
+        let lir_functions: HashMap<_, _> = self
+            .lir
+            .functions
+            .iter()
+            .map(|f| (f.ident.as_str(), f))
+            .collect();
+        let mut rows: Vec<_> = self
+            .module
+            .get_functions()
+            .filter(|f| f.count_basic_blocks() > 0)
+            .map(|f| {
+                let name = f.get_name().to_str().unwrap().to_string();
+                let section = f
+                    .get_section()
+                    .map_or(".text", |s| s.to_str().unwrap())
+                    .to_string();
+                let linkage = match f.get_linkage() {
+                    llvm::Linkage::Internal | llvm::Linkage::Private => {
+                        "internal"
+                    }
+                    _ => "exported",
+                };
+                // Functions LLVM added itself have no LIR to measure
+                let size = lir_functions
+                    .get(name.as_str())
+                    .map_or("-".to_string(), |f| f.num_insts().to_string());
+                (name, section, linkage, size)
+            })
+            .collect();
+        rows.sort();
+
+        let width = rows
+            .iter()
+            .map(|(name, ..)| name.len())
+            .chain(["function".len()])
+            .max()
+            .unwrap();
+        let mut map = format!(
+            "{:<width$}  {:<8}  {:<8}  {}\n",
+            "function", "section", "linkage", "insts"
+        );
+        for (name, section, linkage, size) in rows {
+            map.push_str(&format!(
+                "{name:<width$}  {section:<8}  {linkage:<8}  {size}\n"
+            ));
+        }
+        std::fs::write(map_path, map).expect("Error writing map file!");
+    }
+
+    fn link(&self, output_path: &Path, kind: &str, extra_link_args: &[String]) {
+        let object_file = self.write_temp_object_file();
+        self.run_tool(
+            std::process::Command::new("gcc")
+                .args([
+                    kind,
+                    "-lc",
+                    object_file.to_str().unwrap(),
+                    "-o",
+                    output_path.to_str().unwrap(),
+                ])
+                .args(extra_link_args),
+        );
+    }
+
+    fn write_temp_object_file(&self) -> PathBuf {
         let source_file = Path::new(&self.source_file);
         let object_file = std::env::temp_dir()
             .join(source_file.file_name().unwrap())
             .with_extension("o");
-        let output_path = output_path
-            .map(|path| path.as_os_str().to_str().unwrap().to_string())
-            .unwrap_or_else(|| "a.out".to_string());
         log::debug!("Writing object code to {}", object_file.to_str().unwrap());
         self.target_machine
             .write_to_file(&self.module, llvm::FileType::Object, &object_file)
             .expect("Error writing object file!");
+        object_file
+    }
 
-        let output = std::process::Command::new("gcc")
-            .args([
-                "-no-pie",
-                "-lc",
-                object_file.to_str().unwrap(),
-                "-o",
-                &output_path,
-            ])
-            .output()
-            .unwrap();
+    /// Run an external tool (the linker or archiver) over our output,
+    /// passing along anything it prints.
+    fn run_tool(&self, command: &mut std::process::Command) {
+        fn print_if_nonempty(stream_name: &str, bytes: Vec<u8>) {
+            if !bytes.is_empty() {
+                println!(
+                    "{stream_name}:\n{}",
+                    String::from_utf8(bytes).unwrap()
+                );
+            }
+        }
+
+        let output = command.output().unwrap();
         print_if_nonempty("stdout", output.stdout);
         print_if_nonempty("stderr", output.stderr);
         if !output.status.success() {
@@ -171,7 +420,19 @@ impl<'ctx> CG<'ctx> {
                     .ptr_type(llvm::AddressSpace::from(0))
                     .into()
             }
-            TyKind::Fn { .. } | TyKind::Void => unreachable!("{:?}", ty.kind),
+            // Functions are only values as pointers, e.g. a fn-typed param
+            TyKind::Fn { .. } => {
+                let fn_ty = ty.as_fn_ty();
+                let params: Vec<_> = fn_ty.params(self.lir).collect();
+                self.translate_fn_type(
+                    fn_ty.return_ty(self.lir),
+                    &params,
+                    fn_ty.is_var_args,
+                )
+                .ptr_type(llvm::AddressSpace::from(0))
+                .into()
+            }
+            TyKind::Void => unreachable!("{:?}", ty.kind),
             TyKind::Struct => {
                 let struct_ty = ty.as_struct_ty(self.lir);
                 let member_tys = struct_ty
@@ -215,12 +476,12 @@ impl<'ctx> CG<'ctx> {
 
 fn visit_module<'ctx>(cg: &mut CG<'ctx>, module: &'ctx lir::Module) {
     let mut fn_values = Vec::new();
-    for function in module.functions.iter() {
+    for function in module.functions_sorted() {
         fn_values.push((function, visit_function_decl(cg, function)));
     }
 
     for (function, fn_value) in fn_values {
-        if function.num_blocks() > 0 {
+        if function.has_body() {
             let blocks = populate_basic_blocks(cg, fn_value, &function);
 
             let ctx = lir::Context::full(module, function);
@@ -232,11 +493,30 @@ fn visit_module<'ctx>(cg: &mut CG<'ctx>, module: &'ctx lir::Module) {
                     (param.val, Value::Val(llvm_param))
                 },
             ));
+            cg.builder.position_at_end(blocks[0].bb);
+            spill_address_taken_params(cg, function);
+            let returns_void = function.return_ty(&module.types).is_void();
             for block in blocks {
                 cg.builder.position_at_end(block.bb);
                 for inst in block.insts(function) {
                     visit_inst(cg, ctx, inst);
                 }
+                // A void function may fall off the end of a block without an
+                // explicit `return`, but LLVM wants every block terminated.
+                let last = cg.builder.get_insert_block().unwrap();
+                if returns_void && last.get_terminator().is_none() {
+                    cg.builder.build_return(None);
+                }
+            }
+            // A phi's incoming values may be defined after it, e.g. along a
+            // loop's back edge, so they're only filled in now.
+            for (phi, inst) in std::mem::take(&mut cg.phis) {
+                let inst = function.inst(&inst).unwrap();
+                for incoming in inst.rvals.chunks(2) {
+                    let val = visit_rvalue(cg, ctx, &incoming[0]);
+                    let block = visit_block(cg, &incoming[1]);
+                    phi.add_incoming(&[(&val, block)]);
+                }
             }
             cg.values.clear();
         }
@@ -262,13 +542,36 @@ fn visit_function_decl<'ctx>(
     let fn_ = c
         .module
         .add_function(&function.ident, fn_type, Some(linkage));
-    for (idx, param) in fn_.get_param_iter().enumerate() {
-        let name = &function.nth_param(idx).val.ident(ctx);
+    let names = function.parameter_names();
+    for (param, name) in fn_.get_param_iter().zip(&names) {
         param.set_name(name);
     }
     fn_
 }
 
+/// Params are SSA values, so any whose address is taken are copied to the
+/// stack first, and then used from there.
+fn spill_address_taken_params(c: &mut CG<'_>, function: &lir::Function) {
+    let address_taken: HashSet<_> = function
+        .blocks()
+        .flat_map(|block| block.insts(function).collect::<Vec<_>>())
+        .filter(|inst| matches!(inst.kind, lir::InstKind::AddressOf))
+        .map(|inst| inst.rvals[0].id)
+        .collect();
+    for param in &function.params {
+        if !address_taken.contains(&param.val) {
+            continue;
+        }
+        let Some(&Value::Val(val)) = c.values.get(&param.val) else {
+            unreachable!("params aren't spilled twice");
+        };
+        let ty = val.get_type();
+        let alloca = c.builder.build_alloca(ty, "spill");
+        c.builder.build_store(alloca, val);
+        c.values.insert(param.val, Value::Addr(alloca, ty));
+    }
+}
+
 fn populate_basic_blocks<'ctx>(
     c: &mut CG<'ctx>,
     fn_: llvm::FunctionValue<'ctx>,
@@ -300,7 +603,7 @@ fn visit_inst<'ctx>(
     ctx: lir::Context<'ctx>,
     inst: &lir::Inst,
 ) {
-    use lir::InstKind;
+    use lir::{InstKind, OverflowMode};
     let result = match &inst.kind {
         InstKind::Copy => {
             let val = visit_rvalue(c, ctx, &inst.rvals[0]);
@@ -333,32 +636,51 @@ fn visit_inst<'ctx>(
             };
             Some(Value::Addr(ptr, ty))
         }
-        InstKind::Add => {
+        InstKind::Add { overflow } => {
             let lhs = visit_rvalue(c, ctx, &inst.rvals[0]).into_int_value();
             let rhs = visit_rvalue(c, ctx, &inst.rvals[1]).into_int_value();
-            Some(Value::Val(
-                c.builder
-                    .build_int_nsw_add(lhs, rhs, "add")
-                    .as_basic_value_enum(),
-            ))
+            let add = match overflow {
+                OverflowMode::Checked => {
+                    c.builder.build_int_nsw_add(lhs, rhs, "add")
+                }
+                OverflowMode::Wrapping => {
+                    c.builder.build_int_add(lhs, rhs, "add")
+                }
+                OverflowMode::Saturating => {
+                    build_saturating(c, "llvm.sadd.sat", lhs, rhs)
+                }
+            };
+            Some(Value::Val(add.as_basic_value_enum()))
         }
-        InstKind::Sub => {
+        InstKind::Sub { overflow } => {
             let lhs = visit_rvalue(c, ctx, &inst.rvals[0]).into_int_value();
             let rhs = visit_rvalue(c, ctx, &inst.rvals[1]).into_int_value();
-            Some(Value::Val(
-                c.builder
-                    .build_int_nsw_sub(lhs, rhs, "sub")
-                    .as_basic_value_enum(),
-            ))
+            let sub = match overflow {
+                OverflowMode::Checked => {
+                    c.builder.build_int_nsw_sub(lhs, rhs, "sub")
+                }
+                OverflowMode::Wrapping => {
+                    c.builder.build_int_sub(lhs, rhs, "sub")
+                }
+                OverflowMode::Saturating => {
+                    build_saturating(c, "llvm.ssub.sat", lhs, rhs)
+                }
+            };
+            Some(Value::Val(sub.as_basic_value_enum()))
         }
-        InstKind::Mul => {
+        InstKind::Mul { overflow } => {
             let lhs = visit_rvalue(c, ctx, &inst.rvals[0]).into_int_value();
             let rhs = visit_rvalue(c, ctx, &inst.rvals[1]).into_int_value();
-            Some(Value::Val(
-                c.builder
-                    .build_int_nsw_mul(lhs, rhs, "mul")
-                    .as_basic_value_enum(),
-            ))
+            let mul = match overflow {
+                OverflowMode::Checked => {
+                    c.builder.build_int_nsw_mul(lhs, rhs, "mul")
+                }
+                OverflowMode::Wrapping => {
+                    c.builder.build_int_mul(lhs, rhs, "mul")
+                }
+                OverflowMode::Saturating => build_saturating_mul(c, lhs, rhs),
+            };
+            Some(Value::Val(mul.as_basic_value_enum()))
         }
         InstKind::Div => {
             let lhs = visit_rvalue(c, ctx, &inst.rvals[0]).into_int_value();
@@ -380,19 +702,14 @@ fn visit_inst<'ctx>(
             None
         }
         InstKind::Call => {
-            let called_fn =
-                visit_any_rvalue(c, ctx, &inst.rvals[0]).into_function_value();
             let ops: Vec<_> = inst
                 .rvals
                 .iter()
                 .skip(1)
                 .map(|val| to_basic_mdvalue(visit_any_rvalue(c, ctx, val)))
                 .collect();
-            let call = c.builder.build_call(called_fn, ops.as_slice(), "call");
-            called_fn
-                .get_type()
-                .get_return_type()
-                .map(|_| Value::Val(call.try_as_basic_value().left().unwrap()))
+            let call = build_any_call(c, ctx, &inst.rvals[0], &ops);
+            call.try_as_basic_value().left().map(Value::Val)
         }
         InstKind::Var => {
             let ty = c.translate_type(inst.lval().ty(ctx));
@@ -411,12 +728,9 @@ fn visit_inst<'ctx>(
             };
             let lhs = visit_rvalue(c, ctx, &inst.rvals[0]);
             let rhs = visit_rvalue(c, ctx, &inst.rvals[1]);
-            let cmp = c.builder.build_int_compare(
-                predicate,
-                lhs.into_int_value(),
-                rhs.into_int_value(),
-                "cmp",
-            );
+            let lhs = cmp_operand(c, lhs);
+            let rhs = cmp_operand(c, rhs);
+            let cmp = c.builder.build_int_compare(predicate, lhs, rhs, "cmp");
             Some(Value::Val(cmp.as_basic_value_enum()))
         }
         InstKind::Cast => {
@@ -429,6 +743,18 @@ fn visit_inst<'ctx>(
             };
             Some(Value::Val(cast.as_basic_value_enum()))
         }
+        InstKind::Phi => {
+            let ty = c.translate_type(inst.val.ty(ctx));
+            let phi = c.builder.build_phi(ty, "phi");
+            c.phis.push((phi, inst.val.id));
+            Some(Value::Val(phi.as_basic_value()))
+        }
+        InstKind::ZExt => {
+            let val = visit_rvalue(c, ctx, &inst.rvals[0]).into_int_value();
+            let ty = c.translate_type(inst.val.ty(ctx)).into_int_type();
+            let zext = c.builder.build_int_z_extend(val, ty, "zext");
+            Some(Value::Val(zext.as_basic_value_enum()))
+        }
         InstKind::Jmp => {
             let dst = visit_block(c, &inst.rvals[0]);
             c.builder.build_unconditional_branch(dst);
@@ -441,6 +767,20 @@ fn visit_inst<'ctx>(
             c.builder.build_conditional_branch(cond, then, alt);
             None
         }
+        InstKind::Switch => {
+            let scrutinee =
+                visit_rvalue(c, ctx, &inst.rvals[0]).into_int_value();
+            let default = visit_block(c, &inst.rvals[1]);
+            let cases: Vec<_> = inst.rvals[2..]
+                .chunks(2)
+                .map(|case| {
+                    let val = visit_rvalue(c, ctx, &case[0]).into_int_value();
+                    (val, visit_block(c, &case[1]))
+                })
+                .collect();
+            c.builder.build_switch(scrutinee, default, &cases);
+            None
+        }
         InstKind::GetField => {
             let base =
                 visit_rvalue(c, ctx, &inst.rvals[0]).into_pointer_value();
@@ -460,6 +800,29 @@ fn visit_inst<'ctx>(
                     .as_basic_value_enum(),
             ))
         }
+        InstKind::ForEach => {
+            visit_for_each(c, ctx, inst);
+            None
+        }
+        InstKind::Memcpy => {
+            let src = inst.rvals[1];
+            let dst = visit_lvalue(c, ctx, &inst.rvals[0]).into_pointer_value();
+            let ty = c.translate_type(src.ty(ctx));
+            let src = visit_lvalue(c, ctx, &src).into_pointer_value();
+            let target_data = c.target_machine.get_target_data();
+            let align = target_data.get_abi_alignment(&ty);
+            // LIR's layout assumes a 64-bit target, so it's only used to
+            // decide whether to memcpy; the size has to be the target's
+            let size = target_data.get_abi_size(&ty);
+            let size = c.context.i64_type().const_int(size, false);
+            c.builder
+                .build_memcpy(dst, align, src, align, size)
+                .unwrap();
+            None
+        }
+        InstKind::AddressOf => {
+            Some(Value::Val(visit_lvalue(c, ctx, &inst.rvals[0])))
+        }
         InstKind::Nop => None,
     };
     if let Some(lval) = inst.lval {
@@ -481,6 +844,65 @@ fn visit_inst<'ctx>(
     }
 }
 
+// Expands `for_each arr, len, f` to:
+//
+//     br header
+//   header:
+//     idx = phi [0, entry], [next, body]
+//     br (idx < len), body, exit
+//   body:
+//     f(&arr[idx])
+//     next = idx + 1
+//     br header
+//   exit:
+//
+// Any remaining instructions in the block are emitted into `exit`.
+fn visit_for_each<'ctx>(
+    c: &mut CG<'ctx>,
+    ctx: lir::Context<'ctx>,
+    inst: &lir::Inst,
+) {
+    let arr = inst.rvals[0];
+    let base = visit_rvalue(c, ctx, &arr).into_pointer_value();
+    let len = visit_rvalue(c, ctx, &inst.rvals[1]).into_int_value();
+    let elem_ty = c.translate_type(arr.ty(ctx).as_ptr_ty().pointee(ctx));
+    let idx_ty = len.get_type();
+
+    let fn_ = c.current_function();
+    let entry = c.builder.get_insert_block().unwrap();
+    let header = c.context.append_basic_block(fn_, "for_each.header");
+    let body = c.context.append_basic_block(fn_, "for_each.body");
+    let exit = c.context.append_basic_block(fn_, "for_each.exit");
+    c.builder.build_unconditional_branch(header);
+
+    c.builder.position_at_end(header);
+    let idx = c.builder.build_phi(idx_ty, "idx");
+    let idx_val = idx.as_basic_value().into_int_value();
+    let cond = c.builder.build_int_compare(
+        inkwell::IntPredicate::SLT,
+        idx_val,
+        len,
+        "cmp",
+    );
+    c.builder.build_conditional_branch(cond, body, exit);
+
+    c.builder.position_at_end(body);
+    let elem = unsafe {
+        c.builder
+            .build_in_bounds_gep(elem_ty, base, &[idx_val], "elem")
+    };
+    build_any_call(c, ctx, &inst.rvals[2], &[elem.into()]);
+    let next = c.builder.build_int_nsw_add(
+        idx_val,
+        idx_ty.const_int(1, false),
+        "next",
+    );
+    c.builder.build_unconditional_branch(header);
+    idx.add_incoming(&[(&idx_ty.const_zero(), entry), (&next, body)]);
+
+    c.builder.position_at_end(exit);
+}
+
 fn visit_any_lvalue<'ctx>(
     c: &mut CG<'ctx>,
     ctx: lir::Context<'ctx>,
@@ -504,14 +926,7 @@ fn visit_any_value<'ctx>(
     cat: ValueCategory,
 ) -> llvm::AnyValueEnum<'ctx> {
     match value.kind(ctx) {
-        lir::ValueKind::Param => {
-            let idx = ctx.as_fn().param_num(value).unwrap();
-            c.current_function()
-                .get_nth_param(idx as u32)
-                .unwrap()
-                .into()
-        }
-        lir::ValueKind::Inst => match c
+        lir::ValueKind::Param | lir::ValueKind::Inst => match c
             .values
             .get(&value.id)
             .copied()
@@ -538,6 +953,9 @@ fn visit_any_value<'ctx>(
                     .build_global_string_ptr(value.str_constant(ctx), ".str")
                     .as_pointer_value()
                     .into(),
+                lir::ConstantKind::Null => {
+                    c.translate_type(ty).into_pointer_type().const_null().into()
+                }
             }
         }
         lir::ValueKind::Function => {
@@ -553,6 +971,96 @@ fn visit_any_value<'ctx>(
     }
 }
 
+/// The LLVM intrinsic `name` taking two integers of type `ty`, e.g.
+/// `llvm.sadd.sat.i32`, declared on first use.
+fn int_intrinsic<'ctx>(
+    c: &CG<'ctx>,
+    name: &str,
+    ty: inkwell::types::IntType<'ctx>,
+    return_ty: llvm::BasicTypeEnum<'ctx>,
+) -> llvm::FunctionValue<'ctx> {
+    let name = format!("{name}.i{}", ty.get_bit_width());
+    c.module.get_function(&name).unwrap_or_else(|| {
+        let fn_ty = return_ty.fn_type(&[ty.into(), ty.into()], false);
+        c.module.add_function(&name, fn_ty, None)
+    })
+}
+
+/// `lhs op rhs` with the saturating intrinsic `name`, clamped to the range
+/// of the (signed) type.
+fn build_saturating<'ctx>(
+    c: &CG<'ctx>,
+    name: &str,
+    lhs: llvm::IntValue<'ctx>,
+    rhs: llvm::IntValue<'ctx>,
+) -> llvm::IntValue<'ctx> {
+    let ty = lhs.get_type();
+    let intrinsic = int_intrinsic(c, name, ty, ty.into());
+    c.builder
+        .build_call(intrinsic, &[lhs.into(), rhs.into()], "sat")
+        .try_as_basic_value()
+        .left()
+        .unwrap()
+        .into_int_value()
+}
+
+/// `lhs * rhs`, clamped to the range of the (signed) type. LLVM has no
+/// saturating multiply, so on overflow this picks the bound with the sign
+/// the true product would have had.
+fn build_saturating_mul<'ctx>(
+    c: &CG<'ctx>,
+    lhs: llvm::IntValue<'ctx>,
+    rhs: llvm::IntValue<'ctx>,
+) -> llvm::IntValue<'ctx> {
+    let ty = lhs.get_type();
+    let bits = ty.get_bit_width();
+    let result_ty = c
+        .context
+        .struct_type(&[ty.into(), c.context.bool_type().into()], false);
+    let intrinsic =
+        int_intrinsic(c, "llvm.smul.with.overflow", ty, result_ty.into());
+    let result = c
+        .builder
+        .build_call(intrinsic, &[lhs.into(), rhs.into()], "mul")
+        .try_as_basic_value()
+        .left()
+        .unwrap()
+        .into_struct_value();
+    let product = c.builder.build_extract_value(result, 0, "product").unwrap();
+    let overflowed = c
+        .builder
+        .build_extract_value(result, 1, "overflowed")
+        .unwrap()
+        .into_int_value();
+
+    let signs = c.builder.build_xor(lhs, rhs, "signs");
+    let negative = c.builder.build_int_compare(
+        inkwell::IntPredicate::SLT,
+        signs,
+        ty.const_zero(),
+        "negative",
+    );
+    let min = ty.const_int(1 << (bits - 1), false);
+    let max = ty.const_int((1 << (bits - 1)) - 1, false);
+    let bound = c.builder.build_select(negative, min, max, "bound");
+    c.builder
+        .build_select(overflowed, bound, product, "mul.sat")
+        .into_int_value()
+}
+
+fn cmp_operand<'ctx>(
+    c: &mut CG<'ctx>,
+    v: llvm::BasicValueEnum<'ctx>,
+) -> llvm::IntValue<'ctx> {
+    match v {
+        llvm::BasicValueEnum::PointerValue(p) => {
+            let i64_ty = c.context.i64_type();
+            c.builder.build_ptr_to_int(p, i64_ty, "ptrtoint")
+        }
+        v => v.into_int_value(),
+    }
+}
+
 fn get_undef(v: llvm::BasicTypeEnum) -> llvm::BasicValueEnum {
     match v {
         inkwell::types::BasicTypeEnum::ArrayType(a) => a.get_undef().into(),
@@ -564,6 +1072,37 @@ fn get_undef(v: llvm::BasicTypeEnum) -> llvm::BasicValueEnum {
     }
 }
 
+/// Calls `callee`, which is either a function or, when it's e.g. a fn-typed
+/// param, a pointer to one.
+fn build_any_call<'ctx>(
+    c: &mut CG<'ctx>,
+    ctx: lir::Context<'ctx>,
+    callee: &lir::ValueRef,
+    args: &[llvm::BasicMetadataValueEnum<'ctx>],
+) -> llvm::CallSiteValue<'ctx> {
+    match visit_any_rvalue(c, ctx, callee) {
+        llvm::AnyValueEnum::FunctionValue(fn_) => {
+            c.builder.build_call(fn_, args, "call")
+        }
+        ptr => {
+            let fn_ty = callee.ty(ctx).as_fn_ty();
+            let params: Vec<_> = fn_ty.params(ctx).collect();
+            let fn_type = c.translate_fn_type(
+                fn_ty.return_ty(ctx),
+                &params,
+                fn_ty.is_var_args,
+            );
+            c.builder.build_indirect_call(
+                fn_type,
+                ptr.into_pointer_value(),
+                args,
+                "call",
+            )
+        }
+    }
+}
+
+/// Functions passed as values decay to pointers, as in C.
 fn to_basic_mdvalue(v: llvm::AnyValueEnum) -> llvm::BasicMetadataValueEnum {
     match v {
         llvm::AnyValueEnum::ArrayValue(v) => v.into(),
@@ -573,8 +1112,10 @@ fn to_basic_mdvalue(v: llvm::AnyValueEnum) -> llvm::BasicMetadataValueEnum {
         llvm::AnyValueEnum::StructValue(v) => v.into(),
         llvm::AnyValueEnum::VectorValue(v) => v.into(),
         llvm::AnyValueEnum::MetadataValue(v) => v.into(),
+        llvm::AnyValueEnum::FunctionValue(v) => {
+            v.as_global_value().as_pointer_value().into()
+        }
         llvm::AnyValueEnum::PhiValue(_)
-        | llvm::AnyValueEnum::FunctionValue(_)
         | llvm::AnyValueEnum::InstructionValue(_) => unreachable!(),
     }
 }
@@ -587,9 +1128,11 @@ fn to_basic_value(v: llvm::AnyValueEnum) -> llvm::BasicValueEnum {
         llvm::AnyValueEnum::PointerValue(v) => v.into(),
         llvm::AnyValueEnum::StructValue(v) => v.into(),
         llvm::AnyValueEnum::VectorValue(v) => v.into(),
+        llvm::AnyValueEnum::FunctionValue(v) => {
+            v.as_global_value().as_pointer_value().into()
+        }
         llvm::AnyValueEnum::MetadataValue(_)
         | llvm::AnyValueEnum::PhiValue(_)
-        | llvm::AnyValueEnum::FunctionValue(_)
         | llvm::AnyValueEnum::InstructionValue(_) => unreachable!(),
     }
 }
@@ -623,6 +1166,8 @@ pub fn compile(
     output_path: Option<&str>,
     action: Action,
     optimize: bool,
+    emit_map: bool,
+    extra_link_args: &[String],
 ) {
     let context = &llvm::Context::create();
     let module = context.create_module(source_file);
@@ -632,13 +1177,45 @@ pub fn compile(
 
     let output_path = output_path.map(|path| PathBuf::from(path));
 
-    let mut compiler =
-        CG::new(lir, source_file, context, module, builder, optimize);
+    let pic = matches!(action, Action::WriteSharedLibrary);
+    let profile = matches!(action, Action::WriteProfiledExecutable);
+    let wasm = matches!(action, Action::WriteWasmJs);
+    let mut compiler = CG::new(
+        lir,
+        source_file,
+        context,
+        module,
+        builder,
+        optimize,
+        pic,
+        profile,
+        wasm,
+    );
     compiler.compile();
+    if emit_map {
+        // Next to the output, or the input if there's no output path
+        let map_path = output_path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(source_file))
+            .with_extension("map");
+        compiler.write_map(&map_path);
+    }
     match action {
         Action::WriteAssembly => compiler.write_assembly_file(output_path),
         Action::WriteIr => compiler.write_ir(output_path),
         Action::WriteObject => compiler.write_object_file(output_path),
-        Action::WriteExecutable => compiler.write_executable(output_path),
+        Action::WriteExecutable => {
+            compiler.write_executable(output_path, extra_link_args)
+        }
+        Action::WriteSharedLibrary => {
+            compiler.write_shared_library(output_path, extra_link_args)
+        }
+        Action::WriteStaticLibrary => {
+            compiler.write_static_library(output_path)
+        }
+        Action::WriteProfiledExecutable => {
+            compiler.write_profiled_executable(output_path, extra_link_args)
+        }
+        Action::WriteWasmJs => compiler.write_wasm_js(output_path),
     }
 }
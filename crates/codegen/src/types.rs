@@ -4,16 +4,19 @@ pub(crate) mod llvm {
     pub use inkwell::context::Context;
     pub use inkwell::execution_engine::ExecutionEngine;
     pub use inkwell::module::{Linkage, Module};
-    pub use inkwell::passes::{PassManager, PassManagerBuilder};
+    pub use inkwell::passes::{
+        PassBuilderOptions, PassManager, PassManagerBuilder,
+    };
     pub use inkwell::targets::{
-        CodeModel, FileType, RelocMode, Target, TargetMachine, TargetTriple,
+        CodeModel, FileType, InitializationConfig, RelocMode, Target,
+        TargetMachine, TargetTriple,
     };
     pub use inkwell::types::{
         BasicMetadataTypeEnum, BasicTypeEnum, FunctionType,
     };
     pub use inkwell::values::{
-        AnyValueEnum, BasicMetadataValueEnum, BasicValueEnum, FunctionValue,
-        PointerValue,
+        AnyValueEnum, BasicMetadataValueEnum, BasicValueEnum, CallSiteValue,
+        FunctionValue, InstructionOpcode, IntValue, PointerValue,
     };
     pub use inkwell::{AddressSpace, OptimizationLevel};
 }
@@ -23,6 +26,14 @@ pub enum Action {
     WriteObject,
     WriteAssembly,
     WriteExecutable,
+    WriteSharedLibrary,
+    WriteStaticLibrary,
+    /// An executable that writes how often each block ran to a `.profraw`
+    /// file on exit, for `llvm-profdata` and `llvm-cov`
+    WriteProfiledExecutable,
+    /// A wasm32 `.wasm` file, and a `.mjs` module that loads it and wraps
+    /// its exported functions for use from a browser or Node.js
+    WriteWasmJs,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -0,0 +1,111 @@
+//! The JavaScript module written alongside a `.wasm` file by
+//! `Action::WriteWasmJs`, which loads it and wraps its exports.
+
+use std::fmt::Write;
+
+/// How a value of a LIR type is passed to and from JavaScript.
+fn js_type(ty: &lir::Ty) -> Option<&'static str> {
+    match ty.kind {
+        lir::TyKind::Integer { size } if size <= 32 => Some("number"),
+        lir::TyKind::Integer { .. } => Some("bigint"),
+        // An address in the instance's memory, which is 32-bit on wasm32
+        lir::TyKind::Pointer => Some("number"),
+        lir::TyKind::Void => Some("void"),
+        lir::TyKind::Fn { .. } | lir::TyKind::Struct => None,
+    }
+}
+
+/// Converts a JS value to the parameter type the export expects, so that
+/// e.g. `add(40, 2)` works for an `i64` parameter.
+fn js_conversion(js_ty: &str) -> &'static str {
+    if js_ty == "bigint" {
+        "BigInt"
+    } else {
+        "Number"
+    }
+}
+
+/// A wrapper around the export for `function`, or `None` if it can't be
+/// called from JavaScript (because it takes or returns a struct, or is
+/// variadic).
+fn wrapper(lir: &lir::Module, function: &lir::Function) -> Option<String> {
+    let ctx = lir::Context::full(lir, function);
+    let fn_ty = function.ty(ctx).as_fn_ty();
+    if fn_ty.is_var_args {
+        return None;
+    }
+    let return_ty = js_type(fn_ty.return_ty(ctx))?;
+    let params = fn_ty.params(ctx).map(js_type).collect::<Option<Vec<_>>>()?;
+    let names = function.parameter_names();
+
+    let mut out = String::from("    /**\n");
+    for (name, ty) in names.iter().zip(&params) {
+        writeln!(out, "     * @param {{{ty}}} {name}").unwrap();
+    }
+    writeln!(out, "     * @returns {{{return_ty}}}\n     */").unwrap();
+    let args = names
+        .iter()
+        .zip(&params)
+        .map(|(name, ty)| format!("{}({name})", js_conversion(ty)))
+        .collect::<Vec<_>>();
+    writeln!(
+        out,
+        "    {ident}: ({names}) => exports.{ident}({args}),",
+        ident = function.ident,
+        names = names.join(", "),
+        args = args.join(", "),
+    )
+    .unwrap();
+    Some(out)
+}
+
+/// An ES module exporting `instantiate(env)`, which loads `wasm_file` from
+/// next to the module (with `fetch` in a browser, or from disk in Node.js)
+/// and resolves to its functions. `env` provides any functions the module
+/// declares but doesn't define.
+pub(crate) fn js_module(lir: &lir::Module, wasm_file: &str) -> String {
+    let mut out = format!(
+        r#"// Generated by tyc. Loads {wasm_file}.
+
+async function load(url) {{
+  if (typeof process !== "undefined" && process.versions?.node) {{
+    const {{ readFile }} = await import("node:fs/promises");
+    return readFile(url);
+  }}
+  const response = await fetch(url);
+  if (!response.ok) {{
+    throw new Error(`failed to fetch ${{url}}: ${{response.status}}`);
+  }}
+  return response.arrayBuffer();
+}}
+
+export async function instantiate(env = {{}}) {{
+  let instance;
+  try {{
+    const bytes = await load(new URL("{wasm_file}", import.meta.url));
+    ({{ instance }} = await WebAssembly.instantiate(bytes, {{ env }}));
+  }} catch (err) {{
+    throw new Error("failed to instantiate {wasm_file}", {{ cause: err }});
+  }}
+  const exports = instance.exports;
+  return {{
+    memory: exports.memory,
+"#
+    );
+    for function in lir.functions_sorted() {
+        if function.internal || !function.has_body() {
+            continue;
+        }
+        match wrapper(lir, function) {
+            Some(wrapper) => out.push_str(&wrapper),
+            None => writeln!(
+                out,
+                "    // {}: not callable from JavaScript",
+                function.ident
+            )
+            .unwrap(),
+        }
+    }
+    out.push_str("  };\n}\n");
+    out
+}
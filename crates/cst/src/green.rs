@@ -17,6 +17,17 @@ pub enum Child {
 }
 
 impl Child {
+    pub fn relative_offset(&self) -> usize {
+        match self {
+            Self::Node {
+                relative_offset, ..
+            }
+            | Self::Token {
+                relative_offset, ..
+            } => *relative_offset,
+        }
+    }
+
     pub fn len(&self) -> usize {
         match self {
             Self::Node { node, .. } => node.len,
@@ -66,6 +77,19 @@ impl Child {
     pub fn as_token(&self) -> &Arc<Token> {
         self.into_token().unwrap()
     }
+
+    fn with_relative_offset(self, relative_offset: usize) -> Self {
+        match self {
+            Self::Node { node, .. } => Self::Node {
+                relative_offset,
+                node,
+            },
+            Self::Token { token, .. } => Self::Token {
+                relative_offset,
+                token,
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -80,6 +104,49 @@ impl Node {
         self.children.iter().map(|child| child.text()).collect()
     }
 
+    /// A copy of this node with child `index` replaced by `child`. Nothing
+    /// below this node is copied, so editing a tree only means copying the
+    /// path from the root down to the edit.
+    ///
+    /// For all the `with_child_*` methods, the relative offset `child` comes
+    /// with is ignored, and every child's offset is recomputed.
+    pub fn with_child_replaced(&self, index: usize, child: Child) -> Arc<Node> {
+        let mut children = self.children.clone();
+        children[index] = child;
+        self.with_children(children)
+    }
+
+    /// A copy of this node with `child` inserted before child `at`.
+    pub fn with_child_inserted(&self, at: usize, child: Child) -> Arc<Node> {
+        let mut children = self.children.clone();
+        children.insert(at, child);
+        self.with_children(children)
+    }
+
+    /// A copy of this node without child `index`.
+    pub fn with_child_removed(&self, index: usize) -> Arc<Node> {
+        let mut children = self.children.clone();
+        children.remove(index);
+        self.with_children(children)
+    }
+
+    fn with_children(&self, children: Vec<Child>) -> Arc<Node> {
+        let mut len = 0;
+        let children = children
+            .into_iter()
+            .map(|child| {
+                let relative_offset = len;
+                len += child.len();
+                child.with_relative_offset(relative_offset)
+            })
+            .collect();
+        Arc::new(Node {
+            kind: self.kind,
+            len,
+            children,
+        })
+    }
+
     fn to_string_indented(&self, indent: usize) -> String {
         format!(
             "{indent}{kind:?}:{children}",
@@ -251,4 +318,82 @@ LET_ITEM:
             .trim()
         );
     }
+
+    #[test]
+    fn editing_children() {
+        let node = {
+            let mut builder = NodeBuilder::new();
+            builder.start_node(SyntaxKind::LET_ITEM);
+            builder.token(SyntaxKind::LET_KW, "let");
+            builder.token(SyntaxKind::WHITESPACE, " ");
+            builder.start_node(SyntaxKind::NAME);
+            builder.token(SyntaxKind::IDENT, "foo");
+            builder.finish_node();
+            builder.finish_node();
+            builder.finish()
+        };
+        let name = node.children[2].as_node();
+        let ident = |text: &str| Child::Token {
+            relative_offset: 0,
+            token: Arc::new(Token {
+                kind: SyntaxKind::IDENT,
+                text: text.to_string(),
+            }),
+        };
+
+        let renamed = node.with_child_replaced(
+            2,
+            Child::Node {
+                relative_offset: 0,
+                node: name.with_child_replaced(0, ident("foobar")),
+            },
+        );
+        assert_eq!(renamed.text(), "let foobar");
+        assert_eq!(renamed.len, 10);
+        assert_eq!(renamed.children[2].relative_offset(), 4);
+        // Only the path down to the edit is new
+        assert!(Arc::ptr_eq(
+            node.children[0].as_token(),
+            renamed.children[0].as_token()
+        ));
+        assert_eq!(node.text(), "let foo");
+
+        let removed = node.with_child_removed(1);
+        assert_eq!(removed.text(), "letfoo");
+        assert_eq!(removed.children[1].relative_offset(), 3);
+
+        let inserted = removed.with_child_inserted(
+            1,
+            Child::Token {
+                relative_offset: 0,
+                token: Arc::new(Token {
+                    kind: SyntaxKind::WHITESPACE,
+                    text: "  ".to_string(),
+                }),
+            },
+        );
+        assert_eq!(inserted.text(), "let  foo");
+        assert_eq!(inserted.children[2].relative_offset(), 5);
+        assert_eq!(inserted.len, 8);
+    }
+
+    #[test]
+    fn kind_predicates() {
+        assert!(SyntaxKind::COMMENT.is_trivia());
+        assert!(!SyntaxKind::IDENT.is_trivia());
+        assert!(SyntaxKind::NULL_KW.is_keyword());
+        assert!(SyntaxKind::NULL_KW.is_literal());
+        assert!(SyntaxKind::NUMBER.is_literal());
+        assert!(!SyntaxKind::IDENT.is_literal());
+    }
+
+    #[test]
+    fn kind_display() {
+        assert_eq!(SyntaxKind::LEFT_PAREN.to_string(), "'('");
+        assert_eq!(SyntaxKind::DASH_ARROW.to_string(), "'->'");
+        assert_eq!(SyntaxKind::FN_KW.to_string(), "'fn'");
+        assert_eq!(SyntaxKind::IDENT.to_string(), "identifier");
+        assert_eq!(SyntaxKind::EOF.to_string(), "end of file");
+        assert_eq!(SyntaxKind::CALL_EXPR.to_string(), "call expr");
+    }
 }
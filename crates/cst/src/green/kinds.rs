@@ -14,6 +14,9 @@ pub enum SyntaxKind {
 
     BASIC_TYPE,
     POINTER_TYPE,
+    FN_TYPE,
+    NEVER_TYPE,
+    PARAM_TYPE_LIST,
 
     PARAM_LIST,
     PARAM,
@@ -26,10 +29,12 @@ pub enum SyntaxKind {
     IMPORT_ITEM,
 
     TYPE_MEMBER,
+    ATTR,
 
     IDENT,
     WHITESPACE,
     COMMENT,
+    BLOCK_COMMENT,
 
     LEFT_PAREN,
     RIGHT_PAREN,
@@ -52,6 +57,7 @@ pub enum SyntaxKind {
     SLASH,
     DOT,
     BANG,
+    HASH,
 
     AMPERSAND_AMPERSAND,
     BAR_BAR,
@@ -73,6 +79,7 @@ pub enum SyntaxKind {
     PREFIX_EXPR,
     BIN_EXPR,
     PAREN_EXPR,
+    TUPLE_EXPR,
     BLOCK_EXPR,
     RETURN_EXPR,
     CALL_EXPR,
@@ -96,6 +103,7 @@ pub enum SyntaxKind {
     LOOP_KW,
     WHILE_KW,
     MOD_KW,
+    NULL_KW,
     RETURN_KW,
     TYPE_KW,
 }
@@ -162,6 +170,9 @@ macro_rules! T {
     (!) => {
         crate::SyntaxKind::BANG
     };
+    (#) => {
+        crate::SyntaxKind::HASH
+    };
     (&&) => {
         crate::SyntaxKind::AMPERSAND_AMPERSAND
     };
@@ -231,6 +242,9 @@ macro_rules! T {
     (extern) => {
         crate::SyntaxKind::EXTERN_KW
     };
+    (null) => {
+        crate::SyntaxKind::NULL_KW
+    };
 }
 
 pub enum Subtokens {
@@ -249,7 +263,104 @@ impl Subtokens {
     }
 }
 
+/// A set of `SyntaxKind`s, for checking membership without a chain of `==`s.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SyntaxKindSet(u128);
+
+// Each kind gets one bit of the set
+const _: () = assert!((SyntaxKind::TYPE_KW as usize) < 128);
+
+impl SyntaxKindSet {
+    pub const EMPTY: Self = Self(0);
+
+    pub const fn from_kinds(kinds: &[SyntaxKind]) -> Self {
+        let mut bits = 0;
+        let mut idx = 0;
+        while idx < kinds.len() {
+            bits |= Self::bit(kinds[idx]);
+            idx += 1;
+        }
+        Self(bits)
+    }
+
+    pub const fn contains(self, kind: SyntaxKind) -> bool {
+        self.0 & Self::bit(kind) != 0
+    }
+
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn insert(&mut self, kind: SyntaxKind) {
+        self.0 |= Self::bit(kind);
+    }
+
+    pub fn remove(&mut self, kind: SyntaxKind) {
+        self.0 &= !Self::bit(kind);
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    const fn bit(kind: SyntaxKind) -> u128 {
+        1 << kind as u128
+    }
+}
+
 impl SyntaxKind {
+    /// The source text of a fixed token (punctuation or keyword), if any.
+    pub fn token_text(&self) -> Option<&'static str> {
+        Some(match *self {
+            T!['('] => "(",
+            T![')'] => ")",
+            T!['{'] => "{",
+            T!['}'] => "}",
+            T!['['] => "[",
+            T![']'] => "]",
+            T![<] => "<",
+            T![>] => ">",
+            T![:] => ":",
+            T![;] => ";",
+            T![&] => "&",
+            T![=] => "=",
+            T![|] => "|",
+            T![,] => ",",
+            T![-] => "-",
+            T![+] => "+",
+            T![*] => "*",
+            T![/] => "/",
+            T![.] => ".",
+            T![!] => "!",
+            T![#] => "#",
+            T![&&] => "&&",
+            T![==] => "==",
+            T![!=] => "!=",
+            T![>=] => ">=",
+            T![<=] => "<=",
+            T![||] => "||",
+            T![->] => "->",
+            T![::] => "::",
+            T![...] => "...",
+            T![mod] => "mod",
+            T![import] => "import",
+            T![type] => "type",
+            T![fn] => "fn",
+            T![let] => "let",
+            T![return] => "return",
+            T![if] => "if",
+            T![else] => "else",
+            T![loop] => "loop",
+            T![while] => "while",
+            T![break] => "break",
+            T![continue] => "continue",
+            T![as] => "as",
+            T![extern] => "extern",
+            T![null] => "null",
+            _ => return None,
+        })
+    }
+
     pub fn is_keyword(&self) -> bool {
         match *self {
             Self::MOD_KW
@@ -265,7 +376,8 @@ impl SyntaxKind {
             | Self::BREAK_KW
             | Self::CONTINUE_KW
             | Self::AS_KW
-            | Self::EXTERN_KW => true,
+            | Self::EXTERN_KW
+            | Self::NULL_KW => true,
             _ => false,
         }
     }
@@ -297,7 +409,17 @@ impl SyntaxKind {
 
     pub fn is_trivia(&self) -> bool {
         match self {
-            Self::EOL | Self::WHITESPACE | Self::COMMENT => true,
+            Self::EOL
+            | Self::WHITESPACE
+            | Self::COMMENT
+            | Self::BLOCK_COMMENT => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_literal(&self) -> bool {
+        match *self {
+            Self::NUMBER | Self::STRING | Self::NULL_KW => true,
             _ => false,
         }
     }
@@ -325,6 +447,7 @@ impl SyntaxKind {
             | Self::PREFIX_EXPR
             | Self::BIN_EXPR
             | Self::PAREN_EXPR
+            | Self::TUPLE_EXPR
             | Self::BLOCK_EXPR
             | Self::RETURN_EXPR
             | Self::INDEX_EXPR
@@ -336,3 +459,50 @@ impl SyntaxKind {
         }
     }
 }
+
+impl std::fmt::Display for SyntaxKind {
+    /// Human-readable name for diagnostics, e.g. `')'` or `identifier`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(text) = self.token_text() {
+            return write!(f, "'{text}'");
+        }
+        let name = match *self {
+            Self::EOF => "end of file",
+            Self::EOL => "end of line",
+            Self::IDENT => "identifier",
+            Self::NUMBER => "number",
+            Self::STRING => "string",
+            Self::WHITESPACE => "whitespace",
+            Self::COMMENT => "comment",
+            Self::BLOCK_COMMENT => "block comment",
+            _ => {
+                let name = format!("{self:?}").to_lowercase().replace('_', " ");
+                return f.write_str(&name);
+            }
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_set_membership() {
+        let mut set = SyntaxKindSet::from_kinds(&[T![;], T!['}'], T![type]]);
+        assert!(set.contains(T![;]));
+        assert!(set.contains(SyntaxKind::TYPE_KW));
+        assert!(!set.contains(T!['{']));
+        assert!(!set.contains(SyntaxKind::TOMBSTONE));
+
+        set.remove(T![;]);
+        assert!(!set.contains(T![;]));
+        set.insert(SyntaxKind::TOMBSTONE);
+        assert!(set.contains(SyntaxKind::TOMBSTONE));
+
+        let other = SyntaxKindSet::from_kinds(&[T![;]]);
+        assert!(set.union(other).contains(T![;]));
+        assert!(SyntaxKindSet::EMPTY.is_empty());
+    }
+}
@@ -6,8 +6,10 @@ use std::sync::Arc;
 pub type Token = Arc<green::Token>;
 pub type TokenCache = HashMap<(SyntaxKind, u64), Token>;
 
+/// Text indexed by byte offset, like the ranges of the tokens lexed from it.
 pub trait TextSource {
     fn peek_n(&self, start: usize, n: usize) -> &str;
+    /// The char starting at byte offset `at`.
     fn peek(&self, at: usize) -> Option<char>;
 }
 
@@ -17,7 +19,7 @@ impl TextSource for &str {
     }
 
     fn peek(&self, at: usize) -> Option<char> {
-        self.chars().nth(at)
+        self.get(at..)?.chars().next()
     }
 }
 
@@ -31,10 +33,6 @@ pub struct Lexer<'src, 'snk, Src: TextSource, Snk: TokenSink> {
     offset: usize,
 }
 
-macro_rules! start_ident {
-    () => { 'a'..='z' | 'A'..='Z' | '_' }
-}
-
 macro_rules! number {
     () => {
         '0'..='9'
@@ -59,10 +57,20 @@ where
     Sink: TokenSink,
 {
     pub fn new(source: &'src Src, sink: &'snk mut Sink) -> Self {
+        Self::new_at(source, sink, 0)
+    }
+
+    /// A lexer starting `offset` bytes into `source`, which should be the
+    /// start of a token.
+    pub fn new_at(
+        source: &'src Src,
+        sink: &'snk mut Sink,
+        offset: usize,
+    ) -> Self {
         Self {
             source,
             sink,
-            offset: 0,
+            offset,
         }
     }
 }
@@ -77,7 +85,7 @@ impl<Src: TextSource, Sink: TokenSink> Lexer<'_, '_, Src, Sink> {
             }
         };
         match token {
-            start_ident!() => self.ident_or_keyword(),
+            c if is_start_ident(c) => self.ident_or_keyword(),
             number!() => self.number(),
             whitespace!() => self.whitespace(),
             eol!() => self.eol(token),
@@ -93,6 +101,7 @@ impl<Src: TextSource, Sink: TokenSink> Lexer<'_, '_, Src, Sink> {
             ';' => self.single(SyntaxKind::SEMICOLON),
             '=' => self.single(SyntaxKind::EQUALS),
             '!' => self.single(SyntaxKind::BANG),
+            '#' => self.single(SyntaxKind::HASH),
             ',' => self.single(SyntaxKind::COMMA),
             '-' => self.single(SyntaxKind::DASH),
             '+' => self.single(SyntaxKind::PLUS),
@@ -102,17 +111,23 @@ impl<Src: TextSource, Sink: TokenSink> Lexer<'_, '_, Src, Sink> {
             '|' => self.single(SyntaxKind::BAR),
             '"' => self.string(),
             '/' => {
-                if self.source.peek_n(self.offset, 2) == "//" {
+                if self.peek_ahead(1) == Some('/') {
                     self.comment();
+                } else if self.peek_ahead(1) == Some('*') {
+                    self.block_comment();
                 } else {
                     self.single(SyntaxKind::SLASH);
                 }
             }
-            _ => self.single(SyntaxKind::ERROR),
+            c => self.token(SyntaxKind::ERROR, c.len_utf8()),
         };
         true
     }
 
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
     fn peek(&self) -> Option<char> {
         self.source.peek(self.offset)
     }
@@ -135,12 +150,12 @@ impl<Src: TextSource, Sink: TokenSink> Lexer<'_, '_, Src, Sink> {
     fn matching_range(
         &mut self,
         bias: usize,
-        accept: impl Fn(char) -> bool,
+        mut accept: impl FnMut(char) -> bool,
     ) -> usize {
         let mut len = bias;
         while let Some(c) = self.peek_ahead(len) {
             if accept(c) {
-                len += 1;
+                len += c.len_utf8();
             } else {
                 break;
             }
@@ -168,6 +183,7 @@ impl<Src: TextSource, Sink: TokenSink> Lexer<'_, '_, Src, Sink> {
             "let" => SyntaxKind::LET_KW,
             "loop" => SyntaxKind::LOOP_KW,
             "mod" => SyntaxKind::MOD_KW,
+            "null" => SyntaxKind::NULL_KW,
             "return" => SyntaxKind::RETURN_KW,
             "type" => SyntaxKind::TYPE_KW,
             "while" => SyntaxKind::WHILE_KW,
@@ -189,14 +205,33 @@ impl<Src: TextSource, Sink: TokenSink> Lexer<'_, '_, Src, Sink> {
     }
 
     fn number(&mut self) {
-        self.lex_kind(SyntaxKind::NUMBER, is_number);
+        let is_hex = self.peek_ahead(0) == Some('0')
+            && self.peek_ahead(1) == Some('x')
+            && self.peek_ahead(2).is_some_and(|c| c.is_ascii_hexdigit());
+        if is_hex {
+            let len = self.matching_range(2, |c| c.is_ascii_hexdigit());
+            self.token(SyntaxKind::NUMBER, len);
+        } else {
+            self.lex_kind(SyntaxKind::NUMBER, is_number);
+        }
     }
 
     fn string(&mut self) {
-        let mut len = self.matching_range(1, |c| c != '"');
+        // An escaped `"` doesn't end the string
+        let mut in_escape = false;
+        let mut len = self.matching_range(1, |c| {
+            let accept = in_escape || c != '"';
+            in_escape = !in_escape && c == '\\';
+            accept
+        });
         let kind = if self.peek_ahead(len) == Some('"') {
             len += 1;
-            SyntaxKind::STRING
+            let body = self.source.peek_n(self.offset + 1, len - 2);
+            if utils::unescape(body).is_ok() {
+                SyntaxKind::STRING
+            } else {
+                SyntaxKind::ERROR
+            }
         } else {
             SyntaxKind::ERROR
         };
@@ -207,14 +242,44 @@ impl<Src: TextSource, Sink: TokenSink> Lexer<'_, '_, Src, Sink> {
         let len = self.matching_range(2, |c| c != '\n');
         self.token(SyntaxKind::COMMENT, len);
     }
+
+    fn block_comment(&mut self) {
+        // An unterminated comment runs to the end of the source, and is
+        // reported by `token_error`
+        let mut last = None;
+        let mut closed = false;
+        let len = self.matching_range(2, |c| {
+            if closed {
+                return false;
+            }
+            closed = last == Some('*') && c == '/';
+            last = Some(c);
+            true
+        });
+        self.token(SyntaxKind::BLOCK_COMMENT, len);
+    }
+}
+
+/// The error in a token the lexer produced, if any. Found from the token
+/// alone, so that it holds for tokens reused by `lex_incremental` too.
+pub fn token_error(kind: SyntaxKind, text: &str) -> Option<&'static str> {
+    match kind {
+        // `/*/` doesn't close itself
+        SyntaxKind::BLOCK_COMMENT
+            if text.len() < 4 || !text.ends_with("*/") =>
+        {
+            Some("unterminated block comment")
+        }
+        _ => None,
+    }
 }
 
 fn is_start_ident(c: char) -> bool {
-    matches!(c, start_ident!())
+    c.is_alphabetic() || c == '_'
 }
 
 fn is_ident(c: char) -> bool {
-    char::is_ascii_alphanumeric(&c)
+    c.is_alphanumeric() || c == '_'
 }
 
 fn is_whitespace(c: char) -> bool {
@@ -225,6 +290,7 @@ fn is_number(c: char) -> bool {
     matches!(c, number!())
 }
 
+#[derive(Clone)]
 pub struct Tokens {
     pub stream: Vec<Token>,
     pub cache: TokenCache,
@@ -258,6 +324,75 @@ pub fn lex(source: &str) -> Tokens {
     tokens
 }
 
+/// A replacement of the bytes in `range` of some text with `text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: std::ops::Range<usize>,
+    pub text: String,
+}
+
+impl TextEdit {
+    pub fn apply(&self, old_source: &str) -> String {
+        let mut source = old_source.to_string();
+        source.replace_range(self.range.clone(), &self.text);
+        source
+    }
+}
+
+/// Lex `source`, which is the source of `old` after `edit`, reusing as many
+/// of the tokens of `old` as possible.
+///
+/// The lexer doesn't carry any state from one token to the next, so only the
+/// tokens around the edit need relexing: from the last token that couldn't
+/// have seen the edit, until relexing lines up with an old token boundary.
+pub fn lex_incremental(old: &Tokens, source: &str, edit: &TextEdit) -> Tokens {
+    let mut tokens = Tokens {
+        stream: Vec::with_capacity(old.stream.len()),
+        cache: old.cache.clone(),
+    };
+
+    let mut old_starts = Vec::with_capacity(old.stream.len());
+    let mut offset = 0;
+    for token in &old.stream {
+        old_starts.push(offset);
+        offset += token.len();
+    }
+
+    // A token can depend on the two characters past its end (`0` is only
+    // the start of a hex number if `x` and a hex digit follow), so a token
+    // ending within two characters of the edit may still change.
+    let num_prefix = old_starts
+        .iter()
+        .zip(&old.stream)
+        .take_while(|(start, token)| {
+            *start + token.len() + 1 < edit.range.start
+        })
+        .count();
+    tokens.stream.extend_from_slice(&old.stream[..num_prefix]);
+    let relex_start = old_starts
+        .get(num_prefix)
+        .copied()
+        .unwrap_or(offset)
+        .min(edit.range.start);
+
+    let edit_end = edit.range.start + edit.text.len();
+    let mut lexer = Lexer::new_at(&source, &mut tokens, relex_start);
+    while lexer.lex_one() {
+        let new_offset = lexer.offset();
+        if new_offset < edit_end {
+            continue;
+        }
+        let old_offset = new_offset + edit.range.len() - edit.text.len();
+        if let Ok(idx) = old_starts.binary_search(&old_offset) {
+            if old_offset >= edit.range.end {
+                tokens.stream.extend_from_slice(&old.stream[idx..]);
+                break;
+            }
+        }
+    }
+    tokens
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -298,6 +433,22 @@ mod tests {
         check("foo", &[(IDENT, "foo")]);
     }
 
+    #[test]
+    fn unicode() {
+        check("ñandú", &[(IDENT, "ñandú")]);
+        check("π_2 x", &[(IDENT, "π_2"), (WHITESPACE, " "), (IDENT, "x")]);
+        check(r#""héllo""#, &[(STRING, "\"héllo\"")]);
+        check("// ü\n", &[(COMMENT, "// ü"), (EOL, "\n")]);
+        check("→a", &[(ERROR, "→"), (IDENT, "a")]);
+        check("/é", &[(SLASH, "/"), (IDENT, "é")]);
+    }
+
+    #[test]
+    fn trailing_slash() {
+        check("/", &[(SLASH, "/")]);
+        check("a /", &[(IDENT, "a"), (WHITESPACE, " "), (SLASH, "/")]);
+    }
+
     #[test]
     fn whitespace_and_eol() {
         check("  ", &[(WHITESPACE, "  ")]);
@@ -313,6 +464,8 @@ mod tests {
     fn number() {
         check("123", &[(NUMBER, "123")]);
         check("3", &[(NUMBER, "3")]);
+        check("0xFF", &[(NUMBER, "0xFF")]);
+        check("0x", &[(NUMBER, "0"), (IDENT, "x")]);
     }
 
     #[test]
@@ -323,6 +476,60 @@ mod tests {
             r#""foobar""bazbaz""#,
             &[(STRING, "\"foobar\""), (STRING, "\"bazbaz\"")],
         );
+        check(r#""a\"b\\""#, &[(STRING, r#""a\"b\\""#)]);
+        check(r#""a\qb""#, &[(ERROR, r#""a\qb""#)]);
+    }
+
+    fn check_incremental(old: &str, range: std::ops::Range<usize>, text: &str) {
+        let edit = TextEdit {
+            range,
+            text: text.to_string(),
+        };
+        let new = edit.apply(old);
+        let tokens = lex_incremental(&lex(old), &new, &edit);
+        let expected = lex(&new);
+        let kinds_and_texts = |tokens: &Tokens| {
+            tokens
+                .stream
+                .iter()
+                .map(|token| (token.kind, token.text.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(kinds_and_texts(&tokens), kinds_and_texts(&expected));
+    }
+
+    #[test]
+    fn incremental() {
+        check_incremental("let foo = 1;", 5..8, "bar");
+        check_incremental("let foo = 1;", 7..7, "d");
+        check_incremental("let foo = 1;", 4..4, "x");
+        check_incremental("let foo = 1;", 3..4, "");
+        check_incremental("let foo = 1;", 0..12, "fn");
+        check_incremental("let foo = 1;", 12..12, " // hi");
+        check_incremental("a \"b\" c", 2..2, "\"");
+        check_incremental("a // b\nc d", 2..4, "");
+        check_incremental("", 0..0, "x y");
+        check_incremental("let π = 1;", 4..6, "ñ");
+        check_incremental("a b c", 2..2, "/*");
+        check_incremental("a /* b */ c", 7..9, "");
+        check_incremental("0xg", 2..3, "1");
+    }
+
+    #[test]
+    fn block_comment() {
+        check("/* a */b", &[(BLOCK_COMMENT, "/* a */"), (IDENT, "b")]);
+        check("/* a\n * b */", &[(BLOCK_COMMENT, "/* a\n * b */")]);
+        check("/**/", &[(BLOCK_COMMENT, "/**/")]);
+        check("/*/", &[(BLOCK_COMMENT, "/*/")]);
+        check(
+            "a /* b",
+            &[(IDENT, "a"), (WHITESPACE, " "), (BLOCK_COMMENT, "/* b")],
+        );
+        assert_eq!(token_error(BLOCK_COMMENT, "/* a */"), None);
+        assert_eq!(token_error(BLOCK_COMMENT, "/**/"), None);
+        let unterminated = Some("unterminated block comment");
+        assert_eq!(token_error(BLOCK_COMMENT, "/*/"), unterminated);
+        assert_eq!(token_error(BLOCK_COMMENT, "/* b"), unterminated);
     }
 
     #[test]
@@ -381,7 +588,7 @@ mod tests {
     #[test]
     fn single_tokens() {
         check(
-            "(){}[]<>:;=!,-+*/.&|",
+            "(){}[]<>:;=!#,-+*/.&|",
             &[
                 (LEFT_PAREN, "("),
                 (RIGHT_PAREN, ")"),
@@ -395,6 +602,7 @@ mod tests {
                 (SEMICOLON, ";"),
                 (EQUALS, "="),
                 (BANG, "!"),
+                (HASH, "#"),
                 (COMMA, ","),
                 (DASH, "-"),
                 (PLUS, "+"),
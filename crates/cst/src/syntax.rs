@@ -59,33 +59,43 @@ impl NodeOrToken {
     }
 
     #[inline]
-    pub fn into_token(&self) -> Option<Token> {
+    pub fn is_node(&self) -> bool {
+        matches!(self, Self::Node(_))
+    }
+
+    #[inline]
+    pub fn is_token(&self) -> bool {
+        matches!(self, Self::Token(_))
+    }
+
+    #[inline]
+    pub fn as_node(&self) -> Option<&Node> {
         match self {
-            Self::Token(tok) => Some(tok.clone()),
+            Self::Node(node) => Some(node),
             _ => None,
         }
     }
 
     #[inline]
-    pub fn into_node(&self) -> Option<Node> {
+    pub fn as_token(&self) -> Option<&Token> {
         match self {
-            Self::Node(node) => Some(node.clone()),
+            Self::Token(tok) => Some(tok),
             _ => None,
         }
     }
 
     #[inline]
-    pub fn into_token_ref(&self) -> Option<&Token> {
+    pub fn into_node(self) -> Option<Node> {
         match self {
-            Self::Token(tok) => Some(tok),
+            Self::Node(node) => Some(node),
             _ => None,
         }
     }
 
     #[inline]
-    pub fn into_node_ref(&self) -> Option<&Node> {
+    pub fn into_token(self) -> Option<Token> {
         match self {
-            Self::Node(node) => Some(node),
+            Self::Token(tok) => Some(tok),
             _ => None,
         }
     }
@@ -108,9 +118,7 @@ impl NodeOrToken {
 
     #[inline]
     pub fn siblings(&self) -> impl Iterator<Item = NodeOrToken> + '_ {
-        self.into_node_ref()
-            .into_iter()
-            .flat_map(|node| node.siblings())
+        self.as_node().into_iter().flat_map(|node| node.siblings())
     }
 
     #[inline]
@@ -136,16 +144,14 @@ impl NodeOrToken {
 
     #[inline]
     pub fn children(&self) -> impl Iterator<Item = Node> + '_ {
-        self.into_node_ref()
-            .into_iter()
-            .flat_map(|node| node.children())
+        self.as_node().into_iter().flat_map(|node| node.children())
     }
 
     #[inline]
     pub fn children_with_tokens(
         &self,
     ) -> impl Iterator<Item = NodeOrToken> + '_ {
-        self.into_node_ref()
+        self.as_node()
             .into_iter()
             .flat_map(|node| node.children_with_tokens())
     }
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use crate::syntax::NodeOrToken;
+use crate::syntax::{NodeOrToken, Token};
 use crate::{green, SyntaxKind};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -164,13 +164,11 @@ impl Node {
         self.children().nth(i).unwrap()
     }
 
+    /// The green node already knows its length, so this doesn't need to
+    /// look at the children at all.
     #[inline]
     pub fn range(&self) -> std::ops::Range<usize> {
-        let (start, mut end) = (self.offset, self.offset);
-        for child in self.children_with_tokens() {
-            end = child.range().end;
-        }
-        start..end
+        self.offset..self.offset + self.green.len
     }
 
     pub fn to_string_indented(&self, indent: usize) -> String {
@@ -192,4 +190,96 @@ impl Node {
     pub fn as_node_or_token(&self) -> NodeOrToken {
         NodeOrToken::Node(self.clone())
     }
+
+    /// Find the deepest token whose range contains `offset`. Children are
+    /// stored in source order, so each level is a binary search.
+    pub fn find_token_at_offset(&self, offset: usize) -> Option<Token> {
+        let mut node = self.clone();
+        loop {
+            let relative_offset = offset.checked_sub(node.offset)?;
+            let children = &node.green.children;
+            let idx = children
+                .partition_point(|c| c.relative_offset() <= relative_offset)
+                .checked_sub(1)?;
+            let child = &children[idx];
+            if relative_offset >= child.relative_offset() + child.len() {
+                return None;
+            }
+            match node.construct_child(idx, child) {
+                NodeOrToken::Node(child) => node = child,
+                NodeOrToken::Token(token) => return Some(token),
+            }
+        }
+    }
+
+    /// Check that the descendant tokens of this node are ordered, do not
+    /// overlap, and together cover the node's range without gaps.
+    pub fn validate_ranges(&self) -> Result<(), String> {
+        let mut expected_start = self.offset;
+        for token in crate::syntax::traverse::preorder(self.clone())
+            .filter_map(|n| n.into_token())
+        {
+            let range = token.range();
+            if range.start != expected_start {
+                return Err(format!(
+                    "{:?} @ {range:?}: expected to start at {expected_start}",
+                    token.kind()
+                ));
+            }
+            expected_start = range.end;
+        }
+        let end = self.offset + self.green.len;
+        if expected_start != end {
+            return Err(format!(
+                "{:?} @ {}..{end}: tokens end at {expected_start}",
+                self.kind(),
+                self.offset,
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::syntax::Builder;
+    use crate::SyntaxKind::*;
+
+    #[test]
+    fn find_token_at_offset() {
+        let root = {
+            let mut builder = Builder::new();
+            builder.start_node(LET_ITEM);
+            builder.token(LET_KW, "let");
+            builder.token(WHITESPACE, " ");
+            builder.start_node(NAME);
+            builder.token(IDENT, "foo");
+            builder.finish_node();
+            builder.token(SEMICOLON, ";");
+            builder.finish_node();
+            builder.finish()
+        };
+        let kind_at =
+            |offset| root.find_token_at_offset(offset).map(|t| t.kind());
+        assert_eq!(kind_at(0), Some(LET_KW));
+        assert_eq!(kind_at(2), Some(LET_KW));
+        assert_eq!(kind_at(3), Some(WHITESPACE));
+        assert_eq!(kind_at(4), Some(IDENT));
+        assert_eq!(kind_at(6), Some(IDENT));
+        assert_eq!(kind_at(7), Some(SEMICOLON));
+        assert_eq!(kind_at(8), None);
+    }
+
+    #[test]
+    fn validate_ranges() {
+        let mut builder = Builder::new();
+        builder.start_node(LET_ITEM);
+        builder.token(LET_KW, "let");
+        builder.token(WHITESPACE, " ");
+        builder.start_node(NAME);
+        builder.token(IDENT, "foo");
+        builder.finish_node();
+        builder.finish_node();
+        assert_eq!(builder.finish().validate_ranges(), Ok(()));
+    }
 }
@@ -1,25 +1,65 @@
-use crate::syntax::NodeOrToken;
+use crate::syntax::{Node, NodeOrToken};
 
 pub trait Visitor: Sized {
     fn visit(&mut self, node: NodeOrToken);
 }
 
-pub fn preorder<V: Visitor>(visitor: &mut V, node: impl Into<NodeOrToken>) {
+pub fn walk_preorder<V: Visitor>(
+    visitor: &mut V,
+    node: impl Into<NodeOrToken>,
+) {
     let node = node.into();
     visitor.visit(node.clone());
     for child in node.children_with_tokens() {
-        preorder(visitor, child);
+        walk_preorder(visitor, child);
     }
 }
 
-pub fn postorder<V: Visitor>(visitor: &mut V, node: impl Into<NodeOrToken>) {
+pub fn walk_postorder<V: Visitor>(
+    visitor: &mut V,
+    node: impl Into<NodeOrToken>,
+) {
     let node = node.into();
     for child in node.children_with_tokens() {
-        postorder(visitor, child);
+        walk_postorder(visitor, child);
     }
     visitor.visit(node);
 }
 
+/// Iterate over `root` and all of its descendants, parents before children.
+pub fn preorder(
+    root: impl Into<NodeOrToken>,
+) -> impl Iterator<Item = NodeOrToken> {
+    let mut stack = vec![root.into()];
+    std::iter::from_fn(move || {
+        let next = stack.pop()?;
+        let children: Vec<_> = next.children_with_tokens().collect();
+        stack.extend(children.into_iter().rev());
+        Some(next)
+    })
+}
+
+/// Iterate over `root` and all of its descendants, children before parents.
+pub fn postorder(
+    root: impl Into<NodeOrToken>,
+) -> impl Iterator<Item = NodeOrToken> {
+    let mut stack = vec![(root.into(), false)];
+    std::iter::from_fn(move || loop {
+        let (next, children_visited) = stack.pop()?;
+        if children_visited {
+            return Some(next);
+        }
+        let children: Vec<_> = next.children_with_tokens().collect();
+        stack.push((next, true));
+        stack.extend(children.into_iter().rev().map(|child| (child, false)));
+    })
+}
+
+/// Iterate over the parents of `node`, innermost first.
+pub fn ancestors(node: impl Into<NodeOrToken>) -> impl Iterator<Item = Node> {
+    std::iter::successors(node.into().parent(), Node::parent)
+}
+
 pub enum Step<N, R> {
     Continue(N),
     Terminate(R),
@@ -33,3 +73,60 @@ pub fn iterate<N, R>(mut cursor: N, mut f: impl FnMut(N) -> Step<N, R>) -> R {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::Builder;
+    use crate::SyntaxKind::{self, *};
+
+    fn let_item() -> Node {
+        let mut builder = Builder::new();
+        builder.start_node(LET_ITEM);
+        builder.token(LET_KW, "let");
+        builder.start_node(NAME);
+        builder.token(IDENT, "foo");
+        builder.finish_node();
+        builder.finish_node();
+        builder.finish()
+    }
+
+    fn kinds(iter: impl Iterator<Item = NodeOrToken>) -> Vec<SyntaxKind> {
+        iter.map(|n| n.kind()).collect()
+    }
+
+    #[test]
+    fn preorder_visits_parents_first() {
+        let kinds = kinds(preorder(let_item()));
+        assert_eq!(kinds, [LET_ITEM, LET_KW, NAME, IDENT]);
+    }
+
+    #[test]
+    fn postorder_visits_children_first() {
+        let kinds = kinds(postorder(let_item()));
+        assert_eq!(kinds, [LET_KW, IDENT, NAME, LET_ITEM]);
+    }
+
+    #[test]
+    fn ancestors_innermost_first() {
+        let ident = preorder(let_item()).last().unwrap();
+        let kinds: Vec<_> = ancestors(ident).map(|n| n.kind()).collect();
+        assert_eq!(kinds, [NAME, LET_ITEM]);
+    }
+
+    #[test]
+    fn node_or_token_accessors() {
+        let mut iter = preorder(let_item());
+        let (item, kw) = (iter.next().unwrap(), iter.next().unwrap());
+        assert!(item.is_node() && !item.is_token());
+        assert!(kw.is_token() && !kw.is_node());
+        assert_eq!(item.as_node().map(Node::kind), Some(LET_ITEM));
+        assert!(item.as_token().is_none());
+        assert_eq!(
+            kw.as_token().map(|t| t.text().to_string()),
+            Some("let".into())
+        );
+        assert!(kw.clone().into_node().is_none());
+        assert_eq!(kw.into_token().map(|t| t.kind()), Some(LET_KW));
+    }
+}
@@ -11,10 +11,12 @@ pub struct Builder<'ctx, 'm> {
     current_function: Option<ValueRef>,
     current_block: Option<Block>,
 
-    int_constants: HashMap<usize, ValueID>,
+    int_constants: HashMap<(usize, TyID), ValueID>,
     str_constants: HashMap<String, ValueID>,
+    null_constants: HashMap<TyID, ValueID>,
 
     unresolved_breaks: Vec<BreakPH>,
+    runtime_fns: HashMap<&'static str, ValueID>,
 }
 
 impl<'s, 'm> Builder<'s, 'm> {
@@ -26,7 +28,9 @@ impl<'s, 'm> Builder<'s, 'm> {
             current_block: None,
             int_constants: Default::default(),
             str_constants: Default::default(),
+            null_constants: Default::default(),
             unresolved_breaks: Default::default(),
+            runtime_fns: Default::default(),
         }
     }
 
@@ -52,6 +56,19 @@ impl<'s, 'm> Builder<'s, 'm> {
         )
     }
 
+    pub(crate) fn add_runtime_fn(&mut self, name: &'static str, val: ValueID) {
+        self.runtime_fns.insert(name, val);
+    }
+
+    pub(crate) fn runtime_fn(&self, name: &str) -> ValueRef {
+        ValueRef::new(
+            *self
+                .runtime_fns
+                .get(name)
+                .expect(&format!("runtime fn `{name}` not declared")),
+        )
+    }
+
     pub fn enter_function(&mut self, bir: bir::ID) {
         self.current_function = Some(self.sess.val_from_bir(&bir));
     }
@@ -61,6 +78,17 @@ impl<'s, 'm> Builder<'s, 'm> {
         debug_assert_eq!(fn_, Some(self.sess.val_from_bir(&bir)));
     }
 
+    /// Enter a function that has no BIR counterpart, e.g. a synthesized
+    /// test harness `main`.
+    pub(crate) fn enter_synthetic_function(&mut self, val: ValueID) {
+        self.current_function = Some(ValueRef::new(val));
+    }
+
+    pub(crate) fn exit_synthetic_function(&mut self, val: ValueID) {
+        let fn_ = self.current_function.take();
+        debug_assert_eq!(fn_, Some(ValueRef::new(val)));
+    }
+
     pub fn void_(&self) -> ValueRef {
         let val = ValueRef::new(self.ctx().as_mod().void);
         debug_assert_eq!(val.kind(self.ctx()), ValueKind::Void);
@@ -99,7 +127,7 @@ impl<'s, 'm> Builder<'s, 'm> {
     pub fn new_int_constant(&mut self, n: usize, ty: TyID) -> ValueRef {
         let id = *self
             .int_constants
-            .entry(n)
+            .entry((n, ty))
             .or_insert_with(|| self.module.add_int_constant(n, ty));
         ValueRef::new(id)
     }
@@ -113,6 +141,14 @@ impl<'s, 'm> Builder<'s, 'm> {
         ValueRef::new(id)
     }
 
+    pub fn new_null_constant(&mut self, ty: TyID) -> ValueRef {
+        let id = *self
+            .null_constants
+            .entry(ty)
+            .or_insert_with(|| self.module.add_null_constant(ty));
+        ValueRef::new(id)
+    }
+
     fn new_block_impl<'a, S: Into<String>>(
         &mut self,
         label: Option<S>,
@@ -180,9 +216,13 @@ impl<'s, 'm> Builder<'s, 'm> {
     }
 
     fn assert_rval_expr(&self, val: ValueRef) {
+        // A function is only an rvalue when passed as a function pointer
         debug_assert_matches!(
             val.kind(self.ctx()),
-            ValueKind::Param | ValueKind::Constant(..) | ValueKind::Inst
+            ValueKind::Param
+                | ValueKind::Constant(..)
+                | ValueKind::Inst
+                | ValueKind::Function
         );
     }
 
@@ -217,6 +257,27 @@ impl<'s, 'm> Builder<'s, 'm> {
         self.new_inst(InstKind::Cast).with_rval(val.dup())
     }
 
+    pub fn new_zext(&mut self, val: ValueRef) -> InstBuilder<'_, 's, 'm> {
+        self.assert_rval_expr(val);
+        self.new_inst(InstKind::ZExt).with_rval(val.dup())
+    }
+
+    /// A phi merging each `(value, block)` in `incoming`, where `block` is a
+    /// predecessor of the current block. Phis must come before any other
+    /// instruction in their block.
+    pub fn new_phi(
+        &mut self,
+        incoming: &[(ValueRef, Block)],
+    ) -> InstBuilder<'_, 's, 'm> {
+        let mut rvals = Vec::with_capacity(incoming.len() * 2);
+        for (val, block) in incoming {
+            self.assert_rval_expr(*val);
+            rvals.push(val.dup());
+            rvals.push(block.val(self.fn_()).dup());
+        }
+        self.new_inst(InstKind::Phi).with_rvals(&rvals)
+    }
+
     pub fn new_var(&mut self) -> InstBuilder<'_, 's, 'm> {
         self.new_inst(InstKind::Var)
     }
@@ -237,6 +298,20 @@ impl<'s, 'm> Builder<'s, 'm> {
             .with_rval(val.dup())
     }
 
+    /// Copy the `size` bytes at `src` to `dst`, both of which must be
+    /// addressable, i.e. vars.
+    pub fn new_memcpy(
+        &mut self,
+        dst: ValueRef,
+        src: ValueRef,
+    ) -> InstBuilder<'_, 's, 'm> {
+        self.assert_lval_expr(dst);
+        self.assert_lval_expr(src);
+        self.new_inst(InstKind::Memcpy)
+            .with_rvals(&[dst.dup(), src.dup()])
+            .void_ty()
+    }
+
     pub fn new_subscript(
         &mut self,
         base: ValueRef,
@@ -249,6 +324,11 @@ impl<'s, 'm> Builder<'s, 'm> {
             .add_rvals(offsets.into_iter().map(|op| op.dup()))
     }
 
+    pub fn new_address_of(&mut self, val: ValueRef) -> InstBuilder<'_, 's, 'm> {
+        self.assert_lval_expr(val);
+        self.new_inst(InstKind::AddressOf).with_rval(val.dup())
+    }
+
     pub fn new_get_field(
         &mut self,
         base: ValueRef,
@@ -265,10 +345,11 @@ impl<'s, 'm> Builder<'s, 'm> {
         &mut self,
         lhs: ValueRef,
         rhs: ValueRef,
+        overflow: OverflowMode,
     ) -> InstBuilder<'_, 's, 'm> {
         self.assert_rval_expr(lhs);
         self.assert_rval_expr(rhs);
-        self.new_inst(InstKind::Add)
+        self.new_inst(InstKind::Add { overflow })
             .with_rvals(&[lhs.dup(), rhs.dup()])
     }
 
@@ -276,10 +357,11 @@ impl<'s, 'm> Builder<'s, 'm> {
         &mut self,
         lhs: ValueRef,
         rhs: ValueRef,
+        overflow: OverflowMode,
     ) -> InstBuilder<'_, 's, 'm> {
         self.assert_rval_expr(lhs);
         self.assert_rval_expr(rhs);
-        self.new_inst(InstKind::Sub)
+        self.new_inst(InstKind::Sub { overflow })
             .with_rvals(&[lhs.dup(), rhs.dup()])
     }
 
@@ -287,10 +369,11 @@ impl<'s, 'm> Builder<'s, 'm> {
         &mut self,
         lhs: ValueRef,
         rhs: ValueRef,
+        overflow: OverflowMode,
     ) -> InstBuilder<'_, 's, 'm> {
         self.assert_rval_expr(lhs);
         self.assert_rval_expr(rhs);
-        self.new_inst(InstKind::Mul)
+        self.new_inst(InstKind::Mul { overflow })
             .with_rvals(&[lhs.dup(), rhs.dup()])
     }
 
@@ -322,13 +405,33 @@ impl<'s, 'm> Builder<'s, 'm> {
         fn_: ValueRef,
         ops: Vec<ValueRef>,
     ) -> InstBuilder<'_, 's, 'm> {
-        debug_assert_eq!(fn_.kind(self.ctx()), ValueKind::Function);
+        // Either a function, or a pointer to one (e.g. a fn-typed param)
+        debug_assert!(matches!(fn_.ty(self.ctx()).kind, TyKind::Fn { .. }));
         self.assert_rval_exprs(&ops);
         self.new_inst(InstKind::Call)
             .with_rval(fn_.dup())
             .add_rvals(ops.into_iter().map(|op| op.dup()))
     }
 
+    pub fn new_for_each(
+        &mut self,
+        arr: ValueRef,
+        len: ValueRef,
+        callback: ValueRef,
+    ) -> InstBuilder<'_, 's, 'm> {
+        self.assert_rval_expr(arr);
+        self.assert_rval_expr(len);
+        debug_assert!(matches!(
+            callback.ty(self.ctx()).kind,
+            TyKind::Fn { .. }
+        ));
+        self.new_inst(InstKind::ForEach).with_rvals(&[
+            arr.dup(),
+            len.dup(),
+            callback.dup(),
+        ])
+    }
+
     pub fn new_jump_marker(&mut self) -> Marker {
         let void_ = self.void_();
         let val = self
@@ -378,6 +481,43 @@ impl<'s, 'm> Builder<'s, 'm> {
     }
 }
 
+// Shorthands for common sequences of instructions.
+impl Builder<'_, '_> {
+    /// A var of the struct type `ty`, left uninitialized.
+    pub fn build_struct_alloc(&mut self, ty: TyID) -> ValueRef {
+        debug_assert_eq!(ty.get(self.ctx()).kind, TyKind::Struct);
+        self.new_var().of_ty(ty).with_new_lval().build()
+    }
+
+    /// The value of the `field_idx`-th member of `base`, a struct or a
+    /// pointer to one, loaded into `lval` or else a new temporary.
+    pub fn build_field_load(
+        &mut self,
+        base: ValueRef,
+        field_idx: usize,
+        lval: Option<ValueRef>,
+    ) -> ValueRef {
+        let field_ty = {
+            let mut ty = base.ty(self.ctx());
+            if ty.kind == TyKind::Pointer {
+                ty = ty.as_ptr_ty().pointee(self.ctx());
+            }
+            ty.as_struct_ty(self.ctx()).members[field_idx]
+        };
+        let idx_ty = self.module.types.get_int(32);
+        let idx = self.new_int_constant(field_idx, idx_ty);
+        let addr = self
+            .new_get_field(base, &[idx])
+            .of_ty(field_ty)
+            .with_new_lval()
+            .build();
+        self.new_load(addr)
+            .of_ty(field_ty)
+            .with_lval_or_new(lval)
+            .build()
+    }
+}
+
 #[derive(Debug)]
 struct BreakPH {
     label: String,
@@ -390,6 +530,7 @@ pub struct Session<'bir, 'sema> {
     pub(crate) sema: &'sema sema::Map,
     pub(crate) ty_mapping: TyMapping,
     pub(crate) value_mapping: ValueMapping,
+    pub(crate) overflow_mode: OverflowMode,
 }
 
 impl<'bir, 'sema> Session<'bir, 'sema> {
@@ -399,6 +540,7 @@ impl<'bir, 'sema> Session<'bir, 'sema> {
             sema,
             ty_mapping: TyMapping::default(),
             value_mapping: ValueMapping::default(),
+            overflow_mode: OverflowMode::default(),
         }
     }
 }
@@ -5,10 +5,13 @@ mod types;
 pub use types::*;
 
 mod translate;
-pub use translate::translate;
+pub use translate::{translate, translate_test_harness};
 
 mod printers;
 pub use printers::print;
 
+mod validate;
+pub use validate::ValidationError;
+
 pub mod pass;
 pub mod passes;
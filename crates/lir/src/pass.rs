@@ -1,5 +1,9 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+
 use crate::types::*;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PassStatus {
     NoChange,
     Changed,
@@ -15,14 +19,30 @@ pub trait FunctionPass {
     fn should_run_on(&self, _f: &Function) -> bool {
         true
     }
+    /// Passes that, if they change the IR, should cause this pass to run
+    /// again (e.g. DCE after jump threading leaves dead instructions around).
+    fn requires_rerun_after(&self) -> Vec<TypeId> {
+        Vec::new()
+    }
 }
 
 pub struct PassContext<'c> {
     pub types: &'c TyContext,
+    int_constants: &'c HashMap<ValueID, usize>,
+}
+
+impl PassContext<'_> {
+    pub fn int_constant(&self, val: &ValueID) -> Option<usize> {
+        self.int_constants.get(val).copied()
+    }
 }
 
-pub fn run_pass(m: &mut Module, p: &mut dyn FunctionPass) {
-    let ctx = PassContext { types: &m.types };
+pub fn run_pass(m: &mut Module, p: &mut dyn FunctionPass) -> PassStatus {
+    let mut status = PassStatus::NoChange;
+    let ctx = PassContext {
+        types: &m.types,
+        int_constants: &m.int_constants,
+    };
     for idx in 0..m.functions.len() {
         {
             let f = &m.functions[idx];
@@ -33,6 +53,7 @@ pub fn run_pass(m: &mut Module, p: &mut dyn FunctionPass) {
         }
         match p.visit_function(&mut m.functions[idx], &ctx) {
             PassStatus::Changed => {
+                status = PassStatus::Changed;
                 let f = &m.functions[idx];
                 eprintln!();
                 crate::printers::print_fn(m, f).unwrap();
@@ -40,6 +61,7 @@ pub fn run_pass(m: &mut Module, p: &mut dyn FunctionPass) {
             PassStatus::NoChange => eprintln!("  No change"),
         }
     }
+    status
 }
 
 pub fn run_passes(m: &mut Module, passes: &mut [&mut dyn FunctionPass]) {
@@ -47,3 +69,154 @@ pub fn run_passes(m: &mut Module, passes: &mut [&mut dyn FunctionPass]) {
         run_pass(m, *pass);
     }
 }
+
+struct PipelineEntry {
+    id: TypeId,
+    pass: Box<dyn FunctionPass>,
+}
+
+/// An ordered list of passes. Passes are run in order, and any pass whose
+/// `requires_rerun_after` names a pass that changed the IR is scheduled to
+/// run again, until nothing changes (or we give up).
+///
+/// In debug builds, the module is validated after every pass, and then any
+/// sanitizing passes are run over it.
+#[derive(Default)]
+pub struct PassPipeline {
+    entries: Vec<PipelineEntry>,
+    sanitizers: Vec<Box<dyn FunctionPass>>,
+}
+
+impl PassPipeline {
+    const MAX_ITERATIONS: usize = 8;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The canonical pass ordering.
+    // TODO: mem2reg, CSE and constant folding slot in between jump threading
+    // and DCE, and after DCE, respectively, once they exist.
+    pub fn standard() -> Self {
+        Self::new()
+            .add(crate::passes::SwitchLowering)
+            .add(crate::passes::JumpThreading)
+            .add(crate::passes::DCE)
+    }
+
+    pub fn add<P: FunctionPass + 'static>(mut self, pass: P) -> Self {
+        self.entries.push(PipelineEntry {
+            id: TypeId::of::<P>(),
+            pass: Box::new(pass),
+        });
+        self
+    }
+
+    /// Add a pass that checks the IR rather than changing it, to be run
+    /// after every other pass in debug builds.
+    pub fn add_sanitizing_pass<P: FunctionPass + 'static>(
+        mut self,
+        pass: P,
+    ) -> Self {
+        self.sanitizers.push(Box::new(pass));
+        self
+    }
+
+    pub fn run(&mut self, m: &mut Module) -> PassStatus {
+        let mut status = PassStatus::NoChange;
+        let deps: Vec<_> = self
+            .entries
+            .iter()
+            .map(|e| e.pass.requires_rerun_after())
+            .collect();
+        let mut to_run = vec![true; self.entries.len()];
+        for _ in 0..Self::MAX_ITERATIONS {
+            if !to_run.contains(&true) {
+                break;
+            }
+            let mut next = vec![false; self.entries.len()];
+            for idx in 0..self.entries.len() {
+                if !to_run[idx] {
+                    continue;
+                }
+                let entry = &mut self.entries[idx];
+                let pass_status = run_pass(m, entry.pass.as_mut());
+                debug_assert_eq!(
+                    m.validate(),
+                    vec![],
+                    "invalid IR after {}",
+                    entry.pass.name()
+                );
+                if cfg!(debug_assertions) {
+                    for sanitizer in &mut self.sanitizers {
+                        let sanitizer_status = run_pass(m, sanitizer.as_mut());
+                        assert_eq!(
+                            sanitizer_status,
+                            PassStatus::NoChange,
+                            "{} changed the IR after {}",
+                            sanitizer.name(),
+                            entry.pass.name()
+                        );
+                    }
+                }
+                if pass_status == PassStatus::NoChange {
+                    continue;
+                }
+                status = PassStatus::Changed;
+                let changed = entry.id;
+                for (dep_idx, deps) in deps.iter().enumerate() {
+                    if dep_idx == idx || !deps.contains(&changed) {
+                        continue;
+                    }
+                    // Passes later in this sweep pick up the change anyway;
+                    // earlier ones have to wait for the next sweep.
+                    if dep_idx > idx {
+                        to_run[dep_idx] = true;
+                    } else {
+                        next[dep_idx] = true;
+                    }
+                }
+            }
+            to_run = next;
+        }
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::passes::{SwitchLowering, DCE};
+    use crate::translate::tests::lower;
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn sanitizing_passes_run_after_every_pass() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        struct CountRuns(Rc<Cell<usize>>);
+        impl FunctionPass for CountRuns {
+            fn name(&self) -> &'static str {
+                "count-runs"
+            }
+            fn visit_function(
+                &mut self,
+                _: &mut Function,
+                _: &PassContext,
+            ) -> PassStatus {
+                self.0.set(self.0.get() + 1);
+                PassStatus::NoChange
+            }
+        }
+
+        let mut m = lower("fn main() -> i32 { 0 }");
+        let runs = Rc::new(Cell::new(0));
+        PassPipeline::new()
+            .add(SwitchLowering)
+            .add(DCE)
+            .add_sanitizing_pass(CountRuns(runs.clone()))
+            .run(&mut m);
+        assert_eq!(runs.get(), 2);
+    }
+}
@@ -1,13 +1,19 @@
-use std::collections::HashMap;
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
 
 use crate::pass::*;
 use crate::types::*;
 
+mod dfe;
+
 pub struct DCE;
 impl FunctionPass for DCE {
     fn name(&self) -> &'static str {
         "DCE"
     }
+    fn requires_rerun_after(&self) -> Vec<TypeId> {
+        vec![TypeId::of::<JumpThreading>()]
+    }
     fn visit_function(
         &mut self,
         f: &mut Function,
@@ -15,6 +21,21 @@ impl FunctionPass for DCE {
     ) -> PassStatus {
         let mut status = PassStatus::NoChange;
 
+        let unreachable = unreachable_blocks(f);
+        if !unreachable.is_empty() {
+            for block in &unreachable {
+                let insts: Vec<_> =
+                    block.insts(&*f).map(|i| i.val.id).collect();
+                for id in insts {
+                    f.remove_inst(&id);
+                }
+            }
+            let vals: Vec<_> =
+                unreachable.iter().map(|b| b.val(f).id).collect();
+            f.remove_blocks(&vals);
+            status = PassStatus::Changed;
+        }
+
         let mut dead_insts = Vec::new();
         let mut change = true;
         while change {
@@ -35,7 +56,7 @@ impl FunctionPass for DCE {
             }
 
             for i in &dead_insts {
-                println!("Removing '{}'", i.1.repr(&*f));
+                eprintln!("Removing '{}'", i.1.repr(&*f));
                 f.remove_inst(&i.0);
                 change = true;
             }
@@ -46,6 +67,16 @@ impl FunctionPass for DCE {
     }
 }
 
+fn unreachable_blocks(f: &Function) -> Vec<Block> {
+    let mut reachable = HashSet::new();
+    f.visit_blocks_in_rpo(|block| {
+        reachable.insert(block);
+    });
+    f.blocks()
+        .filter(|b| !reachable.contains(b) && !f.blocks.is_unlinked(&b.0))
+        .collect()
+}
+
 pub struct JumpThreading;
 impl FunctionPass for JumpThreading {
     fn name(&self) -> &'static str {
@@ -130,3 +161,239 @@ impl FunctionPass for JumpThreading {
         true
     }
 }
+
+/// Lowers chains of `br (x == K), ...` on the same scrutinee into a single
+/// `switch`, provided the case values are dense enough to be worth it.
+pub struct SwitchLowering;
+impl SwitchLowering {
+    const MIN_CASES: usize = 2;
+    const MAX_RANGE: usize = 64;
+}
+impl FunctionPass for SwitchLowering {
+    fn name(&self) -> &'static str {
+        "Switch Lowering"
+    }
+    fn visit_function(
+        &mut self,
+        f: &mut Function,
+        ctx: &PassContext,
+    ) -> PassStatus {
+        #[derive(Debug)]
+        struct Chain {
+            head: Block,
+            scrutinee: ValueRef,
+            cases: Vec<(ValueRef, Block)>,
+            default: Block,
+            absorbed: Vec<Block>,
+        }
+
+        let mut chains = Vec::new();
+        let mut absorbed = HashSet::new();
+        f.visit_blocks_in_rpo(|block| {
+            if absorbed.contains(&block) {
+                return;
+            }
+            let Some((scrutinee, case, then, mut alt)) =
+                eq_branch(&*f, ctx, block)
+            else {
+                return;
+            };
+            let mut chain = Chain {
+                head: block,
+                scrutinee,
+                cases: vec![(case, then)],
+                default: alt,
+                absorbed: Vec::new(),
+            };
+            while let Some((next, case, then, next_alt)) =
+                eq_branch(&*f, ctx, alt)
+            {
+                let is_bare_test = alt.insts(&*f).count() == 2
+                    && alt.num_predecessors(&*f) == 1;
+                let is_new_case = chain.cases.iter().all(|(c, _)| {
+                    ctx.int_constant(c) != ctx.int_constant(&case)
+                });
+                if next.id != scrutinee.id || !is_bare_test || !is_new_case {
+                    break;
+                }
+                chain.cases.push((case, then));
+                chain.absorbed.push(alt);
+                alt = next_alt;
+                chain.default = alt;
+            }
+            if chain.cases.len() < Self::MIN_CASES {
+                return;
+            }
+            let values =
+                chain.cases.iter().filter_map(|(c, _)| ctx.int_constant(c));
+            let (min, max) = values.fold((usize::MAX, 0), |(min, max), v| {
+                (min.min(v), max.max(v))
+            });
+            if max - min >= Self::MAX_RANGE {
+                return;
+            }
+            absorbed.extend(chain.absorbed.iter().copied());
+            chains.push(chain);
+        });
+
+        if chains.is_empty() {
+            return PassStatus::NoChange;
+        }
+
+        for chain in chains {
+            let br = chain.head.terminator(f);
+            let void_ty = f.locals.ty(&br.id);
+            f.remove_inst(&br.id);
+
+            for block in &chain.absorbed {
+                let insts: Vec<_> =
+                    block.insts(&*f).map(|i| i.val.id).collect();
+                for id in insts {
+                    f.remove_inst(&id);
+                }
+            }
+            let absorbed: Vec<_> =
+                chain.absorbed.iter().map(|b| b.val(f).id).collect();
+            f.remove_blocks(&absorbed);
+
+            let mut rvals =
+                vec![chain.scrutinee.dup(), chain.default.val(f).dup()];
+            for (case, target) in &chain.cases {
+                rvals.push(case.dup());
+                rvals.push(target.val(f).dup());
+            }
+            let head = chain.head;
+            f.add_inst(InstKind::Switch, void_ty, head, None, rvals, None);
+
+            let targets = chain.cases.iter().map(|(_, target)| *target);
+            for target in targets.chain([chain.default]) {
+                if !chain.head.successors(&*f).any(|succ| succ == target) {
+                    f.add_block_edge(chain.head, target);
+                }
+            }
+            log::debug!(
+                "Lowering {} compares in {} to a switch",
+                chain.cases.len(),
+                chain.head.repr(f)
+            );
+        }
+
+        PassStatus::Changed
+    }
+}
+
+/// If `block` ends in `br (x == K), then, alt` for some integer constant
+/// `K`, returns `(x, K, then, alt)`.
+fn eq_branch(
+    f: &Function,
+    ctx: &PassContext,
+    block: Block,
+) -> Option<(ValueRef, ValueRef, Block, Block)> {
+    let br = block.terminator(f).inst(f)?;
+    if br.kind != InstKind::Branch {
+        return None;
+    }
+    let cond = br.rvals[0].id;
+    let cmp = block
+        .insts(f)
+        .find(|i| i.lval.map(|lval| lval.id) == Some(cond))?;
+    if cmp.kind != (InstKind::Cmp { kind: CmpKind::Eq }) {
+        return None;
+    }
+    let (scrutinee, case) = (cmp.rvals[0], cmp.rvals[1]);
+    ctx.int_constant(&case.id)?;
+    Some((scrutinee, case, br.rvals[1].block(f), br.rvals[2].block(f)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::translate::tests::lower;
+
+    #[test]
+    fn dce_removes_unreachable_blocks() {
+        let mut m = lower(
+            "fn main() -> i32 {
+                loop { return 1; }
+                let x: i32 = 2;
+                x
+            }",
+        );
+        // entry, loop body, the block after `return`, and the loop exit
+        assert_eq!(m.functions[0].num_blocks(), 4);
+        run_pass(&mut m, &mut DCE);
+        // only the entry and the loop body (holding the `return`) are left
+        let f = &m.functions[0];
+        assert_eq!(f.num_blocks(), 2);
+        assert!(unreachable_blocks(f).is_empty());
+    }
+
+    #[test]
+    fn switch_lowering_merges_compare_chains() {
+        let mut m = lower(
+            "fn main() -> i32 {
+                let x: i32 = 2;
+                let y: i32 = 0;
+                if x == 1 { y = 10; } else {
+                    if x == 2 { y = 20; } else {
+                        if x == 3 { y = 30; }
+                    }
+                }
+                y
+            }",
+        );
+        let count = |m: &Module, kind: InstKind| {
+            m.functions[0]
+                .insts
+                .values()
+                .filter(|i| i.kind == kind)
+                .count()
+        };
+        assert_eq!(count(&m, InstKind::Branch), 3);
+        assert_eq!(run_pass(&mut m, &mut SwitchLowering), PassStatus::Changed);
+        assert_eq!(count(&m, InstKind::Branch), 0);
+        assert_eq!(count(&m, InstKind::Switch), 1);
+    }
+
+    #[test]
+    fn rpo_visits_a_predecessor_first() {
+        let m = lower(
+            "fn f() -> i32 {
+                let i: i32 = 0;
+                while i < 10 {
+                    if i == 5 { return i; }
+                    i = i + 1;
+                }
+                loop { return i; }
+                i
+            }",
+        );
+        let f = &m.functions[0];
+        let mut rpo = Vec::new();
+        f.visit_blocks_in_rpo(|block| rpo.push(block));
+        // Unreachable blocks aren't visited, so they have no position
+        let position = |block| rpo.iter().position(|&b| b == block);
+
+        assert_eq!(rpo[0], f.entry_block());
+        for &block in &rpo[1..] {
+            let first_pred = block.predecessors(f).filter_map(position).min();
+            assert!(first_pred.unwrap() < position(block).unwrap());
+        }
+        // The `while` loop's back edge goes to a block visited earlier
+        let has_back_edge = rpo.iter().any(|&block| {
+            block
+                .successors(f)
+                .any(|succ| position(succ) <= position(block))
+        });
+        assert!(has_back_edge);
+
+        // Nothing after the `loop` is reachable, so it isn't visited
+        let unreachable = unreachable_blocks(f);
+        assert!(!unreachable.is_empty());
+        assert!(unreachable.iter().all(|block| !rpo.contains(block)));
+
+        // The early return, and the return in the `loop`
+        let exits = f.exit_blocks().filter(|block| rpo.contains(block));
+        assert_eq!(exits.count(), 2);
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::HashSet;
+
+use crate::pass::PassStatus;
+use crate::types::*;
+
+impl Module {
+    /// Remove every internal function that can't be reached from an exported
+    /// one. Functions whose address is taken (e.g. a `for_each` callback) are
+    /// treated as reachable, the same as if they were called.
+    pub fn dead_function_elimination(&mut self) -> PassStatus {
+        let mut live = HashSet::new();
+        let mut worklist: Vec<_> = self
+            .functions
+            .iter()
+            .filter(|f| !f.internal)
+            .map(|f| f.id)
+            .collect();
+        while let Some(id) = worklist.pop() {
+            if !live.insert(id) {
+                continue;
+            }
+            let f = self.fn_(&id);
+            for inst in f.insts.values() {
+                worklist.extend(
+                    inst.rvals
+                        .iter()
+                        .map(|rval| rval.id)
+                        .filter(|val| self.vals_to_fns.contains_key(val)),
+                );
+            }
+        }
+
+        if live.len() == self.functions.len() {
+            return PassStatus::NoChange;
+        }
+        self.functions.retain(|f| live.contains(&f.id));
+        self.vals_to_fns = self
+            .functions
+            .iter()
+            .enumerate()
+            .map(|(idx, f)| (f.id, idx))
+            .collect();
+        PassStatus::Changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::translate::tests::lower;
+
+    #[test]
+    fn dfe_removes_unreachable_internal_fns() {
+        let mut m = lower(
+            "fn main() -> i32 { helper() }
+            fn helper() -> i32 { 1 }
+            fn dead() -> i32 { dead() }",
+        );
+        // `helper` and `dead` both have callers, so neither is exported
+        assert_eq!(m.functions.iter().filter(|f| f.internal).count(), 2);
+        assert_eq!(m.dead_function_elimination(), PassStatus::Changed);
+        let idents: Vec<_> = m.functions.iter().map(|f| &f.ident).collect();
+        assert_eq!(idents, ["helper", "main"]);
+        assert_eq!(m.validate(), vec![]);
+        assert_eq!(m.dead_function_elimination(), PassStatus::NoChange);
+    }
+
+    #[test]
+    fn dfe_keeps_all_fns_once_exported() {
+        let mut m = lower(
+            "fn add(a: i64, b: i64) -> i64 { a + b }
+            fn add3(a: i64, b: i64, c: i64) -> i64 { add(add(a, b), c) }
+            fn unused() -> i64 { unused() }",
+        );
+        m.export_all();
+        assert!(m.functions.iter().all(|f| !f.internal));
+        assert_eq!(m.dead_function_elimination(), PassStatus::NoChange);
+        assert_eq!(m.functions.len(), 3);
+    }
+}
@@ -249,7 +249,16 @@ pub fn write_inst(
             writeln!(w)?;
             return Ok(());
         }
-        InstKind::Add => write!(w, "add")?,
+        InstKind::ZExt => {
+            let ty = inst.lval().ty(ctx).repr(ctx);
+            write!(w, "@zext.{} ", ty)?;
+            write_val(w, ctx, &inst.rvals[0])?;
+            writeln!(w)?;
+            return Ok(());
+        }
+        InstKind::Add { overflow } => {
+            write!(w, "add{}", overflow_suffix(overflow))?
+        }
         InstKind::Return => write!(w, "return")?,
         InstKind::Cmp { kind } => {
             write!(w, "(")?;
@@ -269,13 +278,22 @@ pub fn write_inst(
         }
         InstKind::Copy => write!(w, "copy")?,
         InstKind::Nop => write!(w, "nop")?,
+        InstKind::ForEach => write!(w, "for_each")?,
         InstKind::Jmp => write!(w, "jmp")?,
         InstKind::Branch => write!(w, "br")?,
-        InstKind::Sub => write!(w, "sub")?,
-        InstKind::Mul => write!(w, "mul")?,
+        InstKind::Switch => write!(w, "switch")?,
+        InstKind::Phi => write!(w, "phi")?,
+        InstKind::Memcpy => write!(w, "memcpy")?,
+        InstKind::Sub { overflow } => {
+            write!(w, "sub{}", overflow_suffix(overflow))?
+        }
+        InstKind::Mul { overflow } => {
+            write!(w, "mul{}", overflow_suffix(overflow))?
+        }
         InstKind::Div => write!(w, "div")?,
         InstKind::Subscript => write!(w, "subscript")?,
         InstKind::GetField => write!(w, "field")?,
+        InstKind::AddressOf => write!(w, "addr")?,
     };
 
     write!(w, " ")?;
@@ -301,3 +319,11 @@ pub fn write_val(
     }
     Ok(())
 }
+
+fn overflow_suffix(overflow: OverflowMode) -> &'static str {
+    match overflow {
+        OverflowMode::Checked => "",
+        OverflowMode::Wrapping => ".wrap",
+        OverflowMode::Saturating => ".sat",
+    }
+}
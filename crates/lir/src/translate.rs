@@ -4,14 +4,43 @@ use crate::builder::Session;
 use crate::types::*;
 use crate::Builder;
 
+/// Structs larger than this many bytes (two words) are copied with a
+/// `memcpy`, rather than loaded into registers and stored back.
+const MEMCPY_THRESHOLD: usize = 16;
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum ValueCategory {
     LVal,
     RVal,
 }
 
-pub fn translate(bir: &bir::Map, sema: &sema::Map) -> Module {
+/// Translate the module, lowering `+`, `-` and `*` with `overflow_mode`.
+pub fn translate(
+    bir: &bir::Map,
+    sema: &sema::Map,
+    overflow_mode: OverflowMode,
+) -> Module {
+    translate_impl(bir, sema, overflow_mode, false)
+}
+
+/// Translate the module with a synthesized `main` that calls each `#[test]`
+/// function in turn, in place of the user's `main`.
+pub fn translate_test_harness(
+    bir: &bir::Map,
+    sema: &sema::Map,
+    overflow_mode: OverflowMode,
+) -> Module {
+    translate_impl(bir, sema, overflow_mode, true)
+}
+
+fn translate_impl(
+    bir: &bir::Map,
+    sema: &sema::Map,
+    overflow_mode: OverflowMode,
+    test_harness: bool,
+) -> Module {
     let mut sess = Session::new(bir, sema);
+    sess.overflow_mode = overflow_mode;
     let mut module = Module::new();
     let mut builder = Builder::new(&mut sess, &mut module);
 
@@ -37,63 +66,180 @@ pub fn translate(bir: &bir::Map, sema: &sema::Map) -> Module {
 
     let mut fns_seen: HashMap<String, ValueID> = HashMap::new();
     let mut fns_with_unprocessed_bodies = Vec::new();
+    let mut test_fns = Vec::new();
+
+    // Declare callees first, so their types are known at each call site
+    let fns = bir.topological_sort_functions(|id| {
+        let sema_fn = sema.bir_to_id(id).unwrap();
+        sema.callees(sema_fn)
+            .filter_map(|callee| sema.bir(callee))
+            .collect::<Vec<_>>()
+    });
+    for bir_f in fns.iter().map(|id| bir.fn_(id)) {
+        let sema = builder.sess.bir_to_sema(&bir_f.id);
+        let sema_fn = builder.sess.sema.fn_(sema).unwrap();
+
+        let full_name = get_full_name(bir_f);
+        if test_harness && full_name == "main" {
+            continue;
+        }
 
-    for mod_ in bir.modules() {
-        for bir_f in mod_.functions(builder.sess.bir) {
-            let sema = builder.sess.bir_to_sema(&bir_f.id);
-            let sema_fn = builder.sess.sema.fn_(sema).unwrap();
-
-            let full_name = get_full_name(bir_f);
+        if let Some(val) = fns_seen.get(&full_name) {
+            builder.sess.value_mapping.insert(sema, *val);
+            continue;
+        }
 
-            if let Some(val) = fns_seen.get(&full_name) {
-                builder.sess.value_mapping.insert(sema, *val);
-                continue;
-            }
+        let return_ty = builder.sess.sema_to_ty(&sema_fn.return_ty);
+        let mut param_names = Vec::new();
+        let mut param_tys = Vec::new();
+        for param in bir_f.parameters.iter() {
+            let sema = builder.sess.bir_to_sema(param);
+            param_tys.push(builder.sess.ty_from_bir(param));
+            param_names
+                .push(builder.sess.sema.name(sema).unwrap().ident.clone());
+        }
+        let is_var_args = sema_fn.is_var_args(builder.sess.sema);
+        let internal =
+            builder.sess.sema.num_callers(sema) > 0 && bir_f.has_body();
+        let val = builder.new_function(
+            full_name.clone(),
+            param_names,
+            return_ty,
+            param_tys,
+            is_var_args,
+            internal,
+        );
+        builder.sess.value_mapping.insert(sema, val);
+        fns_seen.insert(full_name.clone(), val);
 
-            let return_ty = builder.sess.sema_to_ty(&sema_fn.return_ty);
-            let mut param_names = Vec::new();
-            let mut param_tys = Vec::new();
-            for param in bir_f.parameters.iter() {
-                let sema = builder.sess.bir_to_sema(param);
-                param_tys.push(builder.sess.ty_from_bir(param));
-                param_names
-                    .push(builder.sess.sema.name(sema).unwrap().ident.clone());
-            }
-            let is_var_args = sema_fn.is_var_args(builder.sess.sema);
-            let internal =
-                builder.sess.sema.num_callers(sema) > 0 && bir_f.body.is_some();
-            let val = builder.new_function(
-                full_name.clone(),
-                param_names,
-                return_ty,
-                param_tys,
-                is_var_args,
-                internal,
-            );
-            builder.sess.value_mapping.insert(sema, val);
-            fns_seen.insert(full_name, val);
-
-            let f = builder.module.fn_(&val);
-            for (idx, param) in bir_f.parameters.iter().enumerate() {
-                let param = builder.sess.bir_to_sema(param);
-                builder
-                    .sess
-                    .value_mapping
-                    .insert(param, f.nth_param(idx).val);
-            }
-            if bir_f.body.is_some() {
-                fns_with_unprocessed_bodies.push(bir_f.id);
-            }
+        let f = builder.module.fn_(&val);
+        for (idx, param) in bir_f.parameters.iter().enumerate() {
+            let param = builder.sess.bir_to_sema(param);
+            builder
+                .sess
+                .value_mapping
+                .insert(param, f.nth_param(idx).val);
+        }
+        if bir_f.has_body() {
+            fns_with_unprocessed_bodies.push(bir_f.id);
+        }
+        if bir_f.is_test {
+            test_fns.push((full_name, val));
         }
     }
 
+    if sema.num_callers(sema.assert_fn()) > 0 {
+        declare_assert_runtime_fns(&mut builder, &fns_seen);
+    }
+
     for id in fns_with_unprocessed_bodies {
         fn_body(&mut builder, bir.fn_(&id));
     }
 
+    if test_harness {
+        test_harness_main(&mut builder, &fns_seen, &test_fns);
+    }
+
     module
 }
 
+// Synthesizes:
+//
+//   fn main() -> i32 {
+//     test_a()
+//     puts("test test_a ... ok")
+//     ...
+//     ret 0
+//   }
+fn test_harness_main(
+    builder: &mut Builder,
+    fns_seen: &HashMap<String, ValueID>,
+    test_fns: &[(String, ValueID)],
+) {
+    let i32_ty = builder.module.types.get_int(32);
+    let str_ty = builder.module.types.get_str();
+    let void_ty = builder.void_ty();
+    declare_runtime_fn(
+        builder,
+        fns_seen,
+        "puts",
+        &["msg"],
+        i32_ty,
+        vec![str_ty],
+        false,
+    );
+
+    let main = builder.new_function(
+        "main",
+        Vec::<&str>::new(),
+        i32_ty,
+        vec![],
+        false,
+        false,
+    );
+    builder.enter_synthetic_function(main);
+    builder.new_labeled_block(".entry");
+    let puts = builder.runtime_fn("puts");
+    for (name, f) in test_fns {
+        builder
+            .new_call(ValueRef::new(*f), vec![])
+            .of_ty(void_ty)
+            .build();
+        let msg = builder.new_str_constant(format!("test {name} ... ok"));
+        builder
+            .new_call(puts, vec![msg])
+            .of_ty(i32_ty)
+            .with_new_lval()
+            .build();
+    }
+    let zero = builder.new_int_constant(0, i32_ty);
+    builder.new_return(zero).of_ty(i32_ty).build();
+    builder.exit_synthetic_function(main);
+}
+
+fn declare_runtime_fn(
+    builder: &mut Builder,
+    fns_seen: &HashMap<String, ValueID>,
+    name: &'static str,
+    param_names: &[&str],
+    return_ty: TyID,
+    params: Vec<TyID>,
+    is_var_args: bool,
+) {
+    // Re-use the user's declaration, if there is one, so we don't emit two
+    // functions with the same symbol name.
+    let val = fns_seen.get(name).copied().unwrap_or_else(|| {
+        builder.new_function(
+            name,
+            param_names.iter().copied(),
+            return_ty,
+            params,
+            is_var_args,
+            false,
+        )
+    });
+    builder.add_runtime_fn(name, val);
+}
+
+fn declare_assert_runtime_fns(
+    builder: &mut Builder,
+    fns_seen: &HashMap<String, ValueID>,
+) {
+    let i32_ty = builder.module.types.get_int(32);
+    let str_ty = builder.module.types.get_str();
+    let void_ty = builder.module.types.get_void();
+    declare_runtime_fn(
+        builder,
+        fns_seen,
+        "dprintf",
+        &["fd", "fmt"],
+        i32_ty,
+        vec![i32_ty, str_ty],
+        true,
+    );
+    declare_runtime_fn(builder, fns_seen, "abort", &[], void_ty, vec![], false);
+}
+
 fn map_sema_tys_to_lir_tys(builder: &mut Builder) {
     fn map_ty(ty: &sema::Type, builder: &mut Builder) -> TyID {
         if let Some(id) = builder.sess.ty_mapping.try_get(&ty.id) {
@@ -163,6 +309,22 @@ fn fn_body(builder: &mut Builder, f: &bir::Function) {
                 .with_new_lval()
                 .build()
         });
+        // Allocate every local up front, so that vars declared in loops
+        // don't grow the stack on each iteration.
+        let sema_map = builder.sess.sema;
+        let sema_fn = builder.sess.bir_to_sema(&f.id);
+        for var in sema_map.all_vars_in_function(sema_fn) {
+            let sema = var.id;
+            let ty = builder.sess.sema_to_ty(&sema);
+            let ident = &sema_map.name(sema).unwrap().ident;
+            let var = builder
+                .new_var()
+                .of_ty(ty)
+                .with_new_lval()
+                .named(ident)
+                .build();
+            builder.sess.value_mapping.insert(sema, var.id);
+        }
         scope_(builder, lval, body);
         let ret_val = lval.dup().unwrap_or(builder.void_());
         builder.new_return(ret_val).of_ty(ty).build();
@@ -193,18 +355,6 @@ fn scope_(
     lval: Option<ValueRef>,
     scope: &bir::Block,
 ) -> ValueRef {
-    for let_ in scope.lets(builder.sess.bir) {
-        let sema = builder.sess.bir_to_sema(&let_.id);
-        let ty = builder.sess.sema_to_ty(&sema);
-        let var = builder
-            .new_var()
-            .of_ty(ty)
-            .with_new_lval()
-            .named(&let_.ident)
-            .build();
-        builder.sess.value_mapping.insert(sema, var.id);
-    }
-
     for it in scope.items(builder.sess.bir) {
         item(builder, it);
     }
@@ -239,15 +389,37 @@ fn item(builder: &mut Builder, it: &bir::Item) {
                 builder.sess.bir.expr(id),
             );
         }
+        bir::ItemKind::MacroExpansion(_) => {
+            unreachable!("macros should be expanded before lowering")
+        }
     }
 }
 
 fn literal(builder: &mut Builder, id: sema::ID) -> ValueRef {
     match builder.sess.sema.constant(id).unwrap() {
-        sema::Constant::Int(v) => {
-            builder.new_int_constant(*v, builder.sess.sema_to_ty(&id))
-        }
+        sema::Constant::Int { value, .. } => builder
+            .new_int_constant(*value as usize, builder.sess.sema_to_ty(&id)),
         sema::Constant::Str(s) => builder.new_str_constant(s),
+        sema::Constant::Null => {
+            builder.new_null_constant(builder.sess.sema_to_ty(&id))
+        }
+        sema::Constant::Struct => {
+            unreachable!("struct literals are built by `struct_literal`")
+        }
+    }
+}
+
+fn struct_literal(
+    builder: &mut Builder,
+    lval: Option<ValueRef>,
+    lit: &bir::StructLiteral,
+    ty: TyID,
+) -> ValueRef {
+    // Only `S {}` parses, which leaves every field uninitialized
+    debug_assert!(lit.members.is_empty());
+    match lval {
+        Some(lval) => lval,
+        None => builder.build_struct_alloc(ty),
     }
 }
 
@@ -272,8 +444,11 @@ fn value(
     let sema = builder.sess.bir_to_sema(&e.id);
     let ty = builder.sess.sema_to_ty(&sema);
     let val = match &e.kind {
-        bir::ExprKind::Literal(..) => {
+        bir::ExprKind::Literal(lit) => {
             debug_assert_ne!(cat, ValueCategory::LVal);
+            if let bir::Literal::Struct(lit) = builder.sess.bir.lit(lit) {
+                return struct_literal(builder, lval, lit, ty);
+            }
             let val = literal(builder, sema);
             if let Some(lval) = lval {
                 return builder.new_copy(val).with_lval(lval).of_ty(ty).build();
@@ -284,6 +459,10 @@ fn value(
             let named_val = builder.sess.val_from_sema(&sema);
             if let Some(lval) = lval {
                 debug_assert_eq!(cat, ValueCategory::RVal);
+                if should_memcpy(builder, lval, named_val, ty) {
+                    builder.new_memcpy(lval, named_val).build();
+                    return lval;
+                }
                 return builder
                     .new_copy(named_val)
                     .with_lval(lval)
@@ -293,7 +472,11 @@ fn value(
             named_val
         }
         bir::ExprKind::Call { receiver, operands } => {
-            let called_fn = builder.sess.val_from_bir(receiver);
+            let callee = builder.sess.bir_to_sema(receiver);
+            if let Some(intrinsic) = builder.sess.sema.intrinsic(callee) {
+                return intrinsic_call(builder, intrinsic, ty, lval, operands);
+            }
+            let called_fn = builder.sess.val_from_sema(&callee);
             let ops: Vec<_> = operands
                 .iter()
                 .map(|op| rvalue(builder, None, builder.sess.bir.expr(op)))
@@ -330,18 +513,33 @@ fn value(
             bir::OpKind::FieldAccess => {
                 field_access_expr(builder, cat, lval, op, ty)
             }
+            bir::OpKind::Deref => deref_expr(builder, cat, lval, op, ty),
+            bir::OpKind::AddressOf => address_of_expr(builder, lval, op, ty),
+            _ if op.fixity == bir::OpFixity::Prefix => {
+                prefix_op_expr(builder, ty, lval, op)
+            }
             _ => op_expr(builder, ty, lval, op),
         },
         bir::ExprKind::Block { scope } => {
             scope_(builder, lval, builder.sess.bir.block(scope))
         }
-        bir::ExprKind::Cast { val, .. } => {
-            let val = rvalue(builder, None, builder.sess.bir.expr(val));
-            builder
-                .new_cast(val)
-                .of_ty(ty)
-                .with_lval_or_new(lval)
-                .build()
+        bir::ExprKind::Cast { val: operand, .. } => {
+            let val = rvalue(builder, None, builder.sess.bir.expr(operand));
+            // `bool` is the only unsigned integer type, so it's the only one
+            // that's zero-extended when widened.
+            let sema = builder.sess.sema;
+            let from_bool = sema.ty_id(builder.sess.bir_to_sema(operand))
+                == Some(sema.bool_type());
+            let widens = matches!(
+                ty.get(builder.ctx()).kind,
+                TyKind::Integer { size } if size > 1
+            );
+            let cast = if from_bool && widens {
+                builder.new_zext(val)
+            } else {
+                builder.new_cast(val)
+            };
+            cast.of_ty(ty).with_lval_or_new(lval).build()
         }
         bir::ExprKind::Return { expr } => {
             let ret = if let Some(expr) = expr {
@@ -382,11 +580,87 @@ fn value(
             builder.resolve_breaks(scope.label.as_ref().unwrap(), after);
             jmp_to_latch
         }
+        bir::ExprKind::MacroCall { .. } => {
+            unreachable!("macros should be expanded before lowering")
+        }
     };
     builder.sess.value_mapping.insert(sema, val.id);
     val
 }
 
+fn intrinsic_call(
+    builder: &mut Builder,
+    intrinsic: sema::Intrinsic,
+    ty: TyID,
+    lval: Option<ValueRef>,
+    operands: &[bir::ID],
+) -> ValueRef {
+    match intrinsic {
+        sema::Intrinsic::Assert => assert_call(builder, operands),
+        sema::Intrinsic::ForEach => for_each_call(builder, operands),
+        sema::Intrinsic::WrappingAdd => {
+            wrapping_add_call(builder, ty, lval, operands)
+        }
+    }
+}
+
+fn wrapping_add_call(
+    builder: &mut Builder,
+    ty: TyID,
+    lval: Option<ValueRef>,
+    operands: &[bir::ID],
+) -> ValueRef {
+    let lhs = rvalue(builder, None, builder.sess.bir.expr(&operands[0]));
+    let rhs = rvalue(builder, None, builder.sess.bir.expr(&operands[1]));
+    builder
+        .new_add(lhs, rhs, OverflowMode::Wrapping)
+        .of_ty(ty)
+        .with_lval_or_new(lval)
+        .build()
+}
+
+fn for_each_call(builder: &mut Builder, operands: &[bir::ID]) -> ValueRef {
+    let arr = rvalue(builder, None, builder.sess.bir.expr(&operands[0]));
+    let len = rvalue(builder, None, builder.sess.bir.expr(&operands[1]));
+    let callback = rvalue(builder, None, builder.sess.bir.expr(&operands[2]));
+    builder.new_for_each(arr, len, callback).void_ty().build();
+    builder.void_()
+}
+
+// Expands `assert(cond, msg)` to:
+//
+//     br cond, exit, fail
+//   fail:
+//     dprintf(2, "Assertion failed: %s\n", msg)
+//     abort()
+//     jmp exit
+//   exit:
+fn assert_call(builder: &mut Builder, operands: &[bir::ID]) -> ValueRef {
+    let cond = rvalue(builder, None, builder.sess.bir.expr(&operands[0]));
+    let msg = rvalue(builder, None, builder.sess.bir.expr(&operands[1]));
+    let branch_marker = builder.new_branch_marker();
+
+    let fail = builder.new_block();
+    let i32_ty = builder.module.types.get_int(32);
+    let stderr_fd = builder.new_int_constant(2, i32_ty);
+    let fmt = builder.new_str_constant("Assertion failed: %s\n");
+    let dprintf = builder.runtime_fn("dprintf");
+    builder
+        .new_call(dprintf, vec![stderr_fd, fmt, msg])
+        .of_ty(i32_ty)
+        .with_new_lval()
+        .build();
+    let abort = builder.runtime_fn("abort");
+    let void_ty = builder.void_ty();
+    builder.new_call(abort, vec![]).of_ty(void_ty).build();
+    let jmp_to_exit = builder.new_jump_marker();
+
+    let exit = builder.new_block();
+    builder.resolve_jump(jmp_to_exit, exit);
+    builder.resolve_branch(branch_marker, cond, exit, fail);
+    builder.void_()
+}
+
 fn field_access_expr(
     builder: &mut Builder,
     cat: ValueCategory,
@@ -395,34 +669,40 @@ fn field_access_expr(
     ty: TyID,
 ) -> ValueRef {
     let base = rvalue(builder, None, builder.sess.bir.expr(&op.operands[0]));
-    let rhs = {
+    let offset = {
         let sema = builder.sess.bir_to_sema(&op.operands[1]);
-        let offset =
-            builder.sess.sema.ty_member(sema).offset(builder.sess.sema);
-        let ty = builder.sess.sema_to_ty(&sema);
-        builder.new_int_constant(offset, ty)
+        builder.sess.sema.ty_member(sema).offset(builder.sess.sema)
     };
-    let addr = builder
-        .new_get_field(base, &[rhs])
-        .of_ty(ty)
-        .with_new_lval()
-        .build();
     match cat {
-        ValueCategory::LVal => addr,
-        ValueCategory::RVal => {
-            let ty = {
-                let base_ty = base.ty(builder.ctx()).id;
-                builder.ctx_mut().types().get_pointer_to(&base_ty)
-            };
+        ValueCategory::LVal => {
+            // A field index, not a value of the field's type
+            let i32_ty = builder.module.types.get_int(32);
+            let rhs = builder.new_int_constant(offset, i32_ty);
             builder
-                .new_load(addr)
+                .new_get_field(base, &[rhs])
                 .of_ty(ty)
-                .with_lval_or_new(lval)
+                .with_new_lval()
                 .build()
         }
+        ValueCategory::RVal => builder.build_field_load(base, offset, lval),
     }
 }
 
+/// Whether copying `src` of type `ty` into `dst` should be done with a
+/// `memcpy`. Both have to live in memory, which params don't.
+fn should_memcpy(
+    builder: &Builder,
+    dst: ValueRef,
+    src: ValueRef,
+    ty: TyID,
+) -> bool {
+    let ty = ty.get(builder.ctx());
+    ty.kind == TyKind::Struct
+        && ty.size_in_bytes(builder.ctx()) > MEMCPY_THRESHOLD
+        && dst.kind(builder.ctx()) == ValueKind::Inst
+        && src.kind(builder.ctx()) == ValueKind::Inst
+}
+
 fn assign_expr(builder: &mut Builder, op: &bir::Op) -> ValueRef {
     let to = lvalue(builder, builder.sess.bir.expr(&op.operands[0]));
     rvalue(builder, Some(to), builder.sess.bir.expr(&op.operands[1]));
@@ -469,6 +749,65 @@ fn branch_expr(
     lval.unwrap_or(builder.void_())
 }
 
+fn deref_expr(
+    builder: &mut Builder,
+    cat: ValueCategory,
+    lval: Option<ValueRef>,
+    op: &bir::Op,
+    ty: TyID,
+) -> ValueRef {
+    let ptr = rvalue(builder, None, builder.sess.bir.expr(&op.operands[0]));
+    let index_ty = builder.module.types.get_int(64);
+    let zero = builder.new_int_constant(0, index_ty);
+    let addr = builder
+        .new_subscript(ptr, &[zero])
+        .of_ty(ty)
+        .with_new_lval()
+        .build();
+    match cat {
+        ValueCategory::LVal => addr,
+        ValueCategory::RVal => builder
+            .new_load(addr)
+            .of_ty(ty)
+            .with_lval_or_new(lval)
+            .build(),
+    }
+}
+
+fn address_of_expr(
+    builder: &mut Builder,
+    lval: Option<ValueRef>,
+    op: &bir::Op,
+    ty: TyID,
+) -> ValueRef {
+    let place = lvalue(builder, builder.sess.bir.expr(&op.operands[0]));
+    builder
+        .new_address_of(place)
+        .of_ty(ty)
+        .with_lval_or_new(lval)
+        .build()
+}
+
+fn prefix_op_expr(
+    builder: &mut Builder,
+    ty: TyID,
+    lval: Option<ValueRef>,
+    op: &bir::Op,
+) -> ValueRef {
+    let operand = rvalue(builder, None, builder.sess.bir.expr(&op.operands[0]));
+    let zero = builder.new_int_constant(0, ty);
+    let overflow = builder.sess.overflow_mode;
+    match &op.kind {
+        bir::OpKind::Plus => builder.new_copy(operand),
+        bir::OpKind::Minus => builder.new_sub(zero, operand, overflow),
+        bir::OpKind::Not => builder.new_cmp(CmpKind::Eq, operand, zero),
+        kind => unreachable!("`{kind:?}` isn't a prefix operator"),
+    }
+    .of_ty(ty)
+    .with_lval_or_new(lval)
+    .build()
+}
+
 fn op_expr(
     builder: &mut Builder,
     ty: TyID,
@@ -477,10 +816,11 @@ fn op_expr(
 ) -> ValueRef {
     let lhs = rvalue(builder, None, builder.sess.bir.expr(&op.operands[0]));
     let rhs = rvalue(builder, None, builder.sess.bir.expr(&op.operands[1]));
+    let overflow = builder.sess.overflow_mode;
     match &op.kind {
-        bir::OpKind::Plus => builder.new_add(lhs, rhs),
-        bir::OpKind::Minus => builder.new_sub(lhs, rhs),
-        bir::OpKind::Multiply => builder.new_mul(lhs, rhs),
+        bir::OpKind::Plus => builder.new_add(lhs, rhs, overflow),
+        bir::OpKind::Minus => builder.new_sub(lhs, rhs, overflow),
+        bir::OpKind::Multiply => builder.new_mul(lhs, rhs, overflow),
         bir::OpKind::Divide => builder.new_div(lhs, rhs),
         bir::OpKind::LessThan => builder.new_cmp(CmpKind::Lt, lhs, rhs),
         bir::OpKind::LessThanEquals => builder.new_cmp(CmpKind::Lte, lhs, rhs),
@@ -490,9 +830,158 @@ fn op_expr(
         }
         bir::OpKind::Equals => builder.new_cmp(CmpKind::Eq, lhs, rhs),
         bir::OpKind::NotEquals => builder.new_cmp(CmpKind::Ne, lhs, rhs),
-        bir::OpKind::Assignment | bir::OpKind::FieldAccess => unreachable!(),
+        bir::OpKind::Assignment
+        | bir::OpKind::FieldAccess
+        | bir::OpKind::Deref
+        | bir::OpKind::AddressOf
+        | bir::OpKind::Not => unreachable!(),
     }
     .of_ty(ty)
     .with_lval_or_new(lval)
     .build()
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use ast::Node;
+    use std::sync::Arc;
+
+    /// Lower `text`, which mustn't have any errors, to LIR.
+    pub(crate) fn lower(text: &str) -> Module {
+        struct NoImports;
+        impl bir::translate::AstBuilder for NoImports {
+            type Error = ();
+            fn build(&mut self, _: &str) -> Result<Arc<ast::Module>, ()> {
+                Err(())
+            }
+        }
+        let cst = parser::parse_str(text);
+        assert!(cst.errors.is_empty());
+        let ast = ast::Module::cast(cst.root).unwrap();
+        let bir = bir::translate::ast(&ast, &mut NoImports);
+        debug_assert!(bir.check_structural_integrity().is_empty());
+        let sema = sema::check::check(&bir);
+        assert!(!sema.any_errors());
+        crate::translate(&bir, &sema, OverflowMode::default())
+    }
+
+    #[test]
+    fn field_indices_are_i32() {
+        let m = lower(
+            "type Inner { x: i64 }
+             type Outer { p: *i64, inner: Inner }
+             fn f(o: *Outer) -> i64 {
+                 let s: Outer = Outer {};
+                 let p: *i64 = o.p;
+                 let inner: Inner = s.inner;
+                 inner.x + *p
+             }",
+        );
+        assert_eq!(m.validate(), vec![]);
+        let f = &m.functions[0];
+        let ctx = Context::full(&m, f);
+        let is_i32: Vec<_> = f
+            .insts
+            .values()
+            .filter(|inst| matches!(inst.kind, InstKind::GetField))
+            .map(|inst| {
+                let kind = &inst.rvals[1].ty(ctx).kind;
+                matches!(kind, TyKind::Integer { size: 32 })
+            })
+            .collect();
+        assert_eq!(is_i32, [true; 3]);
+    }
+
+    #[test]
+    fn field_loads_have_the_fields_type() {
+        let m = lower(
+            "type S { a: i32, b: i64 }
+             fn f(p: *S) -> i64 { let s: S = S {}; p.b + s.b }",
+        );
+        assert_eq!(m.validate(), vec![]);
+        let f = &m.functions[0];
+        let ctx = Context::full(&m, f);
+        let is_i64: Vec<_> = f
+            .insts
+            .values()
+            .filter(|inst| matches!(inst.kind, InstKind::Load))
+            .map(|inst| {
+                let kind = &inst.lval().ty(ctx).kind;
+                matches!(kind, TyKind::Integer { size: 64 })
+            })
+            .collect();
+        // Both through a pointer to the struct, and from the struct itself
+        assert_eq!(is_i64, [true; 2]);
+    }
+
+    #[test]
+    fn large_struct_copies_use_memcpy() {
+        let m = lower(
+            "type Big { x: i64, y: i64, z: i64 }
+             type Small { x: i64, y: i64 }
+             fn big(a: Big) { let b: Big = a; let c: Big = b; }
+             fn small(a: Small) { let b: Small = a; let c: Small = b; }",
+        );
+        assert_eq!(m.validate(), vec![]);
+        let memcpys = |f: &Function| {
+            f.insts
+                .values()
+                .filter(|inst| matches!(inst.kind, InstKind::Memcpy))
+                .map(|inst| inst.kind)
+                .collect::<Vec<_>>()
+        };
+        // Params aren't in memory, so copying `a` is always done in registers
+        assert_eq!(memcpys(&m.functions[0]), [InstKind::Memcpy]);
+        assert!(memcpys(&m.functions[1]).is_empty());
+    }
+
+    #[test]
+    fn wrapping_add_ignores_overflow_mode() {
+        let m = lower(
+            "fn main() -> i32 {
+                let x: i32 = 1;
+                wrapping_add(x, 2) + x
+            }",
+        );
+        let f = &m.functions[0];
+        let adds: Vec<_> = f
+            .blocks()
+            .flat_map(|block| block.insts(f).collect::<Vec<_>>())
+            .filter_map(|inst| match inst.kind {
+                InstKind::Add { overflow } => Some(overflow),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(adds, [OverflowMode::Wrapping, OverflowMode::Checked]);
+    }
+
+    #[test]
+    fn widening_bool_casts_zero_extend() {
+        let m = lower(
+            "fn f(x: i8) -> i32 {
+                let b: bool = x > 0;
+                b as i32 + x as i32
+            }",
+        );
+        let f = &m.functions[0];
+        let casts: Vec<_> = f
+            .blocks()
+            .flat_map(|block| block.insts(f).collect::<Vec<_>>())
+            .map(|inst| inst.kind)
+            .filter(|kind| matches!(kind, InstKind::Cast | InstKind::ZExt))
+            .collect();
+        assert_eq!(casts, [InstKind::ZExt, InstKind::Cast]);
+    }
+
+    #[test]
+    fn translate_declares_callees_first() {
+        let m = lower(
+            "fn main() -> i32 { a() }
+            fn a() -> i32 { b() }
+            fn b() -> i32 { 1 }",
+        );
+        let idents: Vec<_> = m.functions.iter().map(|f| &f.ident).collect();
+        assert_eq!(idents, ["b", "a", "main"]);
+    }
+}
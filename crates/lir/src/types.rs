@@ -39,20 +39,30 @@ impl Inst {
 pub enum InstKind {
     Var,
     Copy,
+    // Truncates or sign-extends, depending on the width of the result
     Cast,
+    ZExt,
     Load,
     Store,
     Subscript,
     GetField,
+    AddressOf,
     Call,
-    Add,
-    Sub,
-    Mul,
+    Add { overflow: OverflowMode },
+    Sub { overflow: OverflowMode },
+    Mul { overflow: OverflowMode },
     Div,
     Jmp,
     Branch,
+    // rvals: scrutinee, default block, then (case value, case block) pairs
+    Switch,
+    // rvals: (incoming value, predecessor block) pairs
+    Phi,
     Cmp { kind: CmpKind },
+    // rvals: destination, source; copies the whole of the source's type
+    Memcpy,
     Return,
+    ForEach,
     Nop,
 }
 
@@ -66,10 +76,42 @@ pub enum CmpKind {
     Lte,
 }
 
+/// What `add`, `sub` and `mul` do when the result doesn't fit in its type.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowMode {
+    /// Overflow is UB, so LLVM can assume it never happens (`nsw`)
+    #[default]
+    Checked,
+    /// The result wraps around in two's complement
+    Wrapping,
+    /// The result is clamped to the type's min or max
+    Saturating,
+}
+
+impl std::str::FromStr for OverflowMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "checked" => Ok(Self::Checked),
+            "wrapping" => Ok(Self::Wrapping),
+            "saturating" => Ok(Self::Saturating),
+            _ => Err(format!(
+                "unknown overflow mode `{s}`, expected `checked`, `wrapping` \
+                or `saturating`"
+            )),
+        }
+    }
+}
+
 impl InstKind {
     pub const fn can_have_lvals(&self) -> bool {
         match self {
-            Self::Return | Self::Jmp => false,
+            Self::Return
+            | Self::Jmp
+            | Self::Switch
+            | Self::ForEach
+            | Self::Memcpy => false,
             _ => true,
         }
     }
@@ -79,18 +121,22 @@ impl InstKind {
             InstKind::Var | InstKind::Nop => 0..=0,
             InstKind::Copy
             | InstKind::Cast
+            | InstKind::ZExt
             | InstKind::Return
             | InstKind::Load
             | InstKind::Store
+            | InstKind::AddressOf
             | InstKind::Jmp => 1..=1,
-            InstKind::Add
-            | InstKind::Sub
-            | InstKind::Mul
+            InstKind::Add { .. }
+            | InstKind::Sub { .. }
+            | InstKind::Mul { .. }
             | InstKind::Div
             | InstKind::GetField
-            | InstKind::Cmp { .. } => 2..=2,
-            InstKind::Branch => 3..=3,
+            | InstKind::Cmp { .. }
+            | InstKind::Memcpy => 2..=2,
+            InstKind::Branch | InstKind::ForEach => 3..=3,
             InstKind::Call | InstKind::Subscript => 1..=usize::MAX,
+            InstKind::Switch | InstKind::Phi => 2..=usize::MAX,
         }
     }
 }
@@ -128,12 +174,24 @@ impl<'ctx> Context<'ctx> {
 
     #[inline]
     pub fn as_fn(&self) -> &'ctx Function {
-        self.f.expect("context has no function!")
+        self.try_as_fn().expect("context has no function!")
     }
 
     #[inline]
     pub fn as_mod(&self) -> &'ctx Module {
-        self.m.expect("context has no module!")
+        self.try_as_mod().expect("context has no module!")
+    }
+
+    /// The function in this context, if it was built with one.
+    #[inline]
+    pub fn try_as_fn(&self) -> Option<&'ctx Function> {
+        self.f
+    }
+
+    /// The module in this context, if it was built with one.
+    #[inline]
+    pub fn try_as_mod(&self) -> Option<&'ctx Module> {
+        self.m
     }
 
     #[inline]
@@ -267,4 +325,12 @@ mod tests {
         assert_eq!("ValueID::Global(0)", format!("{:?}", ValueID::global(0)));
         assert_eq!("ValueID::Local(123)", format!("{:?}", ValueID::local(123)));
     }
+
+    #[test]
+    fn context_without_a_function() {
+        let m = Module::new();
+        let ctx = Context::mod_(&m);
+        assert!(ctx.try_as_mod().is_some());
+        assert!(ctx.try_as_fn().is_none());
+    }
 }
@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use utils::vec_graph::traversal;
 
 use crate::types::*;
@@ -62,6 +63,68 @@ impl Function {
         self.params.get(n).expect(&format!("{n} out of bounds!"))
     }
 
+    /// The name of each parameter as written, or `param<N>` for the N-th
+    /// if it doesn't have one.
+    pub fn parameter_names(&self) -> Vec<Cow<'_, str>> {
+        self.params
+            .iter()
+            .enumerate()
+            .map(|(idx, param)| match self.locals.ident(&param.val) {
+                Some(name) if !name.is_empty() => Cow::Borrowed(name),
+                _ => Cow::Owned(format!("param{idx}")),
+            })
+            .collect()
+    }
+
+    /// The functions this one calls directly, including `for_each`
+    /// callbacks, in no particular order and possibly more than once.
+    pub fn callees<'m>(
+        &'m self,
+        module: &'m Module,
+    ) -> impl Iterator<Item = &'m Function> + 'm {
+        self.insts.values().filter_map(move |inst| {
+            let callee = match inst.kind {
+                InstKind::Call => inst.rvals[0].id,
+                InstKind::ForEach => inst.rvals[2].id,
+                _ => return None,
+            };
+            module
+                .vals_to_fns
+                .contains_key(&callee)
+                .then(|| module.fn_(&callee))
+        })
+    }
+
+    /// Whether calling this function can lead to calling it again, either
+    /// directly or through other functions.
+    pub fn is_recursive(&self, module: &Module) -> bool {
+        self.can_reach(self, module)
+    }
+
+    /// Whether this function and `other` can each lead to calling the other.
+    pub fn is_mutually_recursive(
+        &self,
+        other: &Function,
+        module: &Module,
+    ) -> bool {
+        self.can_reach(other, module) && other.can_reach(self, module)
+    }
+
+    /// Whether `target` is called by this function, or by anything it calls.
+    fn can_reach(&self, target: &Function, module: &Module) -> bool {
+        let mut visited = HashSet::new();
+        let mut worklist: Vec<_> = self.callees(module).collect();
+        while let Some(f) = worklist.pop() {
+            if f.id == target.id {
+                return true;
+            }
+            if visited.insert(f.id) {
+                worklist.extend(f.callees(module));
+            }
+        }
+        false
+    }
+
     pub fn param_num(&self, val: impl Into<ValueID>) -> Option<usize> {
         let val = val.into();
         debug_assert!(val.is_local());
@@ -80,6 +143,36 @@ impl Function {
         self.blocks.num_vertices()
     }
 
+    /// The number of instructions across every block.
+    pub fn num_insts(&self) -> usize {
+        self.blocks().map(|b| b.insts(self).count()).sum()
+    }
+
+    /// Whether the function is defined in this module, rather than only
+    /// declared.
+    #[inline]
+    pub fn has_body(&self) -> bool {
+        self.num_blocks() > 0
+    }
+
+    /// The block control enters the function through, which is always the
+    /// first block in RPO.
+    #[inline]
+    pub fn entry_block(&self) -> Block {
+        Block(self.blocks.start())
+    }
+
+    /// Every block that leaves the function, i.e. ends in a `return`.
+    pub fn exit_blocks(&self) -> impl Iterator<Item = Block> + '_ {
+        self.blocks().filter(move |block| {
+            !self.blocks.is_unlinked(&block.0)
+                && block
+                    .insts(self)
+                    .last()
+                    .is_some_and(|inst| inst.kind == InstKind::Return)
+        })
+    }
+
     pub fn visit_blocks_in_po(&self, mut f: impl FnMut(Block)) {
         traversal::post_order(&self.blocks, &mut |node| {
             f(Block(node));
@@ -224,3 +317,56 @@ impl Function {
 pub struct Param {
     pub val: ValueID,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::translate::tests::lower;
+
+    #[test]
+    fn parameter_names() {
+        let m = lower("fn add(a: i32, b: i32) -> i32 { a + b }");
+        assert_eq!(m.functions[0].parameter_names(), ["a", "b"]);
+    }
+
+    #[test]
+    fn recursion() {
+        let m = lower(
+            "fn fact(n: i64) -> i64 {
+                 if n < 2 { return 1; }
+                 n * fact(n - 1)
+             }
+             fn even(n: i64) -> i64 { if n == 0 { return 1; } odd(n - 1) }
+             fn odd(n: i64) -> i64 { if n == 0 { return 0; } even(n - 1) }
+             fn start() -> i64 { fact(5) + even(4) }",
+        );
+        let f =
+            |name: &str| m.functions.iter().find(|f| f.ident == name).unwrap();
+        assert!(f("fact").is_recursive(&m));
+        assert!(f("even").is_recursive(&m));
+        assert!(!f("start").is_recursive(&m));
+        assert!(f("even").is_mutually_recursive(f("odd"), &m));
+        assert!(!f("start").is_mutually_recursive(f("fact"), &m));
+    }
+
+    #[test]
+    fn entry_and_exit_blocks() {
+        let m = lower(
+            "fn main() -> i32 {
+                if 1 == 2 { return 1; }
+                2
+            }",
+        );
+        let f = &m.functions[0];
+        let mut rpo = Vec::new();
+        f.visit_blocks_in_rpo(|block| rpo.push(block));
+        assert_eq!(f.entry_block(), rpo[0]);
+
+        let exits: Vec<_> = f.exit_blocks().collect();
+        assert_eq!(exits.len(), 2);
+        for exit in exits {
+            let ret = exit.terminator(f).inst(f).unwrap();
+            assert_eq!(ret.kind, InstKind::Return);
+        }
+    }
+}
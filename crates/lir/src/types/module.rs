@@ -63,6 +63,15 @@ impl Module {
         id
     }
 
+    pub fn add_null_constant(&mut self, ty: TyID) -> ValueID {
+        Self::add_global(
+            &mut self.globals,
+            ValueKind::Constant(ConstantKind::Null),
+            ty,
+            None,
+        )
+    }
+
     pub fn add_fn(
         &mut self,
         name: String,
@@ -90,6 +99,22 @@ impl Module {
         self.functions.get(idx).unwrap()
     }
 
+    /// Give every function external linkage, even those with callers, as
+    /// when the whole module is a library's interface.
+    pub fn export_all(&mut self) {
+        for function in &mut self.functions {
+            function.internal = false;
+        }
+    }
+
+    /// The functions sorted by name, so that anything emitted in this order
+    /// doesn't depend on the order they were translated in.
+    pub fn functions_sorted(&self) -> Vec<&Function> {
+        let mut functions: Vec<_> = self.functions.iter().collect();
+        functions.sort_by(|a, b| a.ident.cmp(&b.ident));
+        functions
+    }
+
     pub(crate) fn fn_mut(&mut self, val: &ValueID) -> &mut Function {
         let idx = self.vals_to_fns[val];
         self.functions.get_mut(idx).unwrap()
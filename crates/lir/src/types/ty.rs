@@ -153,6 +153,37 @@ impl Ty {
         self.kind != TyKind::Void
     }
 
+    /// The size of the type in bytes, laid out as a C compiler would on a
+    /// 64-bit target.
+    pub fn size_in_bytes<'ctx>(
+        &self,
+        ctx: impl Into<&'ctx TyContext>,
+    ) -> usize {
+        self.layout(ctx.into()).0
+    }
+
+    /// (size, alignment) in bytes
+    fn layout(&self, ctx: &TyContext) -> (usize, usize) {
+        match self.kind {
+            TyKind::Integer { size } => {
+                let bytes = ((size + 7) / 8).max(1).next_power_of_two();
+                (bytes, bytes.min(8))
+            }
+            TyKind::Pointer | TyKind::Fn { .. } => (8, 8),
+            TyKind::Void => (0, 1),
+            TyKind::Struct => {
+                let (mut size, mut align) = (0usize, 1);
+                for member in self.inner_tys.iter() {
+                    let (member_size, member_align) =
+                        ctx.get(member).layout(ctx);
+                    size = size.next_multiple_of(member_align) + member_size;
+                    align = align.max(member_align);
+                }
+                (size.next_multiple_of(align), align)
+            }
+        }
+    }
+
     pub fn repr<'ctx>(&self, ctx: impl Into<&'ctx TyContext>) -> String {
         let ctx = ctx.into();
         match self.kind {
@@ -120,6 +120,7 @@ impl ValueID {
                     format!("{:?}", self.str_constant(ctx).to_string())
                 }
                 ConstantKind::Int => self.int_constant(ctx).to_string(),
+                ConstantKind::Null => "null".to_string(),
             },
             ValueKind::Function => ctx.as_mod().fn_(self).ident.clone(),
             ValueKind::Void => "void".to_string(),
@@ -248,6 +249,7 @@ pub enum ValueKind {
 pub enum ConstantKind {
     Str,
     Int,
+    Null,
 }
 
 impl From<ValueRef> for ValueID {
@@ -286,6 +288,10 @@ impl Values {
         *self.types.get(val).unwrap()
     }
 
+    pub(crate) fn ident(&self, val: &ValueID) -> Option<&str> {
+        self.idents.get(val).map(String::as_str)
+    }
+
     pub(crate) fn add_val(
         &mut self,
         kind: ValueKind,
@@ -316,7 +322,11 @@ impl Values {
 
     #[inline]
     pub(crate) fn remove_user(&mut self, val: ValueID, user: ValueID) {
-        self.users.get_mut(&val).unwrap().retain(|u| *u != user);
+        // Resolved jump/branch targets aren't tracked as users, so `val` may
+        // have no user list at all.
+        if let Some(users) = self.users.get_mut(&val) {
+            users.retain(|u| *u != user);
+        }
     }
 
     #[inline]
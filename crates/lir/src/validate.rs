@@ -0,0 +1,276 @@
+use std::collections::HashSet;
+
+use crate::types::*;
+
+/// An inconsistency in a module, usually left behind by a buggy pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// `inst` uses `val`, which isn't defined by a live instruction or param
+    UndefinedValue {
+        function: String,
+        inst: ValueID,
+        val: ValueID,
+    },
+    /// `inst` jumps or branches to `block`, which isn't in the function
+    UnknownBlock {
+        function: String,
+        inst: ValueID,
+        block: ValueID,
+    },
+    /// `inst` calls `callee`, which isn't declared in the module
+    UnknownFunction {
+        function: String,
+        inst: ValueID,
+        callee: ValueID,
+    },
+    /// More than one block in the function has the ID `block`
+    DuplicateBlock { function: String, block: ValueID },
+    /// The phi `inst` merges a value from `block`, which isn't one of the
+    /// predecessors of its own block
+    PhiFromNonPredecessor {
+        function: String,
+        inst: ValueID,
+        block: ValueID,
+    },
+    /// The phi `inst` comes after an instruction that isn't a phi
+    PhiAfterNonPhi { function: String, inst: ValueID },
+    /// The phi `inst` has a value without a block, or vice versa
+    UnpairedPhiOperand { function: String, inst: ValueID },
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UndefinedValue {
+                function,
+                inst,
+                val,
+            } => write!(f, "{function}: {inst} uses undefined value {val}"),
+            Self::UnknownBlock {
+                function,
+                inst,
+                block,
+            } => write!(f, "{function}: {inst} targets unknown block {block}"),
+            Self::UnknownFunction {
+                function,
+                inst,
+                callee,
+            } => {
+                write!(f, "{function}: {inst} calls unknown function {callee}")
+            }
+            Self::DuplicateBlock { function, block } => {
+                write!(f, "{function}: block {block} is defined twice")
+            }
+            Self::PhiFromNonPredecessor {
+                function,
+                inst,
+                block,
+            } => write!(
+                f,
+                "{function}: {inst} merges a value from {block}, which isn't \
+                 a predecessor"
+            ),
+            Self::PhiAfterNonPhi { function, inst } => {
+                write!(f, "{function}: phi {inst} comes after a non-phi")
+            }
+            Self::UnpairedPhiOperand { function, inst } => {
+                write!(f, "{function}: phi {inst} has an unpaired operand")
+            }
+        }
+    }
+}
+
+impl Module {
+    /// Check that every value used is defined, and that every block and
+    /// function referred to exists.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        for f in &self.functions {
+            validate_fn(self, f, &mut errors);
+        }
+        errors
+    }
+}
+
+fn validate_fn(m: &Module, f: &Function, errors: &mut Vec<ValidationError>) {
+    let function = || f.ident.clone();
+
+    let blocks: Vec<_> =
+        f.blocks().filter(|b| !f.blocks.is_unlinked(&b.0)).collect();
+    let mut live_blocks = HashSet::new();
+    for block in &blocks {
+        let id = block.val(f).id;
+        if !live_blocks.insert(id) {
+            errors.push(ValidationError::DuplicateBlock {
+                function: function(),
+                block: id,
+            });
+        }
+    }
+
+    // Values can be used before they're defined, e.g. along a loop's back
+    // edge, and an instruction's lval is a value of its own
+    let defined_vals: HashSet<_> = blocks
+        .iter()
+        .flat_map(|block| block.insts(f).collect::<Vec<_>>())
+        .flat_map(|inst| {
+            std::iter::once(inst.val.id).chain(inst.lval.map(|l| l.id))
+        })
+        .collect();
+
+    for block in blocks {
+        let predecessors: HashSet<_> =
+            block.predecessors(f).map(|pred| pred.val(f).id).collect();
+        let mut seen_non_phi = false;
+        for inst in block.insts(f) {
+            let inst_id = inst.val.id;
+            if inst.kind == InstKind::Phi {
+                if seen_non_phi {
+                    errors.push(ValidationError::PhiAfterNonPhi {
+                        function: function(),
+                        inst: inst_id,
+                    });
+                }
+                if inst.rvals.len() % 2 != 0 {
+                    errors.push(ValidationError::UnpairedPhiOperand {
+                        function: function(),
+                        inst: inst_id,
+                    });
+                }
+                for incoming in inst.rvals.chunks_exact(2) {
+                    let pred = &incoming[1];
+                    if !predecessors.contains(&pred.id) {
+                        errors.push(ValidationError::PhiFromNonPredecessor {
+                            function: function(),
+                            inst: inst_id,
+                            block: pred.id,
+                        });
+                    }
+                }
+            } else {
+                seen_non_phi = true;
+            }
+
+            for val in inst.lval.iter().chain(&inst.rvals) {
+                let val = val.id;
+                if val.is_global() {
+                    if val.as_idx() >= m.globals.vals.len() {
+                        errors.push(ValidationError::UndefinedValue {
+                            function: function(),
+                            inst: inst_id,
+                            val,
+                        });
+                    }
+                    continue;
+                }
+                let defined = val.as_idx() < f.locals.vals.len()
+                    && match val.kind(f) {
+                        ValueKind::Inst => defined_vals.contains(&val),
+                        ValueKind::Param => {
+                            f.params.iter().any(|p| p.val == val)
+                        }
+                        ValueKind::Block => {
+                            if !live_blocks.contains(&val) {
+                                errors.push(ValidationError::UnknownBlock {
+                                    function: function(),
+                                    inst: inst_id,
+                                    block: val,
+                                });
+                            }
+                            true
+                        }
+                        _ => true,
+                    };
+                if !defined {
+                    errors.push(ValidationError::UndefinedValue {
+                        function: function(),
+                        inst: inst_id,
+                        val,
+                    });
+                }
+            }
+
+            if inst.kind == InstKind::Call {
+                let callee = inst.rvals[0].id;
+                let is_fn = callee.is_global()
+                    && callee.as_idx() < m.globals.vals.len()
+                    && callee.kind(Context::mod_(m)) == ValueKind::Function;
+                if is_fn && !m.vals_to_fns.contains_key(&callee) {
+                    errors.push(ValidationError::UnknownFunction {
+                        function: function(),
+                        inst: inst_id,
+                        callee,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::translate::tests::lower;
+
+    #[test]
+    fn validate_catches_dangling_block_refs() {
+        let mut m = lower(
+            "fn main() -> i32 {
+                loop { return 1; }
+                let x: i32 = 2;
+                x
+            }",
+        );
+        assert_eq!(m.validate(), vec![]);
+
+        let f = &mut m.functions[0];
+        let jmp = f.entry_block().terminator(f);
+        let target = jmp.inst(&*f).unwrap().rvals[0].id;
+        f.remove_blocks(&[target]);
+        let dangling = ValidationError::UnknownBlock {
+            function: "main".to_string(),
+            inst: jmp.id,
+            block: target,
+        };
+        assert!(m.validate().contains(&dangling));
+    }
+
+    #[test]
+    fn validate_checks_phis() {
+        let mut m = lower("fn f(x: i32) -> i32 { x }");
+        assert_eq!(m.validate(), vec![]);
+
+        let ty = m.types.get_void();
+        let f = &mut m.functions[0];
+        let entry = f.entry_block();
+        let x = ValueRef::new(f.params[0].val);
+        let entry_val = ValueRef::new(entry.val(f).id);
+        // After the return, and merging from a block that isn't a predecessor
+        let phi = f.add_inst(
+            InstKind::Phi,
+            ty,
+            entry,
+            None,
+            vec![x, entry_val],
+            None,
+        );
+        let errors = m.validate();
+        assert!(errors.contains(&ValidationError::PhiAfterNonPhi {
+            function: "f".to_string(),
+            inst: phi.id,
+        }));
+        assert!(errors.contains(&ValidationError::PhiFromNonPredecessor {
+            function: "f".to_string(),
+            inst: phi.id,
+            block: entry_val.id,
+        }));
+
+        let f = &mut m.functions[0];
+        let unpaired =
+            f.add_inst(InstKind::Phi, ty, entry, None, vec![x], None);
+        assert!(m.validate().contains(&ValidationError::UnpairedPhiOperand {
+            function: "f".to_string(),
+            inst: unpaired.id,
+        }));
+    }
+}
@@ -42,7 +42,7 @@ fn module(parser: &mut Parser<'_>, inner_module: bool) {
             T![mod] => module(parser, true),
             T![import] => items::import_item(parser),
             T![let] => items::let_item(parser),
-            T![fn] => items::fn_item(parser),
+            T![fn] | T![#] => items::fn_item(parser),
             T![type] => items::type_item(parser),
             T!['}'] => {
                 if inner_module {
@@ -88,6 +88,21 @@ fn type_(parser: &mut Parser<'_>) {
                 type_(parser);
             });
         }
+        T![fn] => {
+            parser.node(FN_TYPE, |parser| {
+                parser.expect_token(T![fn]);
+                param_type_list(parser);
+                if parser.maybe(T![->]) {
+                    parser.expect_token(T![->]);
+                    type_(parser);
+                }
+            });
+        }
+        T![!] => {
+            parser.node(NEVER_TYPE, |parser| {
+                parser.expect_token(T![!]);
+            });
+        }
         _ => {
             parser.node(BASIC_TYPE, |parser| {
                 name(parser);
@@ -121,6 +136,31 @@ fn param_list(parser: &mut Parser<'_>) {
     });
 }
 
+fn param_type_list(parser: &mut Parser<'_>) {
+    parser.node(PARAM_TYPE_LIST, |parser| {
+        parser.expect_token(T!['(']);
+        parser.with_follow_set(&[T![')']], |parser| loop {
+            parser.add_to_follow_set(&[T![,]]);
+            match parser.advance_to_next_non_trivia() {
+                T![')'] | EOF => break,
+                T![.] => {
+                    parser.node(VA_PARAM, |parser| {
+                        parser.expect_token(T![...]);
+                    });
+                    break;
+                }
+                _ => {
+                    type_(parser);
+                    if parser.maybe(T![,]) {
+                        parser.token(T![,]);
+                    }
+                }
+            }
+        });
+        parser.expect_token(T![')']);
+    });
+}
+
 fn param(parser: &mut Parser<'_>) {
     parser.node(PARAM, |parser| {
         name(parser);
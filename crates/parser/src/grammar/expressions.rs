@@ -47,7 +47,7 @@ fn expr_with_precedence(
 
 fn expr_lhs(parser: &mut Parser) -> Option<CompletedMarker> {
     Some(match parser.advance_to_next_non_trivia() {
-        NUMBER | STRING => literal(parser),
+        kind if kind.is_literal() => literal(parser),
         IDENT => {
             let n = parser.start_node();
             name(parser);
@@ -80,12 +80,53 @@ fn expr_lhs(parser: &mut Parser) -> Option<CompletedMarker> {
 }
 
 fn paren(parser: &mut Parser<'_>) -> CompletedMarker {
-    parser.node(PAREN_EXPR, |parser| {
+    // `(a)` is a group, but `(a,)` and `(a, b)` are tuples. We can't tell
+    // which until after the first expression, so parse a group and start
+    // over as a tuple if a comma shows up instead of the closing paren.
+    let checkpoint = parser.checkpoint();
+    let mut is_tuple = false;
+    let group = parser.node(PAREN_EXPR, |parser| {
         parser.expect_token(T!['(']);
         parser.with_follow_set(&[T![')']], |parser| {
             expr(parser);
-            parser.expect_token(T![')']);
+            is_tuple = parser.maybe(T![,]);
+            if !is_tuple {
+                parser.expect_token(T![')']);
+            }
+        });
+    });
+    if !is_tuple {
+        return group;
+    }
+    parser.rollback(checkpoint);
+    tuple(parser)
+}
+
+fn tuple(parser: &mut Parser<'_>) -> CompletedMarker {
+    parser.node(TUPLE_EXPR, |parser| {
+        parser.expect_token(T!['(']);
+        parser.with_follow_set(&[T![')']], |parser| loop {
+            match parser.advance_to_next_non_trivia() {
+                T![')'] => {
+                    parser.expect_token(T![')']);
+                    break;
+                }
+                EOF => {
+                    parser.unexpected(EOF);
+                    break;
+                }
+                kind => {
+                    // Skipping up to the `)` always makes progress, since
+                    // `kind` isn't one
+                    if expr(parser).is_none() {
+                        parser.unexpected(kind);
+                    } else if parser.maybe(T![,]) {
+                        parser.expect_token(T![,]);
+                    }
+                }
+            }
         });
+        parser.error("tuple expressions aren't supported yet");
     })
 }
 
@@ -188,8 +229,20 @@ pub(super) fn block_inner(parser: &mut Parser<'_>) {
             T!['}'] => break,
             _ => {
                 finish_previous_expr(parser, previous_expr, Some(T!['}']));
-                let next = expr(parser);
-                previous_expr = next;
+                previous_expr = None;
+                match parser.advance_to_next_non_trivia() {
+                    // Finishing the previous expression may have eaten a `;`,
+                    // leaving us at the start of the next item.
+                    T![let] | T![fn] | T!['}'] | EOF => {}
+                    kind => {
+                        previous_expr = expr(parser);
+                        if previous_expr.is_none() {
+                            parser.error(format!("unexpected {kind}"));
+                            parser.error_recovery_until(&[T![;], T!['}']]);
+                            parser.maybe_token(T![;]);
+                        }
+                    }
+                }
             }
         }
     }
@@ -205,8 +258,7 @@ fn return_(parser: &mut Parser<'_>) -> CompletedMarker {
 fn literal(parser: &mut Parser<'_>) -> CompletedMarker {
     parser.node(LITERAL, |parser| {
         match parser.advance_to_next_non_trivia() {
-            NUMBER => parser.token(NUMBER),
-            STRING => parser.token(STRING),
+            kind if kind.is_literal() => parser.token(kind),
             kind => parser.unexpected(kind),
         }
     })
@@ -242,7 +294,7 @@ fn infix_binding_power(kind: SyntaxKind) -> Option<(usize, usize)> {
 
 fn prefix_binding_power(kind: SyntaxKind) -> Option<((), usize)> {
     match kind {
-        T![-] | T![+] => Some(((), 5)),
+        T![-] | T![+] | T![*] | T![&] | T![!] => Some(((), 5)),
         _ => None,
     }
 }
@@ -317,9 +369,81 @@ fn as_expr(parser: &mut Parser<'_>, lhs: CompletedMarker) -> CompletedMarker {
 
 #[cfg(test)]
 mod tests {
-    use crate::tests::check_tree;
+    use crate::grammar::EntryPoint;
+    use crate::tests::{check_errors_from_entry, check_tree};
     use expect_test::expect;
 
+    #[test]
+    fn block_error_recovery() {
+        check_errors_from_entry(
+            "{ ); a; ]; b }",
+            expect![[r#"
+                BLOCK_EXPR @ 0..14:
+                  LEFT_CURLY @ 0..1: '{' 
+                  WHITESPACE @ 1..2: ' ' 
+                  ERROR @ 2..3:
+                    RIGHT_PAREN @ 2..3: ')' 
+                  SEMICOLON @ 3..4: ';' 
+                  WHITESPACE @ 4..5: ' ' 
+                  EXPR_ITEM @ 5..7:
+                    NAME_REF @ 5..6:
+                      NAME @ 5..6:
+                        IDENT @ 5..6: 'a' 
+                    SEMICOLON @ 6..7: ';' 
+                  WHITESPACE @ 7..8: ' ' 
+                  ERROR @ 8..9:
+                    RIGHT_SQUARE @ 8..9: ']' 
+                  SEMICOLON @ 9..10: ';' 
+                  WHITESPACE @ 10..11: ' ' 
+                  NAME_REF @ 11..12:
+                    NAME @ 11..12:
+                      IDENT @ 11..12: 'b' 
+                  WHITESPACE @ 12..13: ' ' 
+                  RIGHT_CURLY @ 13..14: '}' "#]],
+            &[
+                "unexpected ')' in block expr",
+                "unexpected ']' in block expr",
+            ],
+            EntryPoint::Block,
+        );
+    }
+
+    #[test]
+    fn prefix_ops() {
+        check_tree(
+            "*a = !b + -&c;",
+            expect![[r#"
+                MODULE @ 0..14:
+                  EXPR_ITEM @ 0..14:
+                    BIN_EXPR @ 0..13:
+                      PREFIX_EXPR @ 0..3:
+                        STAR @ 0..1: '*' 
+                        NAME_REF @ 1..2:
+                          NAME @ 1..2:
+                            IDENT @ 1..2: 'a' 
+                        WHITESPACE @ 2..3: ' ' 
+                      EQUALS @ 3..4: '=' 
+                      WHITESPACE @ 4..5: ' ' 
+                      BIN_EXPR @ 5..13:
+                        PREFIX_EXPR @ 5..8:
+                          BANG @ 5..6: '!' 
+                          NAME_REF @ 6..7:
+                            NAME @ 6..7:
+                              IDENT @ 6..7: 'b' 
+                          WHITESPACE @ 7..8: ' ' 
+                        PLUS @ 8..9: '+' 
+                        WHITESPACE @ 9..10: ' ' 
+                        PREFIX_EXPR @ 10..13:
+                          DASH @ 10..11: '-' 
+                          PREFIX_EXPR @ 11..13:
+                            AMPERSAND @ 11..12: '&' 
+                            NAME_REF @ 12..13:
+                              NAME @ 12..13:
+                                IDENT @ 12..13: 'c' 
+                    SEMICOLON @ 13..14: ';' "#]],
+        );
+    }
+
     #[test]
     fn bin_expr_no_precedence() {
         check_tree(
@@ -455,6 +579,83 @@ mod tests {
         );
     }
 
+    #[test]
+    fn paren_expr_rolls_back_to_tuple() {
+        check_errors_from_entry(
+            "{ (a); (a, b) }",
+            expect![[r#"
+                BLOCK_EXPR @ 0..15:
+                  LEFT_CURLY @ 0..1: '{' 
+                  WHITESPACE @ 1..2: ' ' 
+                  EXPR_ITEM @ 2..6:
+                    PAREN_EXPR @ 2..5:
+                      LEFT_PAREN @ 2..3: '(' 
+                      NAME_REF @ 3..4:
+                        NAME @ 3..4:
+                          IDENT @ 3..4: 'a' 
+                      RIGHT_PAREN @ 4..5: ')' 
+                    SEMICOLON @ 5..6: ';' 
+                  WHITESPACE @ 6..7: ' ' 
+                  TUPLE_EXPR @ 7..13:
+                    LEFT_PAREN @ 7..8: '(' 
+                    NAME_REF @ 8..9:
+                      NAME @ 8..9:
+                        IDENT @ 8..9: 'a' 
+                    COMMA @ 9..10: ',' 
+                    WHITESPACE @ 10..11: ' ' 
+                    NAME_REF @ 11..12:
+                      NAME @ 11..12:
+                        IDENT @ 11..12: 'b' 
+                    RIGHT_PAREN @ 12..13: ')' 
+                  WHITESPACE @ 13..14: ' ' 
+                  RIGHT_CURLY @ 14..15: '}' "#]],
+            &["tuple expressions aren't supported yet in tuple expr"],
+            EntryPoint::Block,
+        );
+    }
+
+    #[test]
+    fn tuple_stops_at_non_expr() {
+        check_errors_from_entry(
+            "{ (a, ;) }",
+            expect![[r#"
+                BLOCK_EXPR @ 0..10:
+                  LEFT_CURLY @ 0..1: '{' 
+                  WHITESPACE @ 1..2: ' ' 
+                  TUPLE_EXPR @ 2..8:
+                    LEFT_PAREN @ 2..3: '(' 
+                    NAME_REF @ 3..4:
+                      NAME @ 3..4:
+                        IDENT @ 3..4: 'a' 
+                    COMMA @ 4..5: ',' 
+                    WHITESPACE @ 5..6: ' ' 
+                    SEMICOLON @ 6..7: ';' 
+                    RIGHT_PAREN @ 7..8: ')' 
+                  WHITESPACE @ 8..9: ' ' 
+                  RIGHT_CURLY @ 9..10: '}' "#]],
+            &[
+                "unexpected ';' in tuple expr",
+                "tuple expressions aren't supported yet in tuple expr",
+            ],
+            EntryPoint::Block,
+        );
+        check_errors_from_entry(
+            "{ (a,",
+            expect![[r#"
+                BLOCK_EXPR @ 0..5:
+                  LEFT_CURLY @ 0..1: '{' 
+                  WHITESPACE @ 1..2: ' ' 
+                  TUPLE_EXPR @ 2..5:
+                    LEFT_PAREN @ 2..3: '(' 
+                    NAME_REF @ 3..4:
+                      NAME @ 3..4:
+                        IDENT @ 3..4: 'a' 
+                    COMMA @ 4..5: ',' "#]],
+            &["unexpected end of file in tuple expr"],
+            EntryPoint::Block,
+        );
+    }
+
     #[test]
     fn bin_expr_with_paren() {
         check_tree(
@@ -749,4 +950,23 @@ MODULE:
                     SEMICOLON @ 17..18: ';' "#]],
         )
     }
+    #[test]
+    fn null() {
+        check_tree(
+            "let p = null;",
+            expect![[r#"
+                MODULE @ 0..13:
+                  LET_ITEM @ 0..13:
+                    LET_KW @ 0..3: 'let' 
+                    WHITESPACE @ 3..4: ' ' 
+                    NAME @ 4..5:
+                      IDENT @ 4..5: 'p' 
+                    WHITESPACE @ 5..6: ' ' 
+                    EQUALS @ 6..7: '=' 
+                    WHITESPACE @ 7..8: ' ' 
+                    LITERAL @ 8..12:
+                      NULL_KW @ 8..12: 'null' 
+                    SEMICOLON @ 12..13: ';' "#]],
+        )
+    }
 }
@@ -23,6 +23,9 @@ pub(super) fn let_item(parser: &mut Parser<'_>) {
 
 pub(super) fn fn_item(parser: &mut Parser<'_>) {
     parser.node(FN_ITEM, |parser| {
+        while parser.maybe(T![#]) {
+            attr(parser);
+        }
         parser.expect_token(T![fn]);
         parser.with_follow_set(&[T!['(']], |parser| {
             name(parser);
@@ -33,7 +36,9 @@ pub(super) fn fn_item(parser: &mut Parser<'_>) {
             type_(parser);
         }
         if parser.maybe(T!['{']) {
-            expressions::block(parser);
+            parser.with_context("function body", |parser| {
+                expressions::block(parser);
+            });
             return;
         }
         parser.maybe_token(T![extern]);
@@ -41,6 +46,17 @@ pub(super) fn fn_item(parser: &mut Parser<'_>) {
     });
 }
 
+fn attr(parser: &mut Parser<'_>) {
+    parser.node(ATTR, |parser| {
+        parser.expect_token(T![#]);
+        parser.expect_token(T!['[']);
+        parser.with_follow_set(&[T![']']], |parser| {
+            name(parser);
+        });
+        parser.expect_token(T![']']);
+    });
+}
+
 pub(super) fn expr_item(parser: &mut Parser<'_>) {
     parser.node(EXPR_ITEM, |parser| {
         parser.with_follow_set(&[T![;]], |parser| {
@@ -188,4 +204,100 @@ mod tests {
                     RIGHT_CURLY @ 33..34: '}' "#]],
         );
     }
+
+    #[test]
+    fn fn_returning_never() {
+        check_tree(
+            "fn abort() -> ! extern;",
+            expect_test::expect![[r#"
+                MODULE @ 0..23:
+                  FN_ITEM @ 0..23:
+                    FN_KW @ 0..2: 'fn' 
+                    WHITESPACE @ 2..3: ' ' 
+                    NAME @ 3..8:
+                      IDENT @ 3..8: 'abort' 
+                    PARAM_LIST @ 8..10:
+                      LEFT_PAREN @ 8..9: '(' 
+                      RIGHT_PAREN @ 9..10: ')' 
+                    WHITESPACE @ 10..11: ' ' 
+                    DASH_ARROW @ 11..13: '->' 
+                    WHITESPACE @ 13..14: ' ' 
+                    NEVER_TYPE @ 14..15:
+                      BANG @ 14..15: '!' 
+                    WHITESPACE @ 15..16: ' ' 
+                    EXTERN_KW @ 16..22: 'extern' 
+                    SEMICOLON @ 22..23: ';' "#]],
+        );
+    }
+
+    #[test]
+    fn fn_with_attr() {
+        check_tree(
+            "#[test] fn foo() {}",
+            expect_test::expect![[r#"
+                MODULE @ 0..19:
+                  FN_ITEM @ 0..19:
+                    ATTR @ 0..7:
+                      HASH @ 0..1: '#' 
+                      LEFT_SQUARE @ 1..2: '[' 
+                      NAME @ 2..6:
+                        IDENT @ 2..6: 'test' 
+                      RIGHT_SQUARE @ 6..7: ']' 
+                    WHITESPACE @ 7..8: ' ' 
+                    FN_KW @ 8..10: 'fn' 
+                    WHITESPACE @ 10..11: ' ' 
+                    NAME @ 11..14:
+                      IDENT @ 11..14: 'foo' 
+                    PARAM_LIST @ 14..16:
+                      LEFT_PAREN @ 14..15: '(' 
+                      RIGHT_PAREN @ 15..16: ')' 
+                    WHITESPACE @ 16..17: ' ' 
+                    BLOCK_EXPR @ 17..19:
+                      LEFT_CURLY @ 17..18: '{' 
+                      RIGHT_CURLY @ 18..19: '}' "#]],
+        );
+    }
+    #[test]
+    fn fn_type_param() {
+        check_tree(
+            "fn apply(f: fn(i32, ...) -> i32) {}",
+            expect_test::expect![[r#"
+                MODULE @ 0..35:
+                  FN_ITEM @ 0..35:
+                    FN_KW @ 0..2: 'fn' 
+                    WHITESPACE @ 2..3: ' ' 
+                    NAME @ 3..8:
+                      IDENT @ 3..8: 'apply' 
+                    PARAM_LIST @ 8..32:
+                      LEFT_PAREN @ 8..9: '(' 
+                      PARAM @ 9..31:
+                        NAME @ 9..10:
+                          IDENT @ 9..10: 'f' 
+                        COLON @ 10..11: ':' 
+                        WHITESPACE @ 11..12: ' ' 
+                        FN_TYPE @ 12..31:
+                          FN_KW @ 12..14: 'fn' 
+                          PARAM_TYPE_LIST @ 14..24:
+                            LEFT_PAREN @ 14..15: '(' 
+                            BASIC_TYPE @ 15..18:
+                              NAME @ 15..18:
+                                IDENT @ 15..18: 'i32' 
+                            COMMA @ 18..19: ',' 
+                            WHITESPACE @ 19..20: ' ' 
+                            VA_PARAM @ 20..23:
+                              DOT_DOT_DOT @ 20..23: '...' 
+                            RIGHT_PAREN @ 23..24: ')' 
+                          WHITESPACE @ 24..25: ' ' 
+                          DASH_ARROW @ 25..27: '->' 
+                          WHITESPACE @ 27..28: ' ' 
+                          BASIC_TYPE @ 28..31:
+                            NAME @ 28..31:
+                              IDENT @ 28..31: 'i32' 
+                      RIGHT_PAREN @ 31..32: ')' 
+                    WHITESPACE @ 32..33: ' ' 
+                    BLOCK_EXPR @ 33..35:
+                      LEFT_CURLY @ 33..34: '{' 
+                      RIGHT_CURLY @ 34..35: '}' "#]],
+        );
+    }
 }
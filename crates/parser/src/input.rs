@@ -1,7 +1,8 @@
 use super::*;
 
-use cst::lexer::{self, Token, TokenCache};
+use cst::lexer::{self, Token, TokenCache, TokenSink};
 use cst::SyntaxKind;
+use std::borrow::Cow;
 
 impl TokenSource for TokenStream {
     fn kind_at(&self, index: usize) -> SyntaxKind {
@@ -29,7 +30,7 @@ impl From<Vec<Token>> for TokenStream {
 }
 
 pub struct Input<'text> {
-    pub(crate) text: &'text str,
+    pub(crate) text: Cow<'text, str>,
     pub(crate) tokens: TokenStream,
     pub(crate) token_cache: TokenCache,
     pub(crate) token_lens: Vec<usize>,
@@ -37,17 +38,51 @@ pub struct Input<'text> {
 
 impl<'text> Input<'text> {
     pub fn lex(text: &'text str) -> Self {
-        let tokens = lexer::lex(text);
+        Self::from_lexed(text, lexer::lex(text))
+    }
+
+    /// Input for `text`, which is the text of `old` after `edit`, relexing
+    /// only around the edit.
+    pub fn lex_incremental(
+        old: &Input,
+        text: &'text str,
+        edit: &lexer::TextEdit,
+    ) -> Self {
+        let old = lexer::Tokens {
+            stream: old.tokens.data.clone(),
+            cache: old.token_cache.clone(),
+        };
+        Self::from_lexed(text, lexer::lex_incremental(&old, text, edit))
+    }
+
+    /// Input for `text` from its already lexed `tokens`.
+    pub fn from_lexed(text: &'text str, tokens: lexer::Tokens) -> Self {
         let token_lens =
             tokens.stream.iter().map(|token| token.len()).collect();
         Input {
-            text,
+            text: Cow::Borrowed(text),
             tokens: tokens.stream.into(),
             token_cache: tokens.cache,
             token_lens,
         }
     }
 
+    /// Input made of exactly `tokens` (plus the trailing `EOF`), bypassing
+    /// the lexer. Useful for testing token sequences the lexer would never
+    /// produce.
+    pub fn from_tokens(tokens: &[(SyntaxKind, &str)]) -> Input<'static> {
+        let mut lexed = lexer::Tokens::new();
+        for &(kind, text) in tokens {
+            lexed.token(kind, text);
+        }
+        lexed.token(SyntaxKind::EOF, "");
+        let text: String = tokens.iter().map(|&(_, text)| text).collect();
+        Input {
+            text: Cow::Owned(text),
+            ..Input::from_lexed("", lexed)
+        }
+    }
+
     pub fn tokens(&self) -> &Vec<lexer::Token> {
         &self.tokens.data
     }
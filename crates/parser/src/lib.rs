@@ -1,5 +1,3 @@
-use std::collections::HashSet;
-
 use cst::green::SyntaxKind::{self, *};
 use cst::syntax;
 use utils::Position;
@@ -60,9 +58,52 @@ mod tests {
         eprintln!("{:#?}", errors);
         expected.assert_eq(&root.to_string());
         assert_eq!(errors.len(), 0);
+        assert_eq!(root.validate_ranges(), Ok(()));
+    }
+
+    pub fn check_errors_from_entry(
+        input: &str,
+        expected: expect_test::Expect,
+        expected_errors: &[&str],
+        entry: grammar::EntryPoint,
+    ) {
+        let Output { root, errors } = parse_str_from_entry(input.trim(), entry);
+        expected.assert_eq(&root.to_string());
+        let errors: Vec<_> = errors.iter().map(|e| e.msg.as_str()).collect();
+        assert_eq!(errors, expected_errors);
+        assert_eq!(root.validate_ranges(), Ok(()));
     }
 
     pub fn check_tree(input: &str, expected: expect_test::Expect) {
         check_from_entry(input.trim(), expected, grammar::EntryPoint::Module);
     }
+
+    #[test]
+    fn parse_from_tokens() {
+        // The lexer would never split `ab` into two identifiers
+        let input = Input::from_tokens(&[
+            (FN_KW, "fn"),
+            (WHITESPACE, " "),
+            (IDENT, "a"),
+            (IDENT, "b"),
+            (LEFT_PAREN, "("),
+            (RIGHT_PAREN, ")"),
+            (LEFT_CURLY, "{"),
+            (RIGHT_CURLY, "}"),
+        ]);
+        let Output { root, errors } = parse(input);
+        assert!(!errors.is_empty());
+        assert_eq!(root.validate_ranges(), Ok(()));
+    }
+
+    #[test]
+    fn unterminated_block_comment() {
+        let Output { root, errors } = parse_str("fn f() {}\n/* f() {}");
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|e| (e.msg.as_str(), e.pos.line, e.len))
+            .collect();
+        assert_eq!(errors, [("unterminated block comment", 2, 9)]);
+        assert_eq!(root.validate_ranges(), Ok(()));
+    }
 }
@@ -26,6 +26,13 @@ impl<'source> EventSink for Builder<'source> {
             .map(|n| self.token_lens[n])
             .sum();
         let text = Self::text_in_range(&self.source, &self.position, len);
+        if let Some(msg) = cst::lexer::token_error(kind, text) {
+            self.errors.push(Error {
+                msg: msg.to_string(),
+                pos: self.position,
+                len,
+            });
+        }
         Self::update_pos_from_text(&mut self.position, text);
         if kind != SyntaxKind::ERROR {
             self.builder.token(kind, text);
@@ -44,7 +51,7 @@ impl<'source> EventSink for Builder<'source> {
         let context = self
             .context_stack
             .last()
-            .map(|c| format!(" in {c:?}"))
+            .map(|c| format!(" in {c}"))
             .unwrap_or_default();
         self.errors.push(Error {
             msg: format!("{msg}{context}"),
@@ -108,8 +115,8 @@ pub(crate) fn parse(
     input: crate::input::Input,
     entry: grammar::EntryPoint,
 ) -> Output {
-    let mut builder =
-        Builder::new(input.text, input.token_lens, input.token_cache);
+    let text = input.text;
+    let mut builder = Builder::new(&text, input.token_lens, input.token_cache);
     parse_impl(entry, &input.tokens, &mut builder);
     builder.finish()
 }
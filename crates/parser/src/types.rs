@@ -1,4 +1,4 @@
-use cst::green::Subtokens;
+use cst::green::{Subtokens, SyntaxKindSet};
 
 use super::*;
 
@@ -6,7 +6,9 @@ pub struct Parser<'tokens> {
     tokens: &'tokens dyn TokenSource,
     token_index: usize,
     events: Vec<Event>,
-    follow_stack: Vec<HashSet<SyntaxKind>>,
+    follow_stack: Vec<SyntaxKindSet>,
+    /// What's being parsed, innermost last, e.g. `function body`
+    context_stack: Vec<String>,
     steps: u64,
 }
 
@@ -71,6 +73,15 @@ impl CompletedMarker {
     }
 }
 
+/// A point in the parse to return to with [`Parser::rollback`], for when a
+/// construct can't be told apart from another without parsing some of it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    token_index: usize,
+    num_events: usize,
+    follow_set: SyntaxKindSet,
+}
+
 impl<'tokens> Parser<'tokens> {
     pub fn new(tokens: &'tokens dyn TokenSource) -> Self {
         Self {
@@ -78,6 +89,7 @@ impl<'tokens> Parser<'tokens> {
             token_index: 0,
             events: Default::default(),
             follow_stack: vec![Default::default()],
+            context_stack: Vec::new(),
             steps: 0,
         }
     }
@@ -125,14 +137,70 @@ impl<'tokens> Parser<'tokens> {
 
     pub fn error(&mut self, msg: impl Into<String>) {
         self.step();
-        self.events.push(Event::Error(msg.into()));
+        let msg = match self.context_stack.last() {
+            Some(context) => format!("in {context}: {}", msg.into()),
+            None => msg.into(),
+        };
+        self.events.push(Event::Error(msg));
+    }
+
+    /// Any errors reported by `action` say that they're in `context`, e.g.
+    /// `in function body: expected '}'`. Only the innermost context is
+    /// mentioned.
+    pub fn with_context(
+        &mut self,
+        context: &str,
+        action: impl FnOnce(&mut Self),
+    ) {
+        self.context_stack.push(context.to_string());
+        action(self);
+        self.context_stack.pop();
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            token_index: self.token_index,
+            num_events: self.events.len(),
+            follow_set: *self.follow_set(),
+        }
+    }
+
+    /// Undo everything parsed since `checkpoint`. Any markers started since
+    /// then are invalidated, and mustn't be completed.
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        assert!(checkpoint.num_events <= self.events.len());
+        self.token_index = checkpoint.token_index;
+        self.events.truncate(checkpoint.num_events);
+        *self.follow_set_mut() = checkpoint.follow_set;
     }
 
     pub fn unexpected(&mut self, kind: SyntaxKind) {
-        self.error(format!("unexpected token {kind:?}"));
+        self.error(format!("unexpected {kind}"));
         self.skip_until_expected();
     }
 
+    /// Wrap tokens in an `ERROR` node until one of `sync_tokens` (or EOF) is
+    /// next, so the parse can resume from a known point instead of
+    /// cascading errors through the rest of the input.
+    pub fn error_recovery_until(&mut self, sync_tokens: &[SyntaxKind]) {
+        let sync_tokens = SyntaxKindSet::from_kinds(sync_tokens);
+        let is_sync =
+            |kind: SyntaxKind| kind == EOF || sync_tokens.contains(kind);
+        if is_sync(self.advance_to_next_non_trivia()) {
+            return;
+        }
+        let m = self.start_node();
+        loop {
+            let (kind, _) = self.peek_next_non_trivia();
+            if is_sync(kind) {
+                break;
+            }
+            self.eat_trivia();
+            self.token(kind);
+        }
+        m.complete(self, ERROR);
+    }
+
     pub fn nth(&self, n: usize) -> SyntaxKind {
         self.tokens.kind_at(n)
     }
@@ -151,7 +219,7 @@ impl<'tokens> Parser<'tokens> {
         if found {
             self.advance_to_next_non_trivia();
         }
-        self.follow_set_mut().remove(&kind);
+        self.follow_set_mut().remove(kind);
         found
     }
 
@@ -185,11 +253,12 @@ impl<'tokens> Parser<'tokens> {
     }
 
     pub fn remove_follow(&mut self, kind: SyntaxKind) {
-        self.follow_set_mut().remove(&kind);
+        self.follow_set_mut().remove(kind);
     }
 
     pub fn add_to_follow_set(&mut self, kinds: &[SyntaxKind]) {
-        self.follow_set_mut().extend(kinds)
+        let follow_set = self.follow_set_mut();
+        *follow_set = follow_set.union(SyntaxKindSet::from_kinds(kinds));
     }
 
     pub fn kind_at(&self, idx: usize, kind: SyntaxKind) -> bool {
@@ -235,9 +304,7 @@ impl<'tokens> Parser<'tokens> {
         if found {
             self.token(kind);
         } else {
-            self.error(format!(
-                "unexpected token {actual_kind:?} (expected {kind:?})"
-            ));
+            self.error(format!("expected {kind}, found {actual_kind}"));
             self.skip_until_expected();
         };
         self.remove_follow(kind);
@@ -268,17 +335,41 @@ impl Parser<'_> {
         loop {
             match self.advance_to_next_non_trivia() {
                 SyntaxKind::EOF => break,
-                kind if self.follow_set().contains(&kind) => break,
+                kind if self.follow_set().contains(kind) => break,
                 kind => self.token(kind),
             }
         }
     }
 
-    fn follow_set(&self) -> &HashSet<SyntaxKind> {
+    fn follow_set(&self) -> &SyntaxKindSet {
         self.follow_stack.last().unwrap()
     }
 
-    fn follow_set_mut(&mut self) -> &mut HashSet<SyntaxKind> {
+    fn follow_set_mut(&mut self) -> &mut SyntaxKindSet {
         self.follow_stack.last_mut().unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_context_prefixes_errors() {
+        let input = Input::from_tokens(&[]);
+        let mut parser = Parser::new(&input.tokens);
+        parser.with_context("function body", |parser| {
+            parser.error("expected '}'");
+            parser.with_context("call", |parser| parser.error("expected ')'"));
+        });
+        parser.error("expected item");
+        assert_eq!(
+            parser.events,
+            [
+                Event::Error("in function body: expected '}'".to_string()),
+                Event::Error("in call: expected ')'".to_string()),
+                Event::Error("expected item".to_string()),
+            ]
+        );
+    }
+}
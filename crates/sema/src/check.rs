@@ -1,8 +1,9 @@
 use crate::{
-    errors::{Error, ErrorKind},
+    errors::{Error, ErrorKind, WarningKind},
     types::*,
 };
 use assert_matches::debug_assert_matches;
+use std::collections::HashSet;
 
 struct Checker<'bir> {
     map: Map,
@@ -12,6 +13,10 @@ struct Checker<'bir> {
     current_function: Option<ID>,
     global_namespace: Option<ID>,
     based_types: Vec<BasedType>,
+    fn_types: Vec<ID>,
+    null_exprs: Vec<ID>,
+    int_literals: Vec<(ID, u64)>,
+    used_names: HashSet<ID>,
 
     check_namespace_parents: bool,
 }
@@ -25,6 +30,10 @@ impl<'bir> Checker<'bir> {
             current_function: None,
             global_namespace: None,
             based_types: Default::default(),
+            fn_types: Default::default(),
+            null_exprs: Default::default(),
+            int_literals: Default::default(),
+            used_names: Default::default(),
             check_namespace_parents: true,
         }
     }
@@ -103,22 +112,28 @@ impl Checker<'_> {
         res
     }
 
-    fn lookup_ref(&self, name: &bir::ID) -> Option<ID> {
+    fn lookup_ref(&mut self, name: &bir::ID) -> Option<ID> {
         self.lookup_in(self.current_namespace?, name)
     }
 
-    fn lookup_in(&self, mut ns: ID, name: &bir::ID) -> Option<ID> {
+    /// Resolve `name` segment by segment, starting in `ns`. Each segment
+    /// found counts as a use, e.g. `foo::bar` uses both `foo` and `bar`.
+    ///
+    /// Only the first segment can be found in an enclosing namespace: the
+    /// rest are relative to the module named by the segment before, so
+    /// `foo::bar` is never a `bar` declared outside of `foo`.
+    fn lookup_in(&mut self, mut ns: ID, name: &bir::ID) -> Option<ID> {
         let name = self.bir.name(name);
         assert!(name.segments.len() > 0);
         let mut result = None;
+        let mut check_parents = self.check_namespace_parents;
         for ident in name.segments.iter() {
-            let id = self
-                .map
-                .ns(ns)?
-                .lookup(&self.map, ident, self.check_namespace_parents)?
-                .id;
+            let id =
+                self.map.ns(ns)?.lookup(&self.map, ident, check_parents)?.id;
+            self.used_names.insert(id);
             result = Some(id);
             ns = id;
+            check_parents = false;
         }
         result
     }
@@ -142,21 +157,17 @@ impl Checker<'_> {
             self.map.kind(return_ty),
             Kind::Type | Kind::Error
         );
-        debug_assert!(param_types
-            .iter()
-            .all(|id| self.map.ty_id(*id).is_some()));
+        debug_assert!(param_types.iter().all(|id| {
+            self.map.ty_id(*id).is_some() || self.map.kind(*id) == Kind::Error
+        }));
 
         let ty = {
             let return_ty = self.map.ty_id(return_ty).unwrap_or(return_ty);
-            // TODO should re-use existing function types with same ret/params (use FoldingSet)
-            self.current_ns().new_ty(
-                None,
-                TypeKind::Function(FunctionType {
-                    return_ty,
-                    is_var_args,
-                    parameters: param_types,
-                }),
-            )
+            self.get_fn_ty(FunctionType {
+                return_ty,
+                is_var_args,
+                parameters: param_types,
+            })
         };
         let proto = self.current_ns().new_fn_proto(ident, bir, return_ty);
         self.map.set_ty(proto.id, ty);
@@ -194,6 +205,12 @@ impl Checker<'_> {
             | ErrorKind::InvalidField
             | ErrorKind::InvalidCallReceiver
             | ErrorKind::InvalidFieldReceiver
+            | ErrorKind::InvalidNullType
+            | ErrorKind::InvalidTestFn
+            | ErrorKind::ExternFnWithBody
+            | ErrorKind::LiteralOverflow
+            | ErrorKind::InvalidOperandType
+            | ErrorKind::AddressOfTemporary
             | ErrorKind::CallToNonFnType => vec![ids[0]],
             ErrorKind::Unification | ErrorKind::InvalidIndexType => {
                 vec![ids[0], ids[1]]
@@ -323,6 +340,23 @@ impl Checker<'_> {
         self.find_based_ty(ty, kind)
             .unwrap_or_else(|| self.add_based_ty(kind, ty))
     }
+
+    /// Function types are compared by ID, so every function type with the
+    /// same signature has to be the same one.
+    fn get_fn_ty(&mut self, fn_ty: FunctionType) -> ID {
+        let existing = self.fn_types.iter().copied().find(|&id| {
+            matches!(
+                &self.map.get::<Type>(id).kind,
+                TypeKind::Function(existing) if *existing == fn_ty
+            )
+        });
+        existing.unwrap_or_else(|| {
+            let id =
+                self.global_ns_mut().new_ty(None, TypeKind::Function(fn_ty));
+            self.fn_types.push(id);
+            id
+        })
+    }
 }
 
 pub fn check(bir: &bir::Map) -> Map {
@@ -349,7 +383,10 @@ pub fn check(bir: &bir::Map) -> Map {
 
     create_modules(&mut ck);
 
-    ck.in_ns(ck.global_ns(), |ck| add_builtin_tys(ck));
+    ck.in_ns(ck.global_ns(), |ck| {
+        add_builtin_tys(ck);
+        add_builtin_fns(ck);
+    });
 
     let tys = check_prototype_tys(&mut ck, bir);
     let fns = check_prototype_fns(&mut ck, bir);
@@ -362,6 +399,12 @@ pub fn check(bir: &bir::Map) -> Map {
         let _ = check_fn_inner(&mut ck, fn_);
     }
 
+    check_null_exprs(&mut ck);
+    check_null_derefs(&mut ck);
+    check_int_literals(&mut ck);
+    check_unused_vars(&mut ck);
+    check_unused_imports(&mut ck);
+
     if !ck.map.any_errors() {
         debug_assert!(ck.no_markers());
     }
@@ -369,6 +412,107 @@ pub fn check(bir: &bir::Map) -> Map {
     ck.map
 }
 
+fn check_null_exprs(ck: &mut Checker) {
+    for expr in std::mem::take(&mut ck.null_exprs) {
+        let is_ptr = ck.map.ty(expr).map_or(false, |ty| ty.is_ptr());
+        if !is_ptr {
+            ck.set_err(expr, ErrorKind::InvalidNullType, &[expr]);
+        }
+    }
+}
+
+/// Dereferences of a pointer that's always null: a `null`, or a variable
+/// initialized to one and never assigned or borrowed. Any other null
+/// dereference is only caught at runtime, if at all.
+fn check_null_derefs(ck: &mut Checker) {
+    // Variables that may no longer hold their initializer
+    let mut changed = HashSet::new();
+    let mut derefs = Vec::new();
+    for expr in ck.bir.exprs() {
+        let bir::ExprKind::Op(op) = &expr.kind else {
+            continue;
+        };
+        match op.kind {
+            bir::OpKind::Assignment => {
+                changed.extend(ck.map.bir_to_id(&op.lhs()))
+            }
+            bir::OpKind::AddressOf => {
+                changed.extend(ck.map.bir_to_id(&op.operands[0]))
+            }
+            bir::OpKind::Deref => derefs.push((expr.id, op.operands[0])),
+            _ => {}
+        }
+    }
+    for (deref, pointer) in derefs {
+        if is_always_null(ck, &changed, &pointer) {
+            ck.map.add_warning(deref, WarningKind::NullDereference);
+        }
+    }
+}
+
+fn is_always_null(ck: &Checker, changed: &HashSet<ID>, expr: &bir::ID) -> bool {
+    match &ck.bir.expr(expr).kind {
+        bir::ExprKind::Literal(lit) => {
+            matches!(ck.bir.lit(lit), bir::Literal::Null)
+        }
+        bir::ExprKind::Cast { val, .. } => is_always_null(ck, changed, val),
+        bir::ExprKind::NameRef { .. } => {
+            let Some(var) = ck.map.bir_to_id(expr) else {
+                return false;
+            };
+            if ck.map.kind(var) != Kind::Var || changed.contains(&var) {
+                return false;
+            }
+            let let_ = ck.bir.let_(&ck.map.bir(var).unwrap());
+            let_.expr
+                .is_some_and(|init| is_always_null(ck, changed, &init))
+        }
+        _ => false,
+    }
+}
+
+/// Whether each integer literal fits its inferred type. Every integer type
+/// is signed, besides the single bit of a `bool`.
+fn check_int_literals(ck: &mut Checker) {
+    for (expr, width) in std::mem::take(&mut ck.int_literals) {
+        let Some(ty) = ck.map.ty(expr).filter(|ty| ty.is_numeric()) else {
+            continue;
+        };
+        let size = ty.int_size() as u64;
+        let max_width = if size == 1 { 1 } else { size - 1 };
+        if width > max_width {
+            ck.set_err(expr, ErrorKind::LiteralOverflow, &[expr]);
+        }
+    }
+}
+
+fn check_unused_vars(ck: &mut Checker) {
+    let unused: Vec<_> = ck
+        .map
+        .nodes()
+        .filter(|&(id, kind)| kind == Kind::Var && !ck.used_names.contains(&id))
+        .filter(|&(id, _)| !ck.map.name(id).unwrap().ident.starts_with('_'))
+        .filter_map(|(id, _)| ck.map.bir(id))
+        .collect();
+    for bir in unused {
+        ck.map.add_warning(bir, WarningKind::UnusedVariable);
+    }
+}
+
+fn check_unused_imports(ck: &mut Checker) {
+    let mut unused: Vec<_> = ck
+        .map
+        .imports()
+        .filter(|(id, _)| !ck.used_names.contains(id))
+        .filter(|(_, import)| !ck.bir.import(import).name.starts_with('_'))
+        .map(|(_, import)| import)
+        .collect();
+    unused.sort_by_key(|import| import.0);
+    for import in unused {
+        ck.map.add_warning(import, WarningKind::UnusedImport);
+    }
+}
+
 fn check_ty_inner(ck: &mut Checker, ty: PrototypeTy) {
     ck.in_ns(ty.id, |ck| {
         let def = ck.bir.typedef(&ck.map.bir(ty.id).unwrap());
@@ -403,6 +547,14 @@ fn create_modules(ck: &mut Checker) {
             ck.map.new_node(Kind::Module)
         };
         ck.map.set_bir(m, mod_.id);
+        if mod_.imported {
+            let parent = ck.bir.mod_(&mod_.parent.unwrap());
+            let import = parent
+                .imports(ck.bir)
+                .find(|import| Some(&import.name) == mod_.ident.as_ref())
+                .unwrap();
+            ck.map.set_import(m, import.id);
+        }
 
         for mod_ in mod_.modules(ck.bir) {
             create_mod_and_children(ck, mod_, Some(m));
@@ -448,12 +600,32 @@ fn check_prototype_fns(ck: &mut Checker, bir: &bir::Map) -> Vec<PrototypeFn> {
                     fn_.is_var_args,
                 )
             });
+            if fn_.is_test {
+                check_test_fn_signature(ck, &proto);
+            }
+            // Only `#[extern_c]` can mark a function with a body as extern
+            if fn_.is_extern && fn_.has_body() {
+                ck.set_err(proto.id, ErrorKind::ExternFnWithBody, &[proto.id]);
+            }
             prototype_fns.push(proto);
         }
     }
     prototype_fns
 }
 
+// `#[test]` functions are called by a synthesized harness, so they must have
+// type `fn() -> void`.
+fn check_test_fn_signature(ck: &mut Checker, proto: &PrototypeFn) {
+    let fn_ty = ck.map.ty(proto.id).unwrap().as_fn_ty();
+    let void_ty = ck.void_type();
+    if !fn_ty.parameters.is_empty()
+        || fn_ty.is_var_args
+        || fn_ty.return_ty != void_ty
+    {
+        ck.set_err(proto.id, ErrorKind::InvalidTestFn, &[proto.id]);
+    }
+}
+
 fn add_builtin_tys(ck: &mut Checker) {
     fn add_ty(ck: &mut Checker, name: &str, kind: TypeKind) -> ID {
         ck.current_ns().new_ty(Some(name), kind)
@@ -471,6 +643,65 @@ fn add_builtin_tys(ck: &mut Checker) {
     add_ty(ck, "i32", TypeKind::Integer { size: 32 });
 }
 
+fn add_builtin_fns(ck: &mut Checker) {
+    // `assert(condition: bool, message: str)`: expanded inline during
+    // lowering to a check that reports `message` and aborts on failure.
+    let assert_ty = {
+        let return_ty = ck.void_type();
+        let parameters = vec![ck.bool_type(), ck.string_type()];
+        ck.current_ns().new_ty(
+            None,
+            TypeKind::Function(FunctionType {
+                return_ty,
+                parameters,
+                is_var_args: false,
+            }),
+        )
+    };
+    ck.map.builtins.assert_fn = Some(ck.current_ns().new_builtin_fn(
+        "assert",
+        assert_ty,
+        &["condition", "message"],
+    ));
+
+    // `for_each(arr: *T, len: i64, f: fn(*T))`: generic over `T`, so the
+    // signature can't be expressed as a function type. Calls are checked by
+    // `check_for_each_call` instead.
+    let for_each_ty = {
+        let return_ty = ck.void_type();
+        ck.current_ns().new_ty(
+            None,
+            TypeKind::Function(FunctionType {
+                return_ty,
+                parameters: Vec::new(),
+                is_var_args: true,
+            }),
+        )
+    };
+    ck.map.builtins.for_each_fn =
+        Some(ck.current_ns().new_builtin_fn("for_each", for_each_ty, &[]));
+
+    // `wrapping_add(a: T, b: T) -> T`: generic over the integer type `T`, and
+    // always wraps on overflow, whatever the overflow mode. Calls are checked
+    // by `check_wrapping_add_call`.
+    let wrapping_add_ty = {
+        let return_ty = ck.void_type();
+        ck.current_ns().new_ty(
+            None,
+            TypeKind::Function(FunctionType {
+                return_ty,
+                parameters: Vec::new(),
+                is_var_args: true,
+            }),
+        )
+    };
+    ck.map.builtins.wrapping_add_fn = Some(ck.current_ns().new_builtin_fn(
+        "wrapping_add",
+        wrapping_add_ty,
+        &[],
+    ));
+}
+
 fn check_fn_inner(ck: &mut Checker, proto: PrototypeFn) -> Result<ID, ID> {
     ck.in_ns(proto.id, |ck| {
         let fn_ = ck.bir.fn_(&proto.bir);
@@ -517,6 +748,7 @@ fn check_fn_inner(ck: &mut Checker, proto: PrototypeFn) -> Result<ID, ID> {
 fn check_typeref(ck: &mut Checker, tyref: &bir::TypeRef) -> ID {
     match &tyref.kind {
         bir::TypeRefKind::Void => ck.void_type(),
+        bir::TypeRefKind::Never => ck.never_type(),
         bir::TypeRefKind::Named { name } => ck
             .lookup_ref(name)
             .unwrap_or_else(|| ck.err(ErrorKind::UnknownType, tyref.id)),
@@ -525,6 +757,26 @@ fn check_typeref(ck: &mut Checker, tyref: &bir::TypeRef) -> ID {
             let pointee_ty = ck.ty_id(pointee);
             ck.get_based_ty(pointee_ty, BasedTypeKind::Pointer)
         }
+        bir::TypeRefKind::Function {
+            return_ty,
+            params,
+            is_var_args,
+        } => {
+            let return_ty = check_typeref(ck, ck.bir.typeref(return_ty));
+            let return_ty = ck.ty_id(return_ty);
+            let parameters = params
+                .iter()
+                .map(|param| {
+                    let param = check_typeref(ck, ck.bir.typeref(param));
+                    ck.ty_id(param)
+                })
+                .collect();
+            ck.get_fn_ty(FunctionType {
+                return_ty,
+                parameters,
+                is_var_args: *is_var_args,
+            })
+        }
     }
 }
 
@@ -534,10 +786,20 @@ fn check_block<'bir>(
 ) -> Result<ID, ID> {
     let id = ck.current_ns().new_block();
     ck.in_ns(id, |ck| {
+        let mut diverged = false;
         for item in scope.items(ck.bir) {
-            check_item(ck, item)?;
+            if diverged {
+                ck.map.add_warning(item.id, WarningKind::UnreachableCode);
+                diverged = false;
+            }
+            let id = check_item(ck, item)?;
+            diverged |= item_diverges(ck.bir, item)
+                || calls_never_returning_fn(ck, item, id);
         }
 
+        if let (true, Some(expr)) = (diverged, scope.return_expr(ck.bir)) {
+            ck.map.add_warning(expr.id, WarningKind::UnreachableCode);
+        }
         let ty = if let Some(expr) = scope.return_expr(ck.bir) {
             let id = check_expr(ck, expr)?;
             ck.ty_id(id)
@@ -550,13 +812,37 @@ fn check_block<'bir>(
     })
 }
 
+/// Whether control never continues past `item`, e.g. `return;`.
+fn item_diverges(bir: &bir::Map, item: &bir::Item) -> bool {
+    match &item.kind {
+        bir::ItemKind::Expr(id) => matches!(
+            bir.expr(id).kind,
+            bir::ExprKind::Return { .. }
+                | bir::ExprKind::Break { .. }
+                | bir::ExprKind::Continue { .. }
+        ),
+        bir::ItemKind::Let(_) | bir::ItemKind::MacroExpansion(_) => false,
+    }
+}
+
+/// Whether `item` is a call to a function returning `!`, e.g. `abort()`.
+fn calls_never_returning_fn(ck: &Checker, item: &bir::Item, id: ID) -> bool {
+    let bir::ItemKind::Expr(expr) = &item.kind else {
+        return false;
+    };
+    matches!(ck.bir.expr(expr).kind, bir::ExprKind::Call { .. })
+        && ck.ty_id(id) == ck.never_type()
+}
+
 fn check_item<'bir>(
     ck: &mut Checker<'bir>,
     item: &'bir bir::Item,
 ) -> Result<ID, ID> {
     match &item.kind {
         bir::ItemKind::Let(id) => check_let(ck, ck.bir.let_(id)),
-        bir::ItemKind::Expr(id) => check_expr(ck, ck.bir.expr(id)),
+        bir::ItemKind::Expr(id) | bir::ItemKind::MacroExpansion(id) => {
+            check_expr(ck, ck.bir.expr(id))
+        }
     }
 }
 
@@ -595,19 +881,43 @@ fn check_expr<'bir>(
         }
         bir::ExprKind::Literal(lit) => {
             let (id, ty) = match ck.bir.lit(lit) {
-                bir::Literal::Number(n) => {
+                bir::Literal::Number { value, source_repr } => {
+                    // No integer type is wider than 64 bits. Whether the
+                    // literal fits its type is checked once the type is known.
+                    let width = value.width();
+                    let value = value.to_u64().ok_or_else(|| {
+                        ck.err(ErrorKind::LiteralOverflow, expr.id)
+                    })?;
                     let ty = ck.new_marker_ty();
-                    (ck.map.new_constant(ty, Constant::Int(*n)), ty)
+                    ck.int_literals.push((expr_id, width));
+                    let constant = Constant::Int {
+                        value,
+                        source_repr: source_repr.clone(),
+                    };
+                    (ck.map.new_constant(ty, constant), ty)
                 }
                 bir::Literal::Str(s) => {
                     let ty = ck.string_type();
                     (ck.map.new_constant(ty, Constant::Str(s.clone())), ty)
                 }
+                bir::Literal::Null => {
+                    // The pointer type of a `null` is inferred from context,
+                    // and checked once all functions have been checked.
+                    let ty = ck.new_marker_ty();
+                    ck.null_exprs.push(expr_id);
+                    (ck.map.new_constant(ty, Constant::Null), ty)
+                }
                 bir::Literal::Struct(s) => {
                     let ty = ck.lookup_ref(&s.name).ok_or_else(|| {
                         ck.err(ErrorKind::UnknownName, expr.id)
                     })?;
-                    (ck.map.new_constant(ty, todo!()), ty)
+                    // Only `S {}` parses, which leaves every field
+                    // uninitialized, so there are no members to check.
+                    debug_assert!(s.members.is_empty());
+                    if !ck.map.ty(ty).is_some_and(|ty| ty.is_aggregate()) {
+                        return Err(ck.err(ErrorKind::UnknownType, expr.id));
+                    }
+                    (ck.map.new_constant(ty, Constant::Struct), ty)
                 }
             };
             ck.map.set_expr_constant(expr_id, id);
@@ -679,6 +989,8 @@ fn check_expr<'bir>(
                 bir::LoopKind::While => ck.void_type(),
             }
         }
+        // There's no macro pass yet, so there's nothing to check
+        bir::ExprKind::MacroCall { .. } => ck.void_type(),
         bir::ExprKind::Cast { val, to } => {
             let expr = check_expr(ck, ck.bir.expr(val))?;
             let tyref = check_typeref(ck, ck.bir.typeref(to));
@@ -738,6 +1050,16 @@ fn check_call_expr(
     let fn_id = lookup_or_err(ck, &called_fn.id, receiver)?;
     ck.map.associate_bir_with_id(*receiver, fn_id);
 
+    match ck.map.intrinsic(fn_id) {
+        Some(Intrinsic::ForEach) => {
+            return check_for_each_call(ck, fn_id, receiver, operands);
+        }
+        Some(Intrinsic::WrappingAdd) => {
+            return check_wrapping_add_call(ck, fn_id, receiver, operands);
+        }
+        _ => {}
+    }
+
     let fn_ty = ck
         .map
         .ty(fn_id)
@@ -763,10 +1085,95 @@ fn check_call_expr(
     if !call_sig_match {
         return Err(ck.err(ErrorKind::UnknownCall, *receiver));
     }
-    ck.map.add_caller(ck.current_fn().id, fn_id);
+    // A call through a fn-typed param doesn't name its callee
+    if ck.map.kind(fn_id) == Kind::Function {
+        ck.map.add_caller(ck.current_fn().id, fn_id);
+    }
     Ok(fn_ty.return_ty)
 }
 
+fn check_for_each_call(
+    ck: &mut Checker,
+    fn_id: ID,
+    receiver: &bir::ID,
+    operands: &Vec<bir::ID>,
+) -> Result<ID, ID> {
+    let args = operands
+        .iter()
+        .map(|id| check_expr(ck, ck.bir.expr(id)))
+        .collect::<Result<Vec<_>, ID>>()?;
+    if args.len() != 3 {
+        return Err(ck.err(ErrorKind::UnknownCall, *receiver));
+    }
+    let (arr, len, callback) = (args[0], args[1], args[2]);
+
+    let mut call_sig_match = true;
+    if !ck.map.ty(arr).map_or(false, Type::is_ptr) {
+        ck.set_err(arr, ErrorKind::InvalidPointeeType, &[arr]);
+        call_sig_match = false;
+    }
+    let index_ty = ck.index_type();
+    if ck.unify(index_ty, len).is_none() {
+        ck.set_err(len, ErrorKind::Unification, &[len, index_ty]);
+        call_sig_match = false;
+    }
+    match ck.map.ty(callback).and_then(Type::into_fn_ty) {
+        Some(fn_ty) if fn_ty.parameters.len() == 1 && !fn_ty.is_var_args => {
+            let param_ty = fn_ty.parameters[0];
+            if ck.unify(param_ty, arr).is_none() {
+                ck.set_err(arr, ErrorKind::Unification, &[arr, param_ty]);
+                call_sig_match = false;
+            }
+        }
+        _ => {
+            ck.set_err(callback, ErrorKind::CallToNonFnType, &[callback]);
+            call_sig_match = false;
+        }
+    }
+    if !call_sig_match {
+        return Err(ck.err(ErrorKind::UnknownCall, *receiver));
+    }
+    let caller = ck.current_fn().id;
+    ck.map.add_caller(caller, fn_id);
+    if ck.map.kind(callback) == Kind::Function {
+        ck.map.add_caller(caller, callback);
+    }
+    Ok(ck.void_type())
+}
+
+fn check_wrapping_add_call(
+    ck: &mut Checker,
+    fn_id: ID,
+    receiver: &bir::ID,
+    operands: &Vec<bir::ID>,
+) -> Result<ID, ID> {
+    let args = operands
+        .iter()
+        .map(|id| check_expr(ck, ck.bir.expr(id)))
+        .collect::<Result<Vec<_>, ID>>()?;
+    if args.len() != 2 {
+        return Err(ck.err(ErrorKind::UnknownCall, *receiver));
+    }
+    let (lhs, rhs) = (args[0], args[1]);
+    let ty = match ck.unify(lhs, rhs) {
+        Some(ty) => ty,
+        None => {
+            ck.set_err(rhs, ErrorKind::Unification, &[rhs, lhs]);
+            return Err(ck.err(ErrorKind::UnknownCall, *receiver));
+        }
+    };
+    let is_int = ck
+        .map
+        .ty(ty)
+        .is_some_and(|ty| ty.is_numeric() || ty.is_marker());
+    if !is_int {
+        ck.set_err(lhs, ErrorKind::InvalidOperandType, &[lhs]);
+        return Err(ck.err(ErrorKind::UnknownCall, *receiver));
+    }
+    ck.map.add_caller(ck.current_fn().id, fn_id);
+    Ok(ty)
+}
+
 fn check_op_expr(ck: &mut Checker, op: &bir::Op) -> Result<ID, ID> {
     match (op.fixity, op.kind) {
         (bir::OpFixity::Infix, kind) => match kind {
@@ -794,7 +1201,20 @@ fn check_op_expr(ck: &mut Checker, op: &bir::Op) -> Result<ID, ID> {
                 let rhs = check_expr(ck, ck.bir.expr(&op.operands[1]))?;
                 match ck.unify(lhs, rhs) {
                     Some(ty) if ck.map.ty(ty).unwrap().is_marker() => {
-                        ck.set_err(lhs, ErrorKind::Unification, &[lhs, rhs]);
+                        // Two untyped integer literals are compared as
+                        // indices. `null` has no such fallback.
+                        let any_null = ck.null_exprs.contains(&lhs)
+                            || ck.null_exprs.contains(&rhs);
+                        if any_null {
+                            ck.set_err(
+                                lhs,
+                                ErrorKind::Unification,
+                                &[lhs, rhs],
+                            );
+                        } else {
+                            let index_ty = ck.index_type();
+                            ck.unify(index_ty, ty);
+                        }
                     }
                     None => {
                         // TODO this should be set on the expr itself
@@ -816,9 +1236,85 @@ fn check_op_expr(ck: &mut Checker, op: &bir::Op) -> Result<ID, ID> {
                 }
                 Ok(ck.void_type())
             }
+            bir::OpKind::Deref | bir::OpKind::AddressOf | bir::OpKind::Not => {
+                unreachable!("`{kind:?}` isn't an infix operator")
+            }
+        },
+        (bir::OpFixity::Prefix, _) => check_prefix_op(ck, op),
+        // Calls and indexing have their own expression kinds, so nothing is
+        // lowered to a postfix op yet.
+        (bir::OpFixity::Postfix, kind) => {
+            unreachable!("no postfix `{kind:?}` operator")
+        }
+    }
+}
+
+fn check_prefix_op(ck: &mut Checker, op: &bir::Op) -> Result<ID, ID> {
+    let operand = check_expr(ck, ck.bir.expr(&op.operands[0]))?;
+    match op.kind {
+        bir::OpKind::Plus | bir::OpKind::Minus => {
+            let is_numeric = ck
+                .map
+                .ty(operand)
+                .is_some_and(|ty| ty.is_numeric() || ty.is_marker());
+            if !is_numeric {
+                ck.set_err(operand, ErrorKind::InvalidOperandType, &[operand]);
+            }
+            Ok(ck.ty_id(operand))
+        }
+        bir::OpKind::Not => {
+            let bool_ty = ck.bool_type();
+            if ck.unify(bool_ty, operand).is_none() {
+                ck.set_err(
+                    operand,
+                    ErrorKind::Unification,
+                    &[operand, bool_ty],
+                );
+            }
+            Ok(bool_ty)
+        }
+        bir::OpKind::Deref => match ck.map.ty(operand) {
+            Some(ty) if ty.is_ptr() => Ok(ty.pointee()),
+            _ => Err(ck.err(ErrorKind::InvalidPointeeType, op.operands[0])),
+        },
+        bir::OpKind::AddressOf => {
+            if !is_place(ck, ck.bir.expr(&op.operands[0])) {
+                return Err(
+                    ck.err(ErrorKind::AddressOfTemporary, op.operands[0])
+                );
+            }
+            let ty = ck.ty_id(operand);
+            Ok(ck.get_based_ty(ty, BasedTypeKind::Pointer))
+        }
+        kind => unreachable!("`{kind:?}` isn't a prefix operator"),
+    }
+}
+
+/// Whether `expr` names a location in memory, whose address can be taken:
+/// a variable or param, a dereference, an index, or a field of a place or
+/// pointer.
+fn is_place(ck: &Checker, expr: &bir::Expr) -> bool {
+    match &expr.kind {
+        bir::ExprKind::NameRef { .. } => {
+            ck.map.bir_to_id(&expr.id).is_some_and(|id| {
+                matches!(ck.map.kind(id), Kind::Var | Kind::Param)
+            })
+        }
+        bir::ExprKind::Index { .. } => true,
+        bir::ExprKind::Op(op) => match op.kind {
+            bir::OpKind::Deref => true,
+            bir::OpKind::FieldAccess => {
+                let receiver = ck.bir.expr(&op.operands[0]);
+                is_place(ck, receiver)
+                    || ck
+                        .map
+                        .bir_to_id(&receiver.id)
+                        .and_then(|id| ck.map.ty(id))
+                        .is_some_and(|ty| ty.is_ptr())
+            }
+            _ => false,
         },
-        (bir::OpFixity::Postfix, _) => todo!(),
-        (bir::OpFixity::Prefix, _) => todo!(),
+        _ => false,
     }
 }
 
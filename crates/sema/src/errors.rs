@@ -15,10 +15,16 @@ pub enum ErrorKind {
     Unification,
     InvalidIndexType,
     InvalidPointeeType,
+    InvalidOperandType,
+    AddressOfTemporary,
     ParamAssignment,
     InvalidField,
     InvalidCallReceiver,
     InvalidFieldReceiver,
+    InvalidNullType,
+    InvalidTestFn,
+    ExternFnWithBody,
+    LiteralOverflow,
 }
 
 impl Error {
@@ -54,6 +60,15 @@ impl Error {
             ErrorKind::InvalidPointeeType => {
                 format!("Can't dereference non-pointer!\n{}", replacements[0])
             }
+            ErrorKind::InvalidOperandType => {
+                format!("Invalid operand type!\n{}", replacements[0])
+            }
+            ErrorKind::AddressOfTemporary => {
+                format!(
+                    "Can't take the address of a temporary!\n{}",
+                    replacements[0]
+                )
+            }
             ErrorKind::ParamAssignment => {
                 format!("Can't assign to param!\n{}", replacements[0])
             }
@@ -72,6 +87,49 @@ impl Error {
                     replacements[0], replacements[1]
                 )
             }
+            ErrorKind::InvalidNullType => {
+                format!("`null` must have a pointer type!\n{}", replacements[0])
+            }
+            ErrorKind::InvalidTestFn => {
+                format!("Test function must be `fn()`!\n{}", replacements[0])
+            }
+            ErrorKind::ExternFnWithBody => {
+                format!(
+                    "Extern function can't have a body!\n{}",
+                    replacements[0]
+                )
+            }
+            ErrorKind::LiteralOverflow => {
+                format!("Integer literal is too large: `{}`", replacements[0])
+            }
+        }
+    }
+}
+
+/// Diagnostics that don't stop compilation, keyed by the offending BIR node.
+#[derive(Debug)]
+pub struct Warning {
+    pub bir: bir::ID,
+    pub kind: WarningKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningKind {
+    UnusedVariable,
+    UnreachableCode,
+    UnusedImport,
+    NullDereference,
+}
+
+impl Warning {
+    pub fn render(&self, text: &str) -> String {
+        match self.kind {
+            WarningKind::UnusedVariable => format!("unused variable: `{text}`"),
+            WarningKind::UnreachableCode => "unreachable code".to_string(),
+            WarningKind::UnusedImport => format!("unused import: `{text}`"),
+            WarningKind::NullDereference => {
+                format!("dereference of null pointer: `{text}`")
+            }
         }
     }
 }
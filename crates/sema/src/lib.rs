@@ -15,4 +15,377 @@ pub mod types;
 pub use types::*;
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+    use ast::Node;
+    use errors::{ErrorKind, WarningKind};
+    use std::sync::Arc;
+
+    fn check_str(text: &str) -> Map {
+        check_str_with_bir(text).1
+    }
+
+    fn check_str_with_bir(text: &str) -> (bir::Map, Map) {
+        check_str_with_imports(text, &[])
+    }
+
+    /// Check `text`, where each `(name, text)` in `imports` can be imported.
+    fn check_str_with_imports(
+        text: &str,
+        imports: &[(&str, &str)],
+    ) -> (bir::Map, Map) {
+        fn parse(text: &str) -> Arc<ast::Module> {
+            let cst = parser::parse_str(text);
+            assert!(cst.errors.is_empty());
+            ast::Module::cast(cst.root).unwrap()
+        }
+        struct Imports<'a>(&'a [(&'a str, &'a str)]);
+        impl bir::translate::AstBuilder for Imports<'_> {
+            type Error = ();
+            fn build(&mut self, name: &str) -> Result<Arc<ast::Module>, ()> {
+                let (_, text) =
+                    self.0.iter().find(|(n, _)| *n == name).ok_or(())?;
+                Ok(parse(text))
+            }
+        }
+        let bir = bir::translate::ast(&parse(text), &mut Imports(imports));
+        debug_assert!(bir.check_structural_integrity().is_empty());
+        let map = check::check(&bir);
+        (bir, map)
+    }
+
+    #[test]
+    fn fn_type_params() {
+        let map = check_str("fn apply(f: fn(i32) -> i32) {}");
+        assert!(!map.any_errors());
+        let (_, apply) = map
+            .all_functions()
+            .find(|(_, f)| f.name(&map).unwrap().ident == "apply")
+            .unwrap();
+        let f = map.ty(apply.params[0]).unwrap();
+        assert_eq!(f.repr(&map), "fn (i32) -> i32");
+        let fn_ty = f.as_fn_ty();
+        assert!(!fn_ty.is_var_args);
+        assert_eq!(map.ty(fn_ty.return_ty).unwrap().repr(&map), "i32");
+    }
+
+    #[test]
+    fn fns_are_fn_typed_args() {
+        let map = check_str(
+            "fn apply(f: fn(i32) -> i32, x: i32) -> i32 { f(x) }
+             fn double(x: i32) -> i32 { x + x }
+             fn main() -> i32 { apply(double, 1) }",
+        );
+        assert!(!map.any_errors());
+        let map = check_str(
+            "fn apply(f: fn(i32) -> i32, x: i32) -> i32 { f(x) }
+             fn wide(x: i64) -> i32 { 1 }
+             fn main() -> i32 { apply(wide, 1) }",
+        );
+        assert!(map
+            .errors()
+            .any(|err| matches!(err.kind, ErrorKind::Unification)));
+    }
+
+    #[test]
+    fn block_tail_expr_is_its_value() {
+        let map = check_str("fn f() -> i32 { { 1 + 2 } }");
+        assert!(!map.any_errors());
+
+        // With a trailing `;`, the block has no value and so is `void`
+        let map = check_str("fn f() -> i32 { { 1 + 2; } }");
+        assert!(map
+            .errors()
+            .any(|err| matches!(err.kind, ErrorKind::Unification)));
+    }
+
+    #[test]
+    fn prefix_ops() {
+        let map = check_str(
+            "fn f(p: *i32, b: bool) -> i32 {
+                let q: *i32 = &*p;
+                let c: bool = !b;
+                -*q + +1
+            }",
+        );
+        assert!(!map.any_errors());
+    }
+
+    #[test]
+    fn prefix_op_operand_types() {
+        let map = check_str("fn f(x: i32, s: str) -> i32 { -s; *x }");
+        assert!(map
+            .errors()
+            .any(|err| matches!(err.kind, ErrorKind::InvalidOperandType)));
+        assert!(map
+            .errors()
+            .any(|err| matches!(err.kind, ErrorKind::InvalidPointeeType)));
+    }
+
+    #[test]
+    fn address_of_place() {
+        let map = check_str(
+            "type S { x: i32 }
+             fn f(x: i32, p: *S) -> *i32 { let s: S = S {}; &s.x; &p.x; &x }",
+        );
+        assert!(!map.any_errors());
+
+        for operand in ["1", "g()", "(x + 1)"] {
+            let map = check_str(&format!(
+                "fn g() -> i32 {{ 1 }} fn f(x: i32) {{ &{operand}; }}"
+            ));
+            assert!(
+                map.errors().any(|err| matches!(
+                    err.kind,
+                    ErrorKind::AddressOfTemporary
+                )),
+                "&{operand}"
+            );
+        }
+    }
+
+    #[test]
+    fn struct_literals() {
+        let map = check_str(
+            "type S { x: i32 }
+             fn f() -> S { let s: S = S {}; S {} }",
+        );
+        assert!(!map.any_errors());
+
+        // `S {}` is an `S`, not whatever type the context wants
+        let map = check_str("type S { x: i32 } fn f() -> i32 { S {} }");
+        assert!(map
+            .errors()
+            .any(|err| matches!(err.kind, ErrorKind::Unification)));
+
+        let map = check_str("fn f() { let s = T {}; }");
+        assert!(map
+            .errors()
+            .any(|err| matches!(err.kind, ErrorKind::UnknownName)));
+    }
+
+    #[test]
+    fn syntax_range_of_fn() {
+        let text = "fn answer() -> i32 { 42 }";
+        let (bir, map) = check_str_with_bir(text);
+        let range = |name: &str| {
+            let (id, _) = map
+                .all_functions()
+                .find(|(id, _)| map.name(*id).unwrap().ident == name)
+                .unwrap();
+            map.syntax_range(id, &bir)
+        };
+        assert_eq!(range("answer"), Some(0..text.len()));
+        assert_eq!(range("assert"), None);
+    }
+
+    #[test]
+    fn unused_imports() {
+        let (bir, map) = check_str_with_imports(
+            "import used; import unused; import _quiet;
+            fn f() -> i32 { used::answer() }",
+            &[
+                ("used", "fn answer() -> i32 { 42 }"),
+                ("unused", "fn answer() -> i32 { 42 }"),
+                ("_quiet", "fn answer() -> i32 { 42 }"),
+            ],
+        );
+        assert!(!map.any_errors());
+        let unused: Vec<_> = map
+            .warnings()
+            .filter(|warning| warning.kind == WarningKind::UnusedImport)
+            .map(|warning| bir.import(&warning.bir).name.as_str())
+            .collect();
+        assert_eq!(unused, ["unused"]);
+    }
+
+    #[test]
+    fn scoped_names_resolve_through_imports() {
+        let (_, map) = check_str_with_imports(
+            "import math; fn f() -> i32 { math::consts::answer() }",
+            &[("math", "mod consts { fn answer() -> i32 { 42 } }")],
+        );
+        assert!(!map.any_errors());
+        let fn_named = |name: &str| {
+            map.all_functions()
+                .find(|(id, _)| map.name(*id).unwrap().ident == name)
+                .unwrap()
+                .0
+        };
+        let callees: Vec<_> = map.callees(fn_named("f")).collect();
+        assert_eq!(callees, [fn_named("answer")]);
+    }
+
+    #[test]
+    fn scoped_names_are_module_relative() {
+        let map = check_str(
+            "mod m { fn inner() -> i32 { 1 } type Inner {} }
+             fn f(x: m::Inner) -> i32 { m::inner() }",
+        );
+        assert!(!map.any_errors());
+
+        // `outer` and `Outer` are visible from inside `m`, but aren't in it
+        let map = check_str(
+            "fn outer() -> i32 { 1 } type Outer {} mod m {}
+             fn f() -> i32 { m::outer() }",
+        );
+        assert!(map
+            .errors()
+            .any(|err| matches!(err.kind, ErrorKind::UnknownName)));
+        let map = check_str(
+            "type Outer {} mod m {}
+             fn f(x: m::Outer) {}",
+        );
+        assert!(map
+            .errors()
+            .any(|err| matches!(err.kind, ErrorKind::UnknownType)));
+    }
+
+    #[test]
+    fn calls_to_never_returning_fns_diverge() {
+        let (bir, map) = check_str_with_bir(
+            "fn abort() -> ! extern;
+             fn f() -> i32 { abort(); 1 }",
+        );
+        assert!(!map.any_errors());
+        let never: Vec<_> = bir
+            .functions_that_return_never()
+            .map(|f| f.identifier.as_str())
+            .collect();
+        assert_eq!(never, ["abort"]);
+        let unreachable = map
+            .warnings()
+            .filter(|warning| warning.kind == WarningKind::UnreachableCode);
+        assert_eq!(unreachable.count(), 1);
+    }
+
+    #[test]
+    fn null_dereference_warns_when_always_null() {
+        let (bir, map) = check_str_with_bir(
+            "fn f(r: *i32) -> i32 {
+                 let p: *i32 = null;
+                 let q: *i32 = null;
+                 q = r;
+                 *p + *q + *r
+             }",
+        );
+        assert!(!map.any_errors());
+        // `q` is reassigned, and `r` could be anything
+        let derefs: Vec<_> = map
+            .warnings()
+            .filter(|warning| warning.kind == WarningKind::NullDereference)
+            .map(|warning| {
+                let bir::ExprKind::Op(op) = &bir.expr(&warning.bir).kind else {
+                    panic!("{warning:?}");
+                };
+                let pointer = bir.expr(&op.operands[0]);
+                pointer.name(&bir).unwrap().segments.join("::")
+            })
+            .collect();
+        assert_eq!(derefs, ["p"]);
+    }
+
+    #[test]
+    fn inner_modules_are_linked_to_their_parent() {
+        let (bir, map) = check_str_with_bir(
+            "mod outer { mod inner { fn answer() -> i32 { 42 } } }
+             fn f() -> i32 { outer::inner::answer() }",
+        );
+        assert!(!map.any_errors());
+        let root = bir.root_module();
+        let [outer] = root.modules[..] else { panic!() };
+        let outer = bir.mod_(&outer);
+        assert_eq!(outer.ident.as_deref(), Some("outer"));
+        assert_eq!(outer.parent, Some(root.id));
+        let [inner] = outer.modules[..] else { panic!() };
+        let inner = bir.mod_(&inner);
+        assert_eq!(inner.ident.as_deref(), Some("inner"));
+        assert_eq!(inner.parent, Some(outer.id));
+        assert_eq!(inner.functions.len(), 1);
+    }
+
+    #[test]
+    fn mutually_recursive_fns_share_a_component() {
+        let map = check_str(
+            "fn even(n: i32) -> i32 { if n == 0 { return 1; } odd(n - 1) }
+             fn odd(n: i32) -> i32 { if n == 0 { return 0; } even(n - 1) }
+             fn f() -> i32 { even(4) }",
+        );
+        assert!(!map.any_errors());
+        let components: Vec<Vec<_>> = map
+            .strongly_connected_components()
+            .iter()
+            .map(|component| {
+                let mut names: Vec<_> = component
+                    .iter()
+                    .map(|id| map.name(*id).unwrap().ident.as_str())
+                    .collect();
+                names.sort();
+                names
+            })
+            .collect();
+        assert_eq!(components, [vec!["even", "odd"], vec!["f"]]);
+    }
+
+    #[test]
+    fn namespace_tree() {
+        let (_, map) = check_str_with_imports(
+            "import m; fn f() -> i32 { m::b(1) }",
+            &[("m", "fn b(x: i32) -> i32 { x } fn a() {}")],
+        );
+        assert!(!map.any_errors());
+        let (m, _) = map.imports().next().unwrap();
+        assert_eq!(
+            map.dump_namespace_tree(m),
+            "Function a: fn () -> void
+  Block
+Function b: fn (i32) -> i32
+  Param x: i32
+  Block
+"
+        );
+    }
+
+    #[test]
+    fn extern_c_fns() {
+        let map = check_str(
+            "#[extern_c] fn memcpy(dst: *i8, src: *i8, n: i64) -> *i8;
+            fn f(dst: *i8, src: *i8) { memcpy(dst, src, 8); }",
+        );
+        assert!(!map.any_errors());
+
+        let map = check_str("#[extern_c] fn f() {}");
+        assert!(map
+            .errors()
+            .any(|err| matches!(err.kind, ErrorKind::ExternFnWithBody)));
+    }
+
+    #[test]
+    fn wrapping_add_operand_types() {
+        let map = check_str("fn f(x: i32) -> i32 { wrapping_add(x, 1) }");
+        assert_eq!(map.errors().count(), 0);
+
+        let map = check_str("fn f(s: str) { wrapping_add(s, s); }");
+        assert!(map
+            .errors()
+            .any(|err| matches!(err.kind, ErrorKind::InvalidOperandType)));
+    }
+
+    #[test]
+    fn literal_overflow() {
+        let overflows = |text| {
+            check_str(text)
+                .errors()
+                .filter(|err| matches!(err.kind, ErrorKind::LiteralOverflow))
+                .count()
+        };
+        assert_eq!(overflows("fn f() { let x: i8 = 127; }"), 0);
+        assert_eq!(overflows("fn f() { let x: i8 = 128; }"), 1);
+        assert_eq!(overflows("fn f() { let x: i8 = 300; }"), 1);
+        assert_eq!(overflows("fn f() { let x: i16 = 0x7fff; }"), 0);
+        assert_eq!(overflows("fn f() { let x: i16 = 0xffff; }"), 1);
+        assert_eq!(overflows("fn f(x: i32) -> i32 { x + 2147483648 }"), 1);
+        assert_eq!(overflows("fn f() -> i64 { 9223372036854775807 }"), 0);
+        assert_eq!(overflows("fn f() -> i64 { 18446744073709551616 }"), 1);
+    }
+}
@@ -1,6 +1,7 @@
-use crate::errors::Error;
+use crate::errors::{Error, Warning, WarningKind};
 use assert_matches::debug_assert_matches;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use utils::vec_graph::{traversal, VecGraph, Vertex};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct ID(pub(crate) usize);
@@ -30,7 +31,9 @@ pub struct Map {
     marked_ids: HashMap<ID, Vec<ID>>,
     parents: HashMap<ID, ID>,
     constant_exprs: HashMap<ID, ID>,
-    callee_to_callers: HashMap<ID, HashSet<ID>>,
+    /// An edge from each function to each function it calls
+    call_graph: VecGraph<ID>,
+    call_graph_vertices: HashMap<ID, Vertex<ID>>,
 
     pub(crate) builtins: Builtins,
 
@@ -39,12 +42,15 @@ pub struct Map {
     namespaces: HashMap<ID, Namespace>,
     functions: HashMap<ID, Function>,
     errors: HashMap<ID, Error>,
+    warnings: Vec<Warning>,
     params: HashMap<ID, Param>,
     vars: HashMap<ID, Var>,
     constants: HashMap<ID, Constant>,
 
     birs: HashMap<ID, bir::ID>,
     associated_bir_ids: HashMap<bir::ID, Vec<ID>>,
+    /// Modules brought into scope by an `import`, and the import itself
+    imports: HashMap<ID, bir::ID>,
 }
 
 impl Map {
@@ -65,6 +71,22 @@ impl Map {
         self.types.values()
     }
 
+    /// Every type, along with its ID, in ID order.
+    pub fn all_types(&self) -> impl Iterator<Item = (ID, &Type)> + '_ {
+        let mut types: Vec<_> =
+            self.types.iter().map(|(&id, ty)| (id, ty)).collect();
+        types.sort_by_key(|&(id, _)| id);
+        types.into_iter()
+    }
+
+    /// Every function, along with its ID, in ID order.
+    pub fn all_functions(&self) -> impl Iterator<Item = (ID, &Function)> + '_ {
+        let mut fns: Vec<_> =
+            self.functions.iter().map(|(&id, fn_)| (id, fn_)).collect();
+        fns.sort_by_key(|&(id, _)| id);
+        fns.into_iter()
+    }
+
     pub fn names(&self) -> impl Iterator<Item = &Name> + '_ {
         self.names.values()
     }
@@ -77,6 +99,10 @@ impl Map {
         self.errors.values()
     }
 
+    pub fn warnings(&self) -> impl Iterator<Item = &Warning> + '_ {
+        self.warnings.iter()
+    }
+
     pub fn parent(&self, id: ID) -> Option<ID> {
         self.parents.get(&id).copied()
     }
@@ -85,6 +111,16 @@ impl Map {
         self.birs.get(&id).copied()
     }
 
+    /// The range of the syntax `id` was checked from, if any. Builtins have
+    /// none, and nodes from an imported module index into that module's text.
+    pub fn syntax_range(
+        &self,
+        id: ID,
+        bir: &bir::Map,
+    ) -> Option<std::ops::Range<usize>> {
+        bir.span(&self.bir(id)?)
+    }
+
     pub fn fn_(&self, id: ID) -> Option<&Function> {
         self.functions.get(&id)
     }
@@ -105,10 +141,74 @@ impl Map {
         self.errors.get(&id)
     }
 
+    /// All variables declared within `fn_`, including those in nested
+    /// blocks. Each block's own variables come first, in declaration order,
+    /// followed by those of the blocks nested in it, depth first.
+    pub fn all_vars_in_function(
+        &self,
+        fn_: ID,
+    ) -> impl Iterator<Item = &Var> + '_ {
+        debug_assert_eq!(self.kind(fn_), Kind::Function);
+        let mut vars = Vec::new();
+        let mut namespaces = vec![fn_];
+        while let Some(ns) = namespaces.pop() {
+            let ns = self.ns(ns).unwrap();
+            vars.extend(ns.vars.iter().map(|id| &self.vars[id]));
+            namespaces.extend(ns.blocks.iter().rev());
+        }
+        vars.into_iter()
+    }
+
     pub fn ns(&self, id: ID) -> Option<&Namespace> {
         self.namespaces.get(&id)
     }
 
+    /// `root` and every namespace nested within it, one member per line and
+    /// indented by depth, for debugging name resolution. Members are sorted
+    /// by name, followed by any blocks in the order they were checked.
+    pub fn dump_namespace_tree(&self, root: ID) -> String {
+        let mut out = String::new();
+        self.dump_namespace(root, 0, &mut out);
+        out
+    }
+
+    fn dump_namespace(&self, ns: ID, depth: usize, out: &mut String) {
+        use std::fmt::Write;
+        let Some(ns) = self.ns(ns) else {
+            return;
+        };
+        let indent = "  ".repeat(depth);
+        let mut members: Vec<_> = ns
+            .members
+            .iter()
+            .map(|id| self.name(*id).unwrap())
+            .collect();
+        members.sort_by(|a, b| a.ident.cmp(&b.ident));
+        for name in members {
+            write!(out, "{indent}{:?} {}", self.kind(name.id), name.ident)
+                .unwrap();
+            if let Some(ty) = self.ty(name.id) {
+                write!(out, ": {}", ty.repr(self)).unwrap();
+            }
+            writeln!(out).unwrap();
+            self.dump_namespace(name.id, depth + 1, out);
+        }
+        for &block in &ns.blocks {
+            writeln!(out, "{indent}Block").unwrap();
+            self.dump_namespace(block, depth + 1, out);
+        }
+    }
+
+    /// Whether `id` is a module brought into scope by an `import`.
+    pub fn is_import(&self, id: ID) -> bool {
+        self.imports.contains_key(&id)
+    }
+
+    /// Every imported module, along with the BIR import that introduced it.
+    pub fn imports(&self) -> impl Iterator<Item = (ID, bir::ID)> + '_ {
+        self.imports.iter().map(|(&id, &import)| (id, import))
+    }
+
     pub fn bir_to_id(&self, bir: &bir::ID) -> Option<ID> {
         self.associated_bir_ids
             .get(bir)
@@ -150,17 +250,62 @@ impl Map {
     pub(crate) fn add_caller(&mut self, caller: ID, callee: ID) {
         debug_assert_eq!(self.kind(caller), Kind::Function);
         debug_assert_eq!(self.kind(callee), Kind::Function);
-        self.callee_to_callers
-            .entry(callee)
-            .or_default()
-            .insert(caller);
+        let caller = self.call_graph_vertex(caller);
+        let callee = self.call_graph_vertex(callee);
+        self.call_graph.add_edge(caller, callee);
+    }
+
+    fn call_graph_vertex(&mut self, fn_: ID) -> Vertex<ID> {
+        *self
+            .call_graph_vertices
+            .entry(fn_)
+            .or_insert_with(|| self.call_graph.add_vertex(fn_))
+    }
+
+    /// The call graph, with an edge from each function to each function it
+    /// calls. Functions that neither call nor are called aren't in it.
+    pub fn callee_graph(&self) -> &VecGraph<ID> {
+        &self.call_graph
     }
 
     pub fn num_callers(&self, fn_: ID) -> usize {
+        self.callers(fn_).count()
+    }
+
+    /// The functions that call `fn_`, in no particular order.
+    pub fn callers(&self, fn_: ID) -> impl Iterator<Item = ID> + '_ {
         debug_assert_eq!(self.kind(fn_), Kind::Function);
-        self.callee_to_callers
+        self.call_graph_vertices
             .get(&fn_)
-            .map_or(0, |callers| callers.len())
+            .into_iter()
+            .flat_map(|v| v.predecessors(&self.call_graph))
+            .map(|caller| *caller.data(&self.call_graph))
+    }
+
+    /// The functions called by `fn_`, in no particular order.
+    pub fn callees(&self, fn_: ID) -> impl Iterator<Item = ID> + '_ {
+        debug_assert_eq!(self.kind(fn_), Kind::Function);
+        self.call_graph_vertices
+            .get(&fn_)
+            .into_iter()
+            .flat_map(|v| v.successors(&self.call_graph))
+            .map(|callee| *callee.data(&self.call_graph))
+    }
+
+    /// Each group of functions in the call graph that all call each other,
+    /// directly or not, e.g. mutually recursive functions. A function that
+    /// isn't recursive is in a group of its own. Callees come before their
+    /// callers.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<ID>> {
+        traversal::strongly_connected_components(&self.call_graph)
+            .into_iter()
+            .map(|component| {
+                component
+                    .into_iter()
+                    .map(|v| *v.data(&self.call_graph))
+                    .collect()
+            })
+            .collect()
     }
 
     pub(crate) fn ns_mut(&mut self, id: ID) -> Option<NamespaceHandle<'_>> {
@@ -254,6 +399,10 @@ impl Map {
         self.errors.insert(id, err);
     }
 
+    pub(crate) fn add_warning(&mut self, bir: bir::ID, kind: WarningKind) {
+        self.warnings.push(Warning { bir, kind });
+    }
+
     pub(crate) fn set_expr_constant(&mut self, expr: ID, const_: ID) {
         debug_assert_eq!(self.kind(expr), Kind::Expr);
         debug_assert_eq!(self.kind(const_), Kind::Constant);
@@ -282,6 +431,12 @@ impl Map {
         self.associated_bir_ids.entry(bir).or_default().push(id);
     }
 
+    pub(crate) fn set_import(&mut self, module: ID, import: bir::ID) {
+        debug_assert_eq!(self.kind(module), Kind::Module);
+        self.imports.insert(module, import);
+        self.associate_bir_with_id(import, module);
+    }
+
     pub fn try_get<T: FromMap>(&self, id: ID) -> Option<&T> {
         <T as FromMap>::try_get(id, self)
     }
@@ -298,6 +453,16 @@ pub struct Builtins {
     pub(crate) bool_type: Option<ID>,
     pub(crate) index_type: Option<ID>,
     pub(crate) never_type: Option<ID>,
+    pub(crate) assert_fn: Option<ID>,
+    pub(crate) for_each_fn: Option<ID>,
+    pub(crate) wrapping_add_fn: Option<ID>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Intrinsic {
+    Assert,
+    ForEach,
+    WrappingAdd,
 }
 
 impl Map {
@@ -324,6 +489,35 @@ impl Map {
             .never_type
             .expect("no never type builtin set?")
     }
+
+    pub fn assert_fn(&self) -> ID {
+        self.builtins.assert_fn.expect("no assert fn builtin set?")
+    }
+
+    pub fn for_each_fn(&self) -> ID {
+        self.builtins
+            .for_each_fn
+            .expect("no for_each fn builtin set?")
+    }
+
+    pub fn wrapping_add_fn(&self) -> ID {
+        self.builtins
+            .wrapping_add_fn
+            .expect("no wrapping_add fn builtin set?")
+    }
+
+    pub fn intrinsic(&self, fn_: ID) -> Option<Intrinsic> {
+        if Some(fn_) == self.builtins.assert_fn {
+            return Some(Intrinsic::Assert);
+        }
+        if Some(fn_) == self.builtins.for_each_fn {
+            return Some(Intrinsic::ForEach);
+        }
+        if Some(fn_) == self.builtins.wrapping_add_fn {
+            return Some(Intrinsic::WrappingAdd);
+        }
+        None
+    }
 }
 
 pub(crate) struct PrototypeTy {
@@ -531,7 +725,7 @@ impl AggregateType {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FunctionType {
     pub return_ty: ID,
     pub parameters: Vec<ID>,
@@ -574,8 +768,14 @@ pub struct Var {
 
 #[derive(Debug)]
 pub enum Constant {
-    Int(usize),
+    /// `source_repr` is the literal as written, e.g. `0xFF` for 255
+    Int {
+        value: u64,
+        source_repr: String,
+    },
     Str(String),
+    Null,
+    Struct,
 }
 
 #[derive(Debug, Clone)]
@@ -646,6 +846,7 @@ pub struct Namespace {
 
     params: Vec<ID>,
     vars: Vec<ID>,
+    blocks: Vec<ID>,
 }
 
 impl Namespace {
@@ -655,6 +856,7 @@ impl Namespace {
             members: Vec::new(),
             params: Vec::default(),
             vars: Vec::default(),
+            blocks: Vec::default(),
         }
     }
 
@@ -801,6 +1003,40 @@ impl<'map> NamespaceHandle<'map> {
         PrototypeFn { id, bir, return_ty }
     }
 
+    pub(crate) fn new_builtin_fn(
+        &mut self,
+        ident: &str,
+        ty: ID,
+        param_idents: &[&str],
+    ) -> ID {
+        let fn_ty = self.map.ty(ty).unwrap().as_fn_ty();
+        debug_assert_eq!(fn_ty.parameters.len(), param_idents.len());
+        let id = self.new_node(Kind::Function);
+        self.add_name(id, ident);
+        self.map.set_ty(id, ty);
+
+        let mut fn_ns = self.map.ns_mut(id).unwrap();
+        let params = param_idents
+            .iter()
+            .zip(fn_ty.parameters.iter())
+            .map(|(ident, ty)| {
+                let param = fn_ns.new_param(ident);
+                fn_ns.map.set_ty(param, *ty);
+                param
+            })
+            .collect();
+        self.map.functions.insert(
+            id,
+            Function {
+                id,
+                return_ty: fn_ty.return_ty,
+                params,
+                prototype: false,
+            },
+        );
+        id
+    }
+
     pub(crate) fn new_param(&mut self, ident: &str) -> ID {
         debug_assert_eq!(self.map.kind(self.id), Kind::Function);
         let id = self.new_node(Kind::Param);
@@ -820,7 +1056,9 @@ impl<'map> NamespaceHandle<'map> {
     }
 
     pub(crate) fn new_block(&mut self) -> ID {
-        self.new_node(Kind::Block)
+        let id = self.new_node(Kind::Block);
+        self.ns().blocks.push(id);
+        id
     }
 
     pub(crate) fn new_module(&mut self, name: Option<&str>) -> ID {
@@ -17,13 +17,52 @@ fn check_exists(kind: &str, path: &Path) -> Result<()> {
     Ok(())
 }
 
+#[derive(Default)]
+struct Options {
+    /// Compile each file with `--action=test`, running its `#[test]` fns
+    /// instead of `main`.
+    run_tests: bool,
+    /// Only run tests whose path contains this substring.
+    filter: Option<String>,
+    /// Print the tests that would run, without running them.
+    list: bool,
+    /// Print each command as it's run, and each test that passes.
+    verbose: bool,
+    /// Passed to the compiler for tests without an `.args` sidecar.
+    compiler_args: Vec<String>,
+}
+
+const USAGE: &str = "USAGE: [--run-tests] [--filter <substring>] [--list] \
+                     [--verbose] <compiler> <run-dir> \
+                     [-- <compiler args>...]";
+
 fn main() -> Result<()> {
-    if std::env::args().count() < 3 {
-        eprintln!("USAGE: <compiler> <run-dir>");
+    let mut options = Options::default();
+    let mut args = Vec::new();
+    let mut argv = std::env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "--run-tests" => options.run_tests = true,
+            "--list" => options.list = true,
+            "--verbose" => options.verbose = true,
+            "--" => options.compiler_args.extend(argv.by_ref()),
+            "--filter" => match argv.next() {
+                Some(filter) => options.filter = Some(filter),
+                None => Err("`--filter` expects a substring")?,
+            },
+            _ if arg.starts_with("--filter=") => {
+                options.filter = Some(arg["--filter=".len()..].to_string());
+            }
+            _ if arg.starts_with("--") => Err(format!("unknown flag `{arg}`"))?,
+            _ => args.push(arg),
+        }
+    }
+    if args.len() < 2 {
+        eprintln!("{USAGE}");
         std::process::exit(1);
     }
-    let compiler_binary = std::env::args().nth(1).map(PathBuf::from).unwrap();
-    let run_dir = std::env::args().nth(2).map(PathBuf::from).unwrap();
+    let compiler_binary = PathBuf::from(&args[0]);
+    let run_dir = PathBuf::from(&args[1]);
 
     check_exists("compiler binary", &compiler_binary)?;
     check_exists("run dir", &run_dir)?;
@@ -34,11 +73,24 @@ fn main() -> Result<()> {
     let mut num_passes = 0;
     for ty_file in glob::glob(pattern.to_str().unwrap())? {
         let ty_file = ty_file.unwrap();
+        if let Some(filter) = &options.filter {
+            if !ty_file.to_str().unwrap().contains(filter.as_str()) {
+                continue;
+            }
+        }
+        if options.list {
+            println!("{}: test", ty_file.display());
+            num_tests += 1;
+            continue;
+        }
 
         num_tests += 1;
-        match run_test(&ty_file, &compiler_binary).unwrap() {
+        match run_test(&ty_file, &compiler_binary, &options).unwrap() {
             TestStatus::Pass => {
                 num_passes += 1;
+                if options.verbose {
+                    println!("pass: {}", ty_file.display());
+                }
             }
             TestStatus::CompFail(s) => {
                 println!("===========================");
@@ -55,6 +107,11 @@ fn main() -> Result<()> {
             }
         }
     }
+    if options.list {
+        println!();
+        println!("{num_tests} tests");
+        return Ok(());
+    }
     let num_fails = num_tests - num_passes;
     println!("pass: {}", num_passes);
     println!("fail: {}", num_fails);
@@ -72,10 +129,28 @@ enum TestStatus {
     CompFail(String),
 }
 
-fn run_test(ty_path: &Path, compiler_binary: &Path) -> Result<TestStatus> {
-    let run_compile = Command::new(compiler_binary)
-        .arg(&ty_path)
-        .args(["-o", "./a.out"])
+fn run_test(
+    ty_path: &Path,
+    compiler_binary: &Path,
+    options: &Options,
+) -> Result<TestStatus> {
+    let mut compile = Command::new(compiler_binary);
+    if options.run_tests {
+        compile.arg("--action=test");
+    }
+    // One argument per line; a test's own arguments replace the defaults
+    let args_path = ty_path.with_extension("args");
+    if args_path.exists() {
+        let args = read_or_empty_if_not_exist(&args_path);
+        compile.args(args.lines().filter(|arg| !arg.trim().is_empty()));
+    } else {
+        compile.args(&options.compiler_args);
+    }
+    compile.arg(&ty_path).args(["-o", "./a.out"]);
+    if options.verbose {
+        println!("$ {}", command_line(&compile));
+    }
+    let run_compile = compile
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .spawn()?
@@ -85,8 +160,12 @@ fn run_test(ty_path: &Path, compiler_binary: &Path) -> Result<TestStatus> {
             run_compile.stderr,
         )?));
     }
-    let (run_stdout, run_stderr) = {
-        let mut process = Command::new("./a.out")
+    let (run_stdout, run_stderr, run_status) = {
+        let mut run = Command::new("./a.out");
+        if options.verbose {
+            println!("$ {}", command_line(&run));
+        }
+        let mut process = run
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
             .spawn()?;
@@ -100,25 +179,34 @@ fn run_test(ty_path: &Path, compiler_binary: &Path) -> Result<TestStatus> {
             stderr.read_to_string(&mut stderr_str)?;
         }
 
+        let mut status = None;
         let mut elapsed = std::time::Duration::ZERO;
         while elapsed < MAX_RUN_TIME {
             let now = std::time::Instant::now();
             match process.try_wait() {
                 Ok(None) => {}
-                Ok(Some(..)) => break,
+                Ok(Some(s)) => {
+                    status = Some(s);
+                    break;
+                }
                 Err(..) => panic!(),
             }
             elapsed += now - std::time::Instant::now();
         }
-        (stdout_str, stderr_str)
+        (stdout_str, stderr_str, status)
     };
     let stdout_diff = diff_output(ty_path, &run_stdout, "stdout");
     let stderr_diff = diff_output(ty_path, &run_stderr, "stderr");
-    let status = if stdout_diff.is_some() || stderr_diff.is_some() {
+    let exit_diff = diff_exit_code(ty_path, run_status, options.run_tests);
+    let status = if stdout_diff.is_some()
+        || stderr_diff.is_some()
+        || exit_diff.is_some()
+    {
         TestStatus::RunFail(
             [
                 stdout_diff.unwrap_or_default(),
                 stderr_diff.unwrap_or_default(),
+                exit_diff.unwrap_or_default(),
             ]
             .join("\n"),
         )
@@ -131,6 +219,15 @@ fn run_test(ty_path: &Path, compiler_binary: &Path) -> Result<TestStatus> {
     Ok(status)
 }
 
+/// `command` as it would be typed into a shell, give or take quoting.
+fn command_line(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 fn diff_output(
     base_path: &Path,
     actual: &String,
@@ -154,6 +251,41 @@ fn diff_output(
     ))
 }
 
+/// Exit codes are only checked for tests with an `.exit` sidecar, or when
+/// running `#[test]` fns, which must exit successfully. Processes killed by a
+/// signal report `128 + signal`, as a shell would.
+fn diff_exit_code(
+    base_path: &Path,
+    status: Option<std::process::ExitStatus>,
+    expect_success: bool,
+) -> Option<String> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let expected_path = base_path.with_extension("exit");
+    let expected = if expected_path.exists() {
+        read_or_empty_if_not_exist(&expected_path)
+    } else if expect_success {
+        "0".to_string()
+    } else {
+        return None;
+    };
+    let expected = expected.trim();
+    let actual = match status {
+        Some(status) => status
+            .code()
+            .or_else(|| status.signal().map(|signal| 128 + signal))
+            .map_or_else(|| "<unknown>".to_string(), |c| c.to_string()),
+        None => "<timeout>".to_string(),
+    };
+    if expected == actual {
+        return None;
+    }
+    Some(format!(
+        "exit code: expected {expected} ({}), got {actual}",
+        expected_path.display()
+    ))
+}
+
 fn read_or_empty_if_not_exist(path: &PathBuf) -> String {
     match std::fs::read_to_string(path) {
         Ok(v) => v,
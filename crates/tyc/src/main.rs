@@ -31,6 +31,9 @@ impl std::fmt::Display for Error {
 #[clap(author = "Tyler Lanphear", version = "0.1", about = "tylang compiler")]
 struct Args {
     input: String,
+    /// What to do with the input. `none` stops after lexing and parsing,
+    /// reporting any syntax errors, while `raw-none` stops before even
+    /// lexing.
     #[clap(short, long)]
     action: Option<String>,
     #[clap(short, long)]
@@ -39,6 +42,36 @@ struct Args {
     optimize: bool,
     #[clap(short, long)]
     quiet: bool,
+    /// A file whose items are available in the input without importing it
+    #[clap(long)]
+    prelude: Option<String>,
+    /// Additional directories to search for libraries when linking
+    #[clap(short = 'L', long = "library-path")]
+    library_paths: Vec<String>,
+    /// Additional libraries to link against
+    #[clap(short = 'l', long = "library")]
+    libraries: Vec<String>,
+    /// Write a `.map` file next to the output, listing each function in it
+    /// with its size and linkage
+    #[clap(long)]
+    emit_map: bool,
+    /// Print how long each phase took, as JSON, to stderr
+    #[clap(long)]
+    print_stats: bool,
+    /// With `--action=sema`, print the namespace tree instead of each node
+    #[clap(long)]
+    dump_ns: bool,
+    /// With `--action=bir-diff`, a file written by `--action=bir` to diff
+    /// the input's BIR against
+    #[clap(long)]
+    old_bir: Option<String>,
+    /// Stop reporting errors after this many, summarizing the rest
+    #[clap(long)]
+    max_errors: Option<usize>,
+    /// What integer arithmetic does on overflow: `checked` (the default),
+    /// where overflow is UB, `wrapping` or `saturating`
+    #[clap(long)]
+    overflow_mode: Option<lir::OverflowMode>,
 }
 
 fn main() -> () {
@@ -49,8 +82,10 @@ fn main() -> () {
 
         let module_string = read_source(&args.input)?;
         let module_source = utils::Source::read_path(&args.input);
+        let mut stats = Stats::new(args.print_stats);
+        stats.source(&module_source);
 
-        if let Some("none") = action {
+        if let Some("raw-none") = action {
             return Ok(());
         }
 
@@ -61,6 +96,7 @@ fn main() -> () {
         };
 
         let module_lexed = parser::Input::lex(&module_string);
+        stats.phase("lex");
         if let Some("tokens") = action {
             if !args.quiet {
                 println!("{:#?}", module_lexed.tokens());
@@ -69,16 +105,20 @@ fn main() -> () {
         }
 
         let module_cst = parser::parse(module_lexed);
+        stats.phase("parse");
         if !module_cst.errors.is_empty() {
             if !args.quiet {
-                for error in module_cst.errors {
+                report_limited(module_cst.errors, args.max_errors, |error| {
                     let error = module_ctx
                         .pos_ctx_with_label(error.pos.offset, &error.msg);
                     eprintln!("{error}");
-                }
+                });
             }
             return Err(Error::BuildingCST);
         }
+        if let Some("none") = action {
+            return Ok(());
+        }
         if let Some("cst") = action {
             if !args.quiet {
                 pretty_print(&module_cst);
@@ -93,9 +133,20 @@ fn main() -> () {
             }
             return Ok(());
         }
+        if let Some("macro-expand") = action {
+            // There are no macros yet, so the expansion is just the AST
+            if !args.quiet {
+                eprintln!("note: no macros defined");
+                println!("{}", module_ast);
+            }
+            return Ok(());
+        }
 
         let module_bir = {
-            struct AstBuilder;
+            struct AstBuilder {
+                // Parsed once up front, rather than on every request
+                prelude: Option<Arc<ast::Module>>,
+            }
             impl bir::translate::AstBuilder for AstBuilder {
                 type Error = crate::Error;
                 fn build(
@@ -104,22 +155,48 @@ fn main() -> () {
                 ) -> Result<Arc<ast::Module>, Error> {
                     parse_ast(&format!("{module_name}.ty"))
                 }
+                fn prelude(&mut self) -> Option<Arc<ast::Module>> {
+                    self.prelude.clone()
+                }
             }
-            bir::translate::ast(&module_ast, &mut AstBuilder)
+            let prelude = args.prelude.as_deref().map(parse_ast).transpose()?;
+            bir::translate::ast(&module_ast, &mut AstBuilder { prelude })
         };
+        stats.phase("bir");
+        if let Some("outline") = action {
+            if !args.quiet {
+                print!("{}", module_bir.outline());
+            }
+            return Ok(());
+        }
         if let Some("bir") = action {
             if !args.quiet {
                 bir::print(&module_bir);
             }
             return Ok(());
         }
+        if let Some("bir-diff") = action {
+            let old = match &args.old_bir {
+                Some(path) => read_source(path)?,
+                None => String::new(),
+            };
+            if !args.quiet {
+                print!("{}", bir::diff_against(&old, &module_bir));
+            }
+            return Ok(());
+        }
         module_ctx.bir = Some(&module_bir);
 
         let module_sema = sema::check::check(&module_bir);
         module_ctx.sema = Some(&module_sema);
+        stats.phase("sema");
 
         if let Some("sema") = action {
-            if !args.quiet {
+            if !args.quiet && args.dump_ns {
+                let root = &module_bir.root_module().id;
+                let root = module_sema.bir_to_id(root).unwrap();
+                print!("{}", module_sema.dump_namespace_tree(root));
+            } else if !args.quiet {
                 let map = &module_sema;
                 for (id, kind) in map.nodes() {
                     let repr = match kind {
@@ -145,44 +222,97 @@ fn main() -> () {
                     );
                 }
             }
-            report_sema_errs(&module_sema, &module_ctx);
+            report_sema_errs(&module_sema, &module_ctx, args.max_errors);
             return Ok(());
         }
 
-        let num_sema_errors = report_sema_errs(&module_sema, &module_ctx);
+        let num_sema_errors =
+            report_sema_errs(&module_sema, &module_ctx, args.max_errors);
         if num_sema_errors > 0 {
             return Err(Error::SemanticErrors(num_sema_errors));
         }
 
-        let mut module_lir = lir::translate(&module_bir, &module_sema);
+        if let Some("call-graph") = action {
+            if !args.quiet {
+                print_call_graph(&module_sema);
+            }
+            return Ok(());
+        }
+
+        // `test` compiles an executable whose `main` runs each `#[test]` fn
+        let overflow_mode = args.overflow_mode.unwrap_or_default();
+        let mut module_lir = if let Some("test") = action {
+            lir::translate_test_harness(
+                &module_bir,
+                &module_sema,
+                overflow_mode,
+            )
+        } else {
+            lir::translate(&module_bir, &module_sema, overflow_mode)
+        };
+        // Everything in a library is callable from outside it, including
+        // functions the library also calls itself
+        if let Some("lib" | "shared" | "wasm-js") = action {
+            module_lir.export_all();
+        }
+        stats.phase("lir");
         if let Some("lir") = action {
             lir::print(&module_lir);
             if args.optimize {
-                lir::pass::run_pass(
-                    &mut module_lir,
-                    &mut lir::passes::JumpThreading,
-                );
-                lir::pass::run_pass(&mut module_lir, &mut lir::passes::DCE);
+                lir::pass::PassPipeline::standard().run(&mut module_lir);
+            }
+            return Ok(());
+        }
+        if let Some("lir-after-passes") = action {
+            lir::pass::PassPipeline::standard().run(&mut module_lir);
+            if !args.quiet {
+                lir::print(&module_lir);
+            }
+            return Ok(());
+        }
+        if args.optimize {
+            lir::pass::PassPipeline::standard().run(&mut module_lir);
+        }
+        module_lir.dead_function_elimination();
+        stats.phase("passes");
+        if let Some("symbol-table") = action {
+            if !args.quiet {
+                print_symbol_table(&module_lir);
             }
             return Ok(());
         }
 
         let action = match action {
-            None | Some("compile") => codegen::Action::WriteExecutable,
+            None | Some("compile") | Some("test") => {
+                codegen::Action::WriteExecutable
+            }
             Some("llvm-ir") => codegen::Action::WriteIr,
             Some("asm") => codegen::Action::WriteAssembly,
             Some("obj") => codegen::Action::WriteObject,
+            Some("shared") => codegen::Action::WriteSharedLibrary,
+            Some("lib") => codegen::Action::WriteStaticLibrary,
+            Some("profile") => codegen::Action::WriteProfiledExecutable,
+            Some("wasm-js") => codegen::Action::WriteWasmJs,
             Some(action) => {
                 return Err(Error::UnknownAction(action.to_string()));
             }
         };
+        let extra_link_args: Vec<_> = args
+            .library_paths
+            .iter()
+            .map(|dir| format!("-L{dir}"))
+            .chain(args.libraries.iter().map(|name| format!("-l{name}")))
+            .collect();
         codegen::compile(
             &module_lir,
             &args.input,
             args.output_path.as_deref(),
             action,
             args.optimize,
+            args.emit_map,
+            &extra_link_args,
         );
+        stats.phase("codegen");
         Ok(())
     }()
     .unwrap_or_else(|e| {
@@ -191,6 +321,75 @@ fn main() -> () {
     });
 }
 
+/// How big the input was and how long each phase of compilation took,
+/// printed when dropped so that phases before an early return are still
+/// reported.
+struct Stats {
+    enabled: bool,
+    last: std::time::Instant,
+    counts: Vec<(&'static str, usize)>,
+    phases: Vec<(&'static str, std::time::Duration)>,
+}
+
+impl Stats {
+    fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            last: std::time::Instant::now(),
+            counts: Vec::new(),
+            phases: Vec::new(),
+        }
+    }
+
+    /// Record the size of the input source.
+    fn source(&mut self, source: &utils::Source) {
+        self.counts.extend([
+            ("lines", source.line_count()),
+            ("chars", source.char_count()),
+            ("bytes", source.byte_count()),
+        ]);
+    }
+
+    /// Record the time since the last phase finished as `phase`.
+    fn phase(&mut self, phase: &'static str) {
+        let now = std::time::Instant::now();
+        self.phases.push((phase, now - self.last));
+        self.last = now;
+    }
+}
+
+impl Drop for Stats {
+    fn drop(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        // e.g. `{"lines":3,...,"lex":12,"parse":40}`, times in microseconds
+        let counts = self
+            .counts
+            .iter()
+            .map(|(count, n)| format!("\"{count}\":{n}"));
+        let phases = self
+            .phases
+            .iter()
+            .map(|(phase, time)| format!("\"{phase}\":{}", time.as_micros()));
+        let fields: Vec<_> = counts.chain(phases).collect();
+        eprintln!("{{{}}}", fields.join(","));
+    }
+}
+
+fn print_call_graph(map: &sema::Map) {
+    let ident = |id| map.name(id).unwrap().ident.as_str();
+    for (fn_, _) in map.nodes().filter(|(_, k)| *k == sema::Kind::Function) {
+        let mut callees: Vec<_> = map.callees(fn_).collect();
+        if callees.is_empty() {
+            continue;
+        }
+        callees.sort();
+        let callees: Vec<_> = callees.into_iter().map(ident).collect();
+        println!("{} -> {}", ident(fn_), callees.join(", "));
+    }
+}
+
 fn parse_ast(input: &str) -> Result<Arc<ast::Module>, Error> {
     let module_string = read_source(input)?;
     let module_lexed = parser::Input::lex(&module_string);
@@ -202,19 +401,51 @@ fn parse_ast(input: &str) -> Result<Arc<ast::Module>, Error> {
     ast::Module::cast(module_cst.root.clone()).ok_or(Error::ParsingAST)
 }
 
-fn report_sema_errs(module_sema: &sema::Map, module_ctx: &ModuleCtx) -> usize {
-    let mut num_sema_errors = 0;
-    for err in module_sema.errors() {
-        num_sema_errors += 1;
-        report_sema_err(module_ctx, err);
+/// Report every warning, then each error up to `max_errors`. Returns how
+/// many errors there were; warnings don't count.
+fn report_sema_errs(
+    module_sema: &sema::Map,
+    module_ctx: &ModuleCtx,
+    max_errors: Option<usize>,
+) -> usize {
+    for warning in module_sema.warnings() {
+        report_sema_warning(module_ctx, warning);
     }
-    num_sema_errors
+    report_limited(module_sema.errors(), max_errors, |err| {
+        report_sema_err(module_ctx, err)
+    })
+}
+
+/// Report each of `errors`, up to `max_errors` of them, followed by a count
+/// of any that weren't. Returns how many errors there were in all.
+fn report_limited<T>(
+    errors: impl IntoIterator<Item = T>,
+    max_errors: Option<usize>,
+    mut report: impl FnMut(T),
+) -> usize {
+    let max_errors = max_errors.unwrap_or(usize::MAX);
+    let mut num_errors = 0;
+    for err in errors {
+        if num_errors < max_errors {
+            report(err);
+        }
+        num_errors += 1;
+    }
+    if num_errors > max_errors {
+        eprintln!("... and {} more errors", num_errors - max_errors);
+    }
+    num_errors
 }
 
 fn report_sema_err(ctx: &ModuleCtx, err: &sema::errors::Error) {
     use sema::errors::ErrorKind;
+    let in_fn = err
+        .ids
+        .first()
+        .and_then(|id| ctx.enclosing_fn_of(id))
+        .map_or(String::new(), |name| format!("in function '{name}': "));
     eprintln!(
-        "{}",
+        "{in_fn}{}",
         match err.kind {
             ErrorKind::DuplicateBinding => {
                 let id = &err.ids[0];
@@ -274,6 +505,20 @@ fn report_sema_err(ctx: &ModuleCtx, err: &sema::errors::Error) {
                     ctx.sema_ctx_with_label(expr, &ctx.type_of(expr)),
                 )
             }
+            ErrorKind::InvalidOperandType => {
+                let expr = &err.ids[0];
+                format!(
+                    "Invalid operand type!\n{}",
+                    ctx.sema_ctx_with_label(expr, &ctx.type_of(expr)),
+                )
+            }
+            ErrorKind::AddressOfTemporary => {
+                let expr = &err.ids[0];
+                format!(
+                    "Can't take the address of a temporary!\n{}",
+                    ctx.sema_ctx_with_label(expr, &ctx.type_of(expr)),
+                )
+            }
             ErrorKind::ParamAssignment => {
                 let expr = &err.ids[0];
                 format!(
@@ -308,10 +553,59 @@ fn report_sema_err(ctx: &ModuleCtx, err: &sema::errors::Error) {
                     ctx.sema_ctx_with_label(expr, &ty)
                 )
             }
+            ErrorKind::InvalidNullType => {
+                let expr = &err.ids[0];
+                format!(
+                    "`null` must have a pointer type!\n{}",
+                    ctx.sema_ctx_with_label(expr, &ctx.type_of(expr)),
+                )
+            }
+            ErrorKind::InvalidTestFn => {
+                let fn_ = &err.ids[0];
+                format!(
+                    "Test function must be `fn()`!\n{}",
+                    ctx.sema_ctx_with_label(fn_, &ctx.type_of(fn_)),
+                )
+            }
+            ErrorKind::ExternFnWithBody => {
+                let fn_ = &err.ids[0];
+                format!(
+                    "Extern function can't have a body!\n{}",
+                    ctx.sema_ctx_with_label(fn_, &ctx.type_of(fn_)),
+                )
+            }
+            ErrorKind::LiteralOverflow => {
+                let id = &err.ids[0];
+                ctx.sema_ctx_with_label(
+                    id,
+                    &format!(
+                        "Integer literal is too large: `{}`",
+                        ctx.text_of(id)
+                    ),
+                )
+            }
         }
     );
 }
 
+fn report_sema_warning(ctx: &ModuleCtx, warning: &sema::errors::Warning) {
+    use sema::errors::WarningKind;
+    let id = &warning.bir;
+    let in_fn = ctx
+        .bir_enclosing_fn_of(id)
+        .map_or(String::new(), |name| format!("in function '{name}': "));
+    let text = match warning.kind {
+        WarningKind::UnusedVariable => ctx.bir().let_(id).ident.clone(),
+        WarningKind::UnusedImport => ctx.bir().import(id).name.clone(),
+        WarningKind::UnreachableCode | WarningKind::NullDereference => ctx
+            .bir()
+            .ast(id)
+            .map_or_else(String::new, |ast| ast.syntax().text().trim().into()),
+    };
+    let label = format!("warning: {}", warning.render(&text));
+    eprintln!("{in_fn}{}", ctx.bir_ctx_with_label(id, &label));
+}
+
 struct ModuleCtx<'ctx> {
     source: &'ctx utils::Source,
     bir: Option<&'ctx bir::Map>,
@@ -349,8 +643,18 @@ impl ModuleCtx<'_> {
     }
 
     fn sema_ctx_with_label(&self, id: &sema::ID, label: &str) -> String {
-        self.syntax_of(id)
-            .map(|node| self.range_ctx_with_label(node.range(), label))
+        self.sema()
+            .syntax_range(*id, self.bir())
+            .map(|range| self.range_ctx_with_label(range, label))
+            .unwrap_or_else(|| {
+                format!("{}\n[err getting context] {:?}", label, id)
+            })
+    }
+
+    fn bir_ctx_with_label(&self, id: &bir::ID, label: &str) -> String {
+        self.bir()
+            .span(id)
+            .map(|range| self.range_ctx_with_label(range, label))
             .unwrap_or_else(|| {
                 format!("{}\n[err getting context] {:?}", label, id)
             })
@@ -370,6 +674,15 @@ impl ModuleCtx<'_> {
         self.syntax_of(id).map(|node| node.text()).unwrap()
     }
 
+    fn enclosing_fn_of(&self, id: &sema::ID) -> Option<&str> {
+        self.bir_enclosing_fn_of(&self.sema().bir(*id)?)
+    }
+
+    fn bir_enclosing_fn_of(&self, id: &bir::ID) -> Option<&str> {
+        let fn_id = self.bir().find_enclosing_function(*id)?;
+        Some(&self.bir().fn_(&fn_id).identifier)
+    }
+
     fn syntax_of(&self, id: &sema::ID) -> Option<cst::syntax::Node> {
         let bir_id = self.sema().bir(*id)?;
         let ast = self.bir().ast(&bir_id)?;
@@ -390,6 +703,23 @@ fn read_source(input_path: &str) -> Result<String, Error> {
     }
 }
 
+/// Each function symbol in the output, as `<name>\t<type>\t<linkage>`,
+/// sorted by name.
+fn print_symbol_table(module_lir: &lir::Module) {
+    let mut symbols: Vec<_> = module_lir
+        .functions
+        .iter()
+        .map(|f| {
+            let linkage = if f.internal { "internal" } else { "external" };
+            (&f.ident, linkage)
+        })
+        .collect();
+    symbols.sort();
+    for (name, linkage) in symbols {
+        println!("{name}\tfunction\t{linkage}");
+    }
+}
+
 fn pretty_print(output: &parser::Output) {
     println!("{}", output.root);
 }
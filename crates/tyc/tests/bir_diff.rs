@@ -0,0 +1,28 @@
+mod common;
+
+use common::{run, tyc, TempDir};
+
+#[test]
+fn bir_diff_shows_only_changed_functions() {
+    let dir = TempDir::new("bir-diff");
+    let input =
+        dir.write("input.ty", "fn f() -> i32 { 1 } fn g() -> i32 { 2 }");
+    let old_bir =
+        dir.write("old.bir", &run(tyc().arg("--action=bir").arg(&input)));
+    let bir_diff = || {
+        run(tyc()
+            .args(["--action=bir-diff", "--old-bir", &old_bir])
+            .arg(&input))
+    };
+    assert_eq!(bir_diff(), "");
+
+    dir.write("input.ty", "fn f() -> i32 { 1 } fn g() -> i32 { 3 }");
+    let diff = bir_diff();
+    let changed: Vec<_> = diff
+        .lines()
+        .filter(|line| line.starts_with(['-', '+']))
+        .filter(|line| !line.starts_with("---") && !line.starts_with("+++"))
+        .collect();
+    assert_eq!(changed.len(), 2, "{diff}");
+    assert!(changed.iter().all(|line| !line.contains('1')), "{diff}");
+}
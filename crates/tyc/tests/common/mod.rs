@@ -0,0 +1,75 @@
+//! Helpers shared by the integration tests. Each test file is its own
+//! crate, and not all of them use every helper.
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A directory of files for one test, removed when the test finishes.
+///
+/// The directory is named after the test and this process, so tests that
+/// run in parallel, or in two `cargo test`s at once, don't share files.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(test: &str) -> Self {
+        let dir = std::env::temp_dir()
+            .join(format!("tyc-{test}-test-{}", std::process::id()));
+        // Left behind by an earlier process with the same ID
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn path(&self, name: &str) -> String {
+        self.0.join(name).to_str().unwrap().to_string()
+    }
+
+    /// Writes `contents` to `name` in the directory, returning its path.
+    pub fn write(&self, name: &str, contents: &str) -> String {
+        let path = self.path(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+pub fn tyc() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_tyc"))
+}
+
+/// Runs `command`, which must succeed, and returns what it printed.
+pub fn run(command: &mut Command) -> String {
+    let output = command.output().unwrap();
+    assert!(
+        output.status.success(),
+        "{command:?} failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Runs `command`, which may fail, and returns what it printed to stderr.
+pub fn run_stderr(command: &mut Command) -> String {
+    let output = command.output().unwrap();
+    String::from_utf8(output.stderr).unwrap()
+}
+
+/// Whether `tool` can be run. Tests that need a tool that isn't installed
+/// are skipped, rather than failed, so check this first.
+pub fn has_tool(tool: &str) -> bool {
+    let found = Command::new(tool).arg("--version").output().is_ok();
+    if !found {
+        eprintln!("skipping: `{tool}` isn't installed");
+    }
+    found
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::{run, tyc, TempDir};
+
+const SOURCE: &str = "
+fn printf(fmt: str, ...) -> i32;
+
+fn helper() -> i32 {
+    1
+}
+
+fn main() -> i32 {
+    printf(\"%d\\n\", helper());
+    0
+}
+";
+
+/// The map's (function, linkage) columns, skipping the header.
+fn emit_map(dir: &TempDir, optimize: bool) -> Vec<(String, String)> {
+    run(tyc()
+        .arg("--action=obj")
+        .arg("--emit-map")
+        .args(optimize.then_some("--optimize"))
+        .arg(dir.path("map.ty"))
+        .args(["-o", &dir.path("map.o")]));
+    let map = std::fs::read_to_string(dir.path("map.map")).unwrap();
+    map.lines()
+        .skip(1)
+        .map(|line| {
+            let columns: Vec<_> = line.split_whitespace().collect();
+            (columns[0].to_string(), columns[2].to_string())
+        })
+        .collect()
+}
+
+#[test]
+fn map_lists_functions_that_survive_optimization() {
+    let dir = TempDir::new("emit-map");
+    dir.write("map.ty", SOURCE);
+
+    let entry =
+        |name: &str, linkage: &str| (name.to_string(), linkage.to_string());
+    assert_eq!(
+        emit_map(&dir, false),
+        [entry("helper", "internal"), entry("main", "exported")]
+    );
+    // `helper` is inlined into `main`, and then dropped
+    assert_eq!(emit_map(&dir, true), [entry("main", "exported")]);
+}
@@ -0,0 +1,16 @@
+mod common;
+
+use common::{run_stderr, tyc, TempDir};
+
+#[test]
+fn errors_name_their_enclosing_function() {
+    let dir = TempDir::new("error-context");
+    let path =
+        dir.write("errors.ty", "type T { a: U }\nfn main() -> i32 { x }");
+
+    let stderr = run_stderr(tyc().arg("--action=sema").arg(&path));
+    assert!(stderr.contains("unknown type: `U`"), "{stderr}");
+    assert!(stderr.contains("in function 'main': ["), "{stderr}");
+    // A typedef isn't in any function, so its error gets no prefix
+    assert_eq!(stderr.matches("in function").count(), 1, "{stderr}");
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::{has_tool, run, tyc, TempDir};
+use std::path::Path;
+use std::process::Command;
+
+const LIBRARY: &str = "
+fn add(a: i64, b: i64) -> i64 {
+    a + b
+}
+
+fn add3(a: i64, b: i64, c: i64) -> i64 {
+    add(add(a, b), c)
+}
+";
+
+const HARNESS: &str = r#"
+#include <stdio.h>
+long add(long a, long b);
+long add3(long a, long b, long c);
+int main() {
+    // `add` is called from inside the library too, but is still exported
+    printf("%ld %ld\n", add(40, 2), add3(1, 2, 3));
+    return 0;
+}
+"#;
+
+/// Builds `LIBRARY` into `lib<name>.<extension>` with `action`, and links
+/// the C harness against it.
+///
+/// tyc names its temporary object file after the source, so each test
+/// compiles a differently named one.
+fn check_library(action: &str, name: &str, extension: &str) {
+    if !has_tool("gcc") {
+        return;
+    }
+    let dir = TempDir::new(name);
+    let library = dir.path(&format!("lib{name}.{extension}"));
+    run(tyc()
+        .arg(format!("--action={action}"))
+        .arg(dir.write(&format!("{name}.ty"), LIBRARY))
+        .args(["-o", &library]));
+    assert!(Path::new(&library).exists());
+
+    run(Command::new("gcc")
+        .arg(dir.write("harness.c", HARNESS))
+        .arg(format!("-L{}", dir.dir().display()))
+        .arg(format!("-l{name}"))
+        .args(["-o", &dir.path("harness")]));
+
+    // Only needed to find the shared library
+    let stdout = run(
+        Command::new(dir.path("harness")).env("LD_LIBRARY_PATH", dir.dir())
+    );
+    assert_eq!(stdout, "42 6\n");
+}
+
+#[test]
+fn shared_library_is_callable_from_c() {
+    check_library("shared", "shared", "so");
+}
+
+#[test]
+fn static_library_is_callable_from_c() {
+    check_library("lib", "static", "a");
+}
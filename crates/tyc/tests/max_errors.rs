@@ -0,0 +1,20 @@
+mod common;
+
+use common::{run_stderr, tyc, TempDir};
+
+#[test]
+fn max_errors_summarizes_the_rest() {
+    let dir = TempDir::new("max-errors");
+    let path = dir.write(
+        "errors.ty",
+        "fn main() -> i32 { let a: i32 = x; let b: i32 = y; z }",
+    );
+
+    let stderr = run_stderr(
+        tyc()
+            .args(["--action=sema", "--max-errors", "1"])
+            .arg(&path),
+    );
+    assert_eq!(stderr.matches("unknown name").count(), 1, "{stderr}");
+    assert!(stderr.contains("... and 2 more errors"), "{stderr}");
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use common::{tyc, TempDir};
+
+fn tyc_none(dir: &TempDir, name: &str, source: &str, action: &str) -> bool {
+    tyc()
+        .arg(format!("--action={action}"))
+        .arg("--quiet")
+        .arg(dir.write(name, source))
+        .status()
+        .unwrap()
+        .success()
+}
+
+#[test]
+fn none_reports_syntax_errors() {
+    let dir = TempDir::new("none");
+    assert!(tyc_none(&dir, "valid.ty", "fn main() -> i32 { 0 }", "none"));
+    assert!(!tyc_none(
+        &dir,
+        "invalid.ty",
+        "fn main( -> i32 { 0 }",
+        "none"
+    ));
+    assert!(tyc_none(
+        &dir,
+        "raw.ty",
+        "fn main( -> i32 { 0 }",
+        "raw-none"
+    ));
+}
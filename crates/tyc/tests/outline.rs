@@ -0,0 +1,33 @@
+mod common;
+
+use common::{run, tyc, TempDir};
+
+const SOURCE: &str = "
+type Point { x: i32, y: i32 }
+
+fn printf(fmt: str, ...) -> i32 extern;
+
+fn origin() -> Point {
+    Point {}
+}
+
+fn main() -> i32 {
+    printf(\"%d\\n\", 1);
+    0
+}
+";
+
+#[test]
+fn outline_prints_signatures_without_bodies() {
+    let dir = TempDir::new("outline");
+    let path = dir.write("outline.ty", SOURCE);
+
+    assert_eq!(
+        run(tyc().arg("--action=outline").arg(&path)),
+        "type Point {x: i32, y: i32}
+fn printf(fmt: str, ...) -> i32 extern;
+fn origin() -> Point;
+fn main() -> i32;
+"
+    );
+}
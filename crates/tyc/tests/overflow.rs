@@ -0,0 +1,33 @@
+mod common;
+
+use common::{has_tool, run, tyc, TempDir};
+use std::process::Command;
+
+const SOURCE: &str = r#"
+fn printf(fmt: str, ...) -> i32;
+
+fn main() -> i32 {
+    let max: i32 = 2147483647;
+    let min: i32 = -max - 1;
+    printf("%d %d\n", max + 1, min - 1);
+    printf("%d %d %d\n", max * 2, min * 2, max * -1);
+    0
+}
+"#;
+
+#[test]
+fn saturating_arithmetic_clamps_to_the_type() {
+    if !has_tool("gcc") {
+        return;
+    }
+    let dir = TempDir::new("overflow");
+    run(tyc()
+        .arg("--overflow-mode=saturating")
+        .arg(dir.write("overflow.ty", SOURCE))
+        .args(["-o", &dir.path("overflow")]));
+
+    assert_eq!(
+        run(&mut Command::new(dir.path("overflow"))),
+        "2147483647 -2147483648\n2147483647 -2147483648 -2147483647\n"
+    );
+}
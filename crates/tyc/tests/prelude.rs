@@ -0,0 +1,31 @@
+mod common;
+
+use common::{has_tool, run, tyc, TempDir};
+use std::process::Command;
+
+const PRELUDE: &str = "
+fn answer() -> i32 {
+    42
+}
+";
+
+const MAIN: &str = "
+fn main() -> i32 {
+    answer()
+}
+";
+
+#[test]
+fn prelude_functions_are_callable() {
+    if !has_tool("gcc") {
+        return;
+    }
+    let dir = TempDir::new("prelude");
+    run(tyc()
+        .args(["--prelude", &dir.write("prelude.ty", PRELUDE)])
+        .arg(dir.write("main.ty", MAIN))
+        .args(["-o", &dir.path("main")]));
+
+    let status = Command::new(dir.path("main")).status().unwrap();
+    assert_eq!(status.code(), Some(42));
+}
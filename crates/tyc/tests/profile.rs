@@ -0,0 +1,37 @@
+mod common;
+
+use common::{has_tool, run, tyc, TempDir};
+use std::process::Command;
+
+const PROGRAM: &str = "
+fn fib(n: i64) -> i64 {
+    if n < 2 {
+        return n;
+    }
+    fib(n - 1) + fib(n - 2)
+}
+
+fn main() -> i32 {
+    fib(10);
+    0
+}
+";
+
+#[test]
+fn profiled_executable_writes_profile_on_exit() {
+    if !has_tool("clang") {
+        return;
+    }
+    let dir = TempDir::new("profile");
+    run(tyc()
+        .arg("--action=profile")
+        .arg(dir.write("fib.ty", PROGRAM))
+        .args(["-o", &dir.path("fib")]));
+
+    // The runtime writes to `default.profraw` in the working directory
+    // unless told otherwise
+    let profraw = dir.path("fib.profraw");
+    run(Command::new(dir.path("fib")).env("LLVM_PROFILE_FILE", &profraw));
+    let profile = std::fs::metadata(&profraw).unwrap();
+    assert!(profile.len() > 0);
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::{run, tyc, TempDir};
+
+const SOURCE: &str = "
+fn zeta() -> i32 {
+    1
+}
+
+fn alpha() -> i32 {
+    2
+}
+
+fn main() -> i32 {
+    alpha() + zeta()
+}
+";
+
+/// `SOURCE` with `zeta` and `alpha` swapped, which used to swap them in
+/// the object file too.
+const REORDERED: &str = "
+fn alpha() -> i32 {
+    2
+}
+
+fn zeta() -> i32 {
+    1
+}
+
+fn main() -> i32 {
+    alpha() + zeta()
+}
+";
+
+#[test]
+fn object_files_dont_depend_on_declaration_order() {
+    let dir = TempDir::new("reproducible");
+    // Both are compiled from the same path, so only the order differs
+    let compile = |source: &str, output: &str| {
+        run(tyc()
+            .arg("--action=obj")
+            .arg(dir.write("main.ty", source))
+            .args(["-o", &dir.path(output)]));
+        std::fs::read(dir.path(output)).unwrap()
+    };
+    let first = compile(SOURCE, "first.o");
+    let second = compile(REORDERED, "second.o");
+    assert!(first == second, "object files differ between builds");
+}
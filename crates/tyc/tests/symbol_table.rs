@@ -0,0 +1,30 @@
+mod common;
+
+use common::{run, tyc, TempDir};
+
+const SOURCE: &str = "
+fn printf(fmt: str, ...) -> i32;
+
+fn helper() -> i32 {
+    1
+}
+
+fn main() -> i32 {
+    printf(\"%d\\n\", helper());
+    0
+}
+";
+
+#[test]
+fn symbol_table_lists_functions_by_name() {
+    let dir = TempDir::new("symbol-table");
+    let path = dir.write("symbols.ty", SOURCE);
+
+    assert_eq!(
+        run(tyc().arg("--action=symbol-table").arg(&path)),
+        "helper\tfunction\tinternal
+main\tfunction\texternal
+printf\tfunction\texternal
+"
+    );
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use common::{run, run_stderr, tyc, TempDir};
+
+#[test]
+fn warnings_are_reported_but_not_errors() {
+    let dir = TempDir::new("warnings");
+    let path = dir.write(
+        "warnings.ty",
+        "fn main() -> i32 { let unused: i32 = 1; let p: *i32 = null; *p }",
+    );
+
+    let stderr = run_stderr(tyc().arg("--action=sema").arg(&path));
+    assert!(
+        stderr.contains("warning: unused variable: `unused`"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("warning: dereference of null pointer: `*p`"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("in function 'main': ["), "{stderr}");
+
+    // Actions that stop on sema errors carry on past warnings
+    run(tyc().arg("--action=call-graph").arg(&path));
+}
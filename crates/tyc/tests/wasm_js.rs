@@ -0,0 +1,40 @@
+mod common;
+
+use common::{has_tool, run, tyc, TempDir};
+use std::path::Path;
+use std::process::Command;
+
+const LIBRARY: &str = "
+fn add(a: i64, b: i64) -> i64 {
+    a + b
+}
+
+fn twice(n: i32) -> i32 {
+    n * 2
+}
+";
+
+const HARNESS: &str = r#"
+import { instantiate } from "./add.mjs";
+const { add, twice } = await instantiate();
+console.log(add(40, 2), twice(21));
+"#;
+
+#[test]
+fn wasm_js_module_is_callable_from_node() {
+    if !has_tool("wasm-ld") || !has_tool("node") {
+        return;
+    }
+    let dir = TempDir::new("wasm-js");
+    run(tyc()
+        .arg("--action=wasm-js")
+        .arg(dir.write("add.ty", LIBRARY))
+        .args(["-o", &dir.path("add.wasm")]));
+    assert!(Path::new(&dir.path("add.wasm")).exists());
+    assert!(Path::new(&dir.path("add.mjs")).exists());
+
+    // `i64`s come back as BigInts, `i32`s as Numbers
+    let stdout =
+        run(Command::new("node").arg(dir.write("harness.mjs", HARNESS)));
+    assert_eq!(stdout, "42n 42\n");
+}
@@ -1,5 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use ast::Node;
 use crossbeam_channel::Sender;
 use crossbeam_queue::ArrayQueue;
 use lsp_server::Connection;
@@ -37,7 +38,7 @@ fn server_caps() -> ServerCapabilities {
         Some(lsp_types::TextDocumentSyncCapability::Options({
             let mut options = lsp_types::TextDocumentSyncOptions::default();
             options.open_close = Some(true);
-            options.change = Some(lsp_types::TextDocumentSyncKind::FULL);
+            options.change = Some(lsp_types::TextDocumentSyncKind::INCREMENTAL);
             options
         }));
     server_caps.semantic_tokens_provider = Some(
@@ -52,6 +53,14 @@ fn server_caps() -> ServerCapabilities {
     );
     server_caps.hover_provider =
         Some(lsp_types::HoverProviderCapability::Simple(true));
+    server_caps.document_highlight_provider =
+        Some(lsp_types::OneOf::Left(true));
+    server_caps.definition_provider = Some(lsp_types::OneOf::Left(true));
+    server_caps.code_action_provider =
+        Some(lsp_types::CodeActionProviderCapability::Simple(true));
+    server_caps.workspace_symbol_provider = Some(lsp_types::OneOf::Left(true));
+    server_caps.folding_range_provider =
+        Some(lsp_types::FoldingRangeProviderCapability::Simple(true));
     server_caps
 }
 
@@ -76,34 +85,364 @@ fn initialize_lsp_connection(conn: &Connection) {
     info(conn, "tyls initialized");
 }
 
-fn find_syntax_tree_at_position(
+fn hover_at_position(
     pos: &lsp_types::Position,
     info: &mut ModuleInfo,
 ) -> Option<String> {
     log::debug!("trying to find node at {pos:?}");
+    let offset = offset_at_position(pos, info);
+    let contents = describe_name_at_offset(offset, info)
+        .or_else(|| find_syntax_tree_at_offset(offset, info))?;
+    Some(format!("```tylang\n{contents}\n```"))
+}
+
+fn offset_at_position(
+    pos: &lsp_types::Position,
+    info: &mut ModuleInfo,
+) -> usize {
     let lines_to_offsets = info
         .lines_to_offsets
         .retrieve(|| compute_lines_to_offsets(&info.text));
-    let offset = (lines_to_offsets[&pos.line] + pos.character) as usize;
+    // Clients can send positions past the end of the document, so clamp
+    // rather than index out of bounds.
+    lines_to_offsets
+        .get(&pos.line)
+        .map_or(info.text.len(), |&start| {
+            let start = start as usize;
+            start + column_to_offset(&info.text[start..], pos.character)
+        })
+}
 
-    use cst::syntax::traverse::Step;
+/// The byte offset of `character` into the line `text` starts with. LSP
+/// counts characters in UTF-16 code units, and a character past the end of
+/// the line means the end of the line.
+fn column_to_offset(text: &str, character: u32) -> usize {
+    let mut units = 0;
+    for (offset, c) in text.char_indices() {
+        if units >= character as usize || c == '\r' || c == '\n' {
+            return offset;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
 
-    let node_at_cursor =
-        cst::syntax::traverse::iterate(info.mod_.as_node_or_token(), |node| {
-            for child in node.children_with_tokens() {
-                if child.range().contains(&offset) {
-                    return Step::Continue(child.clone());
-                }
+fn offset_to_position(text: &str, offset: usize) -> lsp_types::Position {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    let character: usize =
+        before[line_start..].chars().map(char::len_utf16).sum();
+    lsp_types::Position::new(line as u32, character as u32)
+}
+
+fn range_to_lsp(text: &str, range: std::ops::Range<usize>) -> lsp_types::Range {
+    lsp_types::Range::new(
+        offset_to_position(text, range.start),
+        offset_to_position(text, range.end),
+    )
+}
+
+/// The syntax of a BIR node, if it was parsed from this module.
+///
+/// Imported modules are checked alongside this one, but their nodes live in
+/// another file's tree and can't be reported here.
+fn syntax_in_module(
+    id: &bir::ID,
+    info: &ModuleInfo,
+) -> Option<cst::syntax::Node> {
+    let syntax = info.bir.as_ref()?.ast(id)?.syntax().clone();
+    let root = syntax.ancestors().last()?;
+    std::sync::Arc::ptr_eq(&root.green, &info.mod_.green).then_some(syntax)
+}
+
+/// The sema node for the identifier under the cursor, if any.
+fn sema_id_at_offset(offset: usize, info: &ModuleInfo) -> Option<sema::ID> {
+    let (bir, sema) = (info.bir.as_ref()?, info.sema.as_ref()?);
+    let token = info.mod_.find_token_at_offset(offset)?;
+    if token.kind() != cst::SyntaxKind::IDENT {
+        return None;
+    }
+    // The innermost BIR node around the cursor that sema knows about
+    let (id, _) = bir
+        .ast_nodes()
+        .filter_map(|(id, _)| {
+            let range = syntax_in_module(&id, info)?.range();
+            if !range.contains(&offset) {
+                return None;
             }
-            Step::Terminate(node)
-        });
+            Some((sema.bir_to_id(&id)?, range.len()))
+        })
+        .min_by_key(|(_, len)| *len)?;
+    Some(id)
+}
+
+/// For an identifier, the signature of the function, variable, etc. it names.
+fn describe_name_at_offset(offset: usize, info: &ModuleInfo) -> Option<String> {
+    let sema = info.sema.as_ref()?;
+    let id = sema_id_at_offset(offset, info)?;
+
+    let ty = |id: sema::ID| {
+        sema.ty(id).map_or("<err>".to_string(), |ty| ty.repr(sema))
+    };
+    let ident = |id: sema::ID| sema.name(id).map(|name| name.ident.as_str());
+    match sema.kind(id) {
+        sema::Kind::Function => {
+            let fn_ = sema.fn_(id)?;
+            let params: Vec<_> = fn_
+                .params
+                .iter()
+                .map(|&param| {
+                    let param = sema.param(param).unwrap();
+                    format!("{}: {}", param.ident(sema), ty(param.id))
+                })
+                .collect();
+            Some(format!(
+                "fn {}({}) -> {}",
+                ident(id)?,
+                params.join(", "),
+                ty(fn_.return_ty)
+            ))
+        }
+        sema::Kind::Var => Some(format!("let {}: {}", ident(id)?, ty(id))),
+        sema::Kind::Param => Some(format!("{}: {}", ident(id)?, ty(id))),
+        sema::Kind::Type => Some(format!("type {}", ty(id))),
+        _ => sema.ty(id).map(|ty| ty.repr(sema)),
+    }
+}
+
+/// Every occurrence of the name under the cursor, e.g. all uses of a `let`.
+fn highlights_at_position(
+    pos: &lsp_types::Position,
+    info: &mut ModuleInfo,
+) -> Option<Vec<lsp_types::DocumentHighlight>> {
+    use lsp_types::{DocumentHighlight, DocumentHighlightKind};
+    let offset = offset_at_position(pos, info);
+    let (bir, sema) = (info.bir.as_ref()?, info.sema.as_ref()?);
+    let target = sema_id_at_offset(offset, info)?;
+    let ident = sema.name(target)?.ident.as_str();
+
+    let assigned: HashSet<bir::ID> = bir
+        .exprs()
+        .filter_map(|expr| match &expr.kind {
+            bir::ExprKind::Op(op) if op.kind == bir::OpKind::Assignment => {
+                Some(op.lhs())
+            }
+            _ => None,
+        })
+        .collect();
+    let highlights = bir
+        .ast_nodes()
+        .filter(|(id, _)| sema.bir_to_id(id) == Some(target))
+        .filter_map(|(id, _)| {
+            let syntax = syntax_in_module(&id, info)?;
+            // Only the identifier itself, not e.g. the whole `let`
+            let token = cst::syntax::traverse::preorder(syntax)
+                .filter_map(|node| node.into_token())
+                .find(|token| {
+                    token.kind() == cst::SyntaxKind::IDENT
+                        && token.text() == ident
+                })?;
+            let kind = if assigned.contains(&id) {
+                DocumentHighlightKind::WRITE
+            } else {
+                DocumentHighlightKind::TEXT
+            };
+            Some(DocumentHighlight {
+                range: range_to_lsp(&info.text, token.range()),
+                kind: Some(kind),
+            })
+        })
+        .collect();
+    Some(highlights)
+}
+
+/// The function, type or module the path under the cursor refers to.
+///
+/// This only needs BIR, not sema's resolution of the name. Definitions in
+/// imported modules live in another file, and aren't found.
+fn definition_at_position(
+    pos: &lsp_types::Position,
+    info: &mut ModuleInfo,
+) -> Option<lsp_types::Range> {
+    let offset = offset_at_position(pos, info);
+    let bir = info.bir.as_ref()?;
+    let (name, _) = bir
+        .names()
+        .filter_map(|name| {
+            let range = syntax_in_module(&name.id, info)?.range();
+            range.contains(&offset).then_some((name, range.len()))
+        })
+        .min_by_key(|(_, len)| *len)?;
+    // Outside of a function, a name can only be part of a typedef
+    let scope = match bir.find_enclosing_function(name.id) {
+        Some(fn_) => bir.fn_(&fn_).mod_,
+        None => bir
+            .typedefs()
+            .find(|ty| {
+                syntax_in_module(&ty.id, info)
+                    .is_some_and(|syntax| syntax.range().contains(&offset))
+            })
+            .map_or(bir.root_module().id, |ty| ty.mod_),
+    };
+    let def = bir.resolve_name_to_definition(name, &scope)?;
+    Some(range_to_lsp(
+        &info.text,
+        syntax_in_module(&def, info)?.range(),
+    ))
+}
+
+/// Quick fixes spelling out the inferred type of each `let` in `range`.
+fn type_annotation_actions(
+    uri: &lsp_types::Url,
+    range: &lsp_types::Range,
+    info: &mut ModuleInfo,
+) -> Vec<lsp_types::CodeAction> {
+    let start = offset_at_position(&range.start, info);
+    let end = offset_at_position(&range.end, info);
+    let (Some(bir), Some(sema)) = (info.bir.as_ref(), info.sema.as_ref())
+    else {
+        return Vec::new();
+    };
+    bir.lets()
+        .filter(|let_| let_.ty.is_none())
+        .filter_map(|let_| {
+            let syntax = syntax_in_module(&let_.id, info)?;
+            let let_range = syntax.range();
+            if let_range.end < start || end < let_range.start {
+                return None;
+            }
+            let ty = sema.ty(sema.bir_to_id(&let_.id)?)?;
+            if ty.is_marker() {
+                return None;
+            }
+            // The binding is the first identifier in the `let`
+            let ident = cst::syntax::traverse::preorder(syntax)
+                .filter_map(|node| node.into_token())
+                .find(|token| token.kind() == cst::SyntaxKind::IDENT)?;
+            let at = offset_to_position(&info.text, ident.range().end);
+            let edit = lsp_types::TextEdit {
+                range: lsp_types::Range::new(at, at),
+                new_text: format!(": {}", ty.repr(sema)),
+            };
+            Some(lsp_types::CodeAction {
+                title: format!("Add explicit type `{}`", ty.repr(sema)),
+                kind: Some(lsp_types::CodeActionKind::QUICKFIX),
+                edit: Some(lsp_types::WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                    ..lsp_types::WorkspaceEdit::default()
+                }),
+                ..lsp_types::CodeAction::default()
+            })
+        })
+        .collect()
+}
+
+const MAX_WORKSPACE_SYMBOLS: usize = 100;
+
+/// Functions and types across all open modules whose name contains `query`.
+fn workspace_symbols(
+    query: &str,
+    modules: &HashMap<String, ModuleInfo>,
+) -> Vec<lsp_types::SymbolInformation> {
+    use lsp_types::{Location, SymbolInformation, SymbolKind};
+    let query = query.to_lowercase();
+    let mut symbols = Vec::new();
+    for (path, info) in modules {
+        let (Some(sema), Ok(uri)) =
+            (info.sema.as_ref(), lsp_types::Url::from_file_path(path))
+        else {
+            continue;
+        };
+        // There are no globals yet, so every `Var` is local to some function
+        let fns = sema
+            .all_functions()
+            .map(|(id, _)| (id, SymbolKind::FUNCTION));
+        let types = sema.all_types().map(|(id, _)| (id, SymbolKind::STRUCT));
+        for (id, kind) in fns.chain(types) {
+            let Some(name) = sema.name(id) else {
+                continue;
+            };
+            if !name.ident.to_lowercase().contains(&query) {
+                continue;
+            }
+            // Builtins have no syntax to point at
+            let Some(syntax) =
+                sema.bir(id).and_then(|bir| syntax_in_module(&bir, info))
+            else {
+                continue;
+            };
+            #[allow(deprecated)]
+            symbols.push(SymbolInformation {
+                name: name.ident.clone(),
+                kind,
+                tags: None,
+                deprecated: None,
+                location: Location::new(
+                    uri.clone(),
+                    range_to_lsp(&info.text, syntax.range()),
+                ),
+                container_name: None,
+            });
+            if symbols.len() == MAX_WORKSPACE_SYMBOLS {
+                return symbols;
+            }
+        }
+    }
+    symbols
+}
+
+/// One region per brace-delimited `type`, `mod`, or block (incl. `fn` bodies).
+fn folding_ranges(info: &ModuleInfo) -> Vec<lsp_types::FoldingRange> {
+    use cst::SyntaxKind;
+    cst::syntax::traverse::preorder(info.mod_.clone())
+        .filter_map(|node| node.into_node())
+        .filter(|node| {
+            matches!(
+                node.kind(),
+                SyntaxKind::TYPE_ITEM
+                    | SyntaxKind::MODULE
+                    | SyntaxKind::BLOCK_EXPR
+            )
+        })
+        .filter_map(|node| {
+            let curly = |kind| {
+                node.children_with_tokens()
+                    .filter_map(|child| child.into_token())
+                    .filter(|token| token.kind() == kind)
+                    .map(|token| token.range().start)
+                    .collect::<Vec<_>>()
+            };
+            let left = *curly(SyntaxKind::LEFT_CURLY).first()?;
+            let right = *curly(SyntaxKind::RIGHT_CURLY).last()?;
+            let start = offset_to_position(&info.text, left);
+            let end = offset_to_position(&info.text, right);
+            (start.line < end.line).then(|| lsp_types::FoldingRange {
+                start_line: start.line,
+                start_character: Some(start.character),
+                end_line: end.line,
+                end_character: Some(end.character),
+                kind: Some(lsp_types::FoldingRangeKind::Region),
+            })
+        })
+        .collect()
+}
+
+fn find_syntax_tree_at_offset(
+    offset: usize,
+    info: &ModuleInfo,
+) -> Option<String> {
+    let node_at_cursor = info.mod_.find_token_at_offset(offset).map_or_else(
+        || info.mod_.as_node_or_token(),
+        cst::syntax::Token::as_node_or_token,
+    );
 
     let repr = |node: &cst::syntax::NodeOrToken| -> String {
         format!("{}: {:?}", node.index(), node.kind())
     };
 
     let mut reprs = vec![repr(&node_at_cursor)];
-    for ancestor in node_at_cursor.ancestors() {
+    for ancestor in cst::syntax::traverse::ancestors(node_at_cursor.clone()) {
         reprs.push(repr(&ancestor.as_node_or_token()));
     }
 
@@ -135,7 +474,11 @@ struct ModuleInfo {
     mod_: cst::syntax::Node,
     errs: Vec<parser::Error>,
     text: String,
+    tokens: cst::lexer::Tokens,
     lines_to_offsets: Provider<HashMap<u32, u32>>,
+    // Only available for modules that parse cleanly
+    bir: Option<bir::Map>,
+    sema: Option<sema::Map>,
 }
 
 impl ModuleInfo {
@@ -143,12 +486,16 @@ impl ModuleInfo {
         mod_: cst::syntax::Node,
         errs: Vec<parser::Error>,
         text: String,
+        tokens: cst::lexer::Tokens,
     ) -> Self {
         Self {
             mod_,
             errs,
             text,
+            tokens,
             lines_to_offsets: Provider::new(),
+            bir: None,
+            sema: None,
         }
     }
 }
@@ -223,15 +570,36 @@ fn dispatch_msg(
                 let params: lsp_types::DidOpenTextDocumentParams =
                     Deserialize::deserialize(not.params).unwrap();
                 let uri = params.text_document.uri;
-                let text = &params.text_document.text;
-                handle_open_or_change(text, uri, message_queue, modules);
+                let text = params.text_document.text;
+                let tokens = cst::lexer::lex(&text);
+                handle_open_or_change(
+                    text,
+                    tokens,
+                    uri,
+                    message_queue,
+                    modules,
+                );
             }
             "textDocument/didChange" => {
                 let params: lsp_types::DidChangeTextDocumentParams =
                     Deserialize::deserialize(not.params).unwrap();
                 let uri = params.text_document.uri;
-                let text = &params.content_changes[0].text;
-                handle_open_or_change(text, uri, message_queue, modules);
+                let Some(module) = modules.get(uri.path()) else {
+                    log::debug!("change to unopened document {uri}");
+                    return;
+                };
+                let (text, tokens) = apply_changes(
+                    module.text.clone(),
+                    module.tokens.clone(),
+                    params.content_changes,
+                );
+                handle_open_or_change(
+                    text,
+                    tokens,
+                    uri,
+                    message_queue,
+                    modules,
+                );
             }
             "textDocument/didClose" => {
                 let params: lsp_types::DidCloseTextDocumentParams =
@@ -252,16 +620,59 @@ fn dispatch_msg(
                     .path();
                 if let Some(module) = modules.get_mut(path) {
                     let pos = params.text_document_position_params.position;
-                    let result = find_syntax_tree_at_position(&pos, module)
-                        .map(|kind| {
-                            serde_json::to_value(lsp_types::Hover {
-                                contents: lsp_types::HoverContents::Markup(
-                                    lsp_types::MarkupContent {
-                                        kind: lsp_types::MarkupKind::PlainText,
-                                        value: kind,
-                                    },
-                                ),
-                                range: None,
+                    let result = hover_at_position(&pos, module).map(|kind| {
+                        serde_json::to_value(lsp_types::Hover {
+                            contents: lsp_types::HoverContents::Markup(
+                                lsp_types::MarkupContent {
+                                    kind: lsp_types::MarkupKind::Markdown,
+                                    value: kind,
+                                },
+                            ),
+                            range: None,
+                        })
+                        .unwrap()
+                    });
+                    let message = Message::Response(lsp_server::Response {
+                        id: req.id,
+                        result,
+                        error: None,
+                    });
+                    message_queue.push(message).unwrap();
+                }
+            }
+            "textDocument/documentHighlight" => {
+                let params: lsp_types::DocumentHighlightParams =
+                    Deserialize::deserialize(req.params).unwrap();
+                let path = params
+                    .text_document_position_params
+                    .text_document
+                    .uri
+                    .path();
+                if let Some(module) = modules.get_mut(path) {
+                    let pos = params.text_document_position_params.position;
+                    let result = highlights_at_position(&pos, module).map(
+                        |highlights| serde_json::to_value(highlights).unwrap(),
+                    );
+                    let message = Message::Response(lsp_server::Response {
+                        id: req.id,
+                        result,
+                        error: None,
+                    });
+                    message_queue.push(message).unwrap();
+                }
+            }
+            "textDocument/definition" => {
+                let params: lsp_types::GotoDefinitionParams =
+                    Deserialize::deserialize(req.params).unwrap();
+                let uri =
+                    params.text_document_position_params.text_document.uri;
+                if let Some(module) = modules.get_mut(uri.path()) {
+                    let pos = params.text_document_position_params.position;
+                    let result =
+                        definition_at_position(&pos, module).map(|range| {
+                            serde_json::to_value(lsp_types::Location {
+                                uri,
+                                range,
                             })
                             .unwrap()
                         });
@@ -273,6 +684,49 @@ fn dispatch_msg(
                     message_queue.push(message).unwrap();
                 }
             }
+            "textDocument/codeAction" => {
+                let params: lsp_types::CodeActionParams =
+                    Deserialize::deserialize(req.params).unwrap();
+                let uri = params.text_document.uri;
+                if let Some(module) = modules.get_mut(uri.path()) {
+                    let actions: lsp_types::CodeActionResponse =
+                        type_annotation_actions(&uri, &params.range, module)
+                            .into_iter()
+                            .map(lsp_types::CodeActionOrCommand::CodeAction)
+                            .collect();
+                    let message = Message::Response(lsp_server::Response {
+                        id: req.id,
+                        result: Some(serde_json::to_value(actions).unwrap()),
+                        error: None,
+                    });
+                    message_queue.push(message).unwrap();
+                }
+            }
+            "textDocument/foldingRange" => {
+                let params: lsp_types::FoldingRangeParams =
+                    Deserialize::deserialize(req.params).unwrap();
+                let path = params.text_document.uri.path();
+                if let Some(module) = modules.get(path) {
+                    let ranges = folding_ranges(module);
+                    let message = Message::Response(lsp_server::Response {
+                        id: req.id,
+                        result: Some(serde_json::to_value(ranges).unwrap()),
+                        error: None,
+                    });
+                    message_queue.push(message).unwrap();
+                }
+            }
+            "workspace/symbol" => {
+                let params: lsp_types::WorkspaceSymbolParams =
+                    Deserialize::deserialize(req.params).unwrap();
+                let symbols = workspace_symbols(&params.query, modules);
+                let message = Message::Response(lsp_server::Response {
+                    id: req.id,
+                    result: Some(serde_json::to_value(symbols).unwrap()),
+                    error: None,
+                });
+                message_queue.push(message).unwrap();
+            }
             "textDocument/semanticTokens/full" => {
                 let params: lsp_types::SemanticTokensParams =
                     Deserialize::deserialize(req.params).unwrap();
@@ -299,13 +753,51 @@ fn dispatch_msg(
     }
 }
 
+/// Apply each of `changes` in turn, relexing only around each edit.
+fn apply_changes(
+    mut text: String,
+    mut tokens: cst::lexer::Tokens,
+    changes: Vec<lsp_types::TextDocumentContentChangeEvent>,
+) -> (String, cst::lexer::Tokens) {
+    for change in changes {
+        match change.range {
+            Some(range) => {
+                let edit = cst::lexer::TextEdit {
+                    range: position_to_offset(&text, range.start)
+                        ..position_to_offset(&text, range.end),
+                    text: change.text,
+                };
+                let new_text = edit.apply(&text);
+                tokens = cst::lexer::lex_incremental(&tokens, &new_text, &edit);
+                text = new_text;
+            }
+            None => {
+                tokens = cst::lexer::lex(&change.text);
+                text = change.text;
+            }
+        }
+    }
+    (text, tokens)
+}
+
+fn position_to_offset(text: &str, pos: lsp_types::Position) -> usize {
+    let line_start: usize = text
+        .split_inclusive('\n')
+        .take(pos.line as usize)
+        .map(str::len)
+        .sum();
+    line_start + column_to_offset(&text[line_start..], pos.character)
+}
+
 fn handle_open_or_change(
-    text: &str,
+    text: String,
+    tokens: cst::lexer::Tokens,
     uri: lsp_types::Url,
     message_queue: &ArrayQueue<Message>,
     modules: &mut HashMap<String, ModuleInfo>,
 ) {
-    match std::panic::catch_unwind(|| parse_module(text)) {
+    let path = std::path::Path::new(uri.path());
+    match std::panic::catch_unwind(|| parse_module(text, tokens, path)) {
         Ok(mod_) => {
             let diagnostics = diagnostics_from_mod(&mod_);
             message_queue
@@ -339,9 +831,14 @@ fn handle_open_or_change(
     }
 }
 
+/// Past this, more diagnostics would only bury the first (and most likely
+/// real) ones, and slow the editor down.
+const MAX_DIAGNOSTICS: usize = 100;
+
 fn diagnostics_from_mod(mod_: &ModuleInfo) -> Vec<lsp_types::Diagnostic> {
     use lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
-    mod_.errs
+    let mut diagnostics: Vec<_> = mod_
+        .errs
         .iter()
         .map(|err| {
             let start = Position::new(err.pos.line - 1, err.pos.column - 1);
@@ -354,10 +851,217 @@ fn diagnostics_from_mod(mod_: &ModuleInfo) -> Vec<lsp_types::Diagnostic> {
                 ..Diagnostic::default()
             }
         })
-        .collect()
+        .collect();
+    diagnostics.extend(sema_diagnostics_from_mod(mod_));
+    diagnostics.truncate(MAX_DIAGNOSTICS);
+    diagnostics
+}
+
+fn sema_diagnostics_from_mod(mod_: &ModuleInfo) -> Vec<lsp_types::Diagnostic> {
+    use lsp_types::{Diagnostic, DiagnosticSeverity, DiagnosticTag};
+    let (Some(bir), Some(sema)) = (mod_.bir.as_ref(), mod_.sema.as_ref())
+    else {
+        return Vec::new();
+    };
+    let syntax_of = |id: &bir::ID| syntax_in_module(id, mod_);
+    let range_of =
+        |syntax: &cst::syntax::Node| range_to_lsp(&mod_.text, syntax.range());
+
+    let mut diagnostics = Vec::new();
+    for err in sema.errors() {
+        let Some(syntax) = sema.bir(err.ids[0]).and_then(|id| syntax_of(&id))
+        else {
+            continue;
+        };
+        let replacements: Vec<_> = err
+            .ids
+            .iter()
+            .map(|&id| {
+                sema.bir(id)
+                    .and_then(|id| syntax_of(&id))
+                    .map_or_else(String::new, |syntax| syntax.text())
+            })
+            .collect();
+        diagnostics.push(Diagnostic {
+            range: range_of(&syntax),
+            message: err.render(&replacements),
+            severity: Some(DiagnosticSeverity::ERROR),
+            ..Diagnostic::default()
+        });
+    }
+    for warning in sema.warnings() {
+        let Some(syntax) = syntax_of(&warning.bir) else {
+            continue;
+        };
+        let (text, severity) = match warning.kind {
+            sema::errors::WarningKind::UnusedVariable => (
+                bir.let_(&warning.bir).ident.clone(),
+                DiagnosticSeverity::WARNING,
+            ),
+            sema::errors::WarningKind::UnreachableCode => {
+                (syntax.text(), DiagnosticSeverity::HINT)
+            }
+            sema::errors::WarningKind::UnusedImport => (
+                bir.import(&warning.bir).name.clone(),
+                DiagnosticSeverity::WARNING,
+            ),
+            sema::errors::WarningKind::NullDereference => {
+                (syntax.text(), DiagnosticSeverity::WARNING)
+            }
+        };
+        // Everything else points out code that can be removed
+        let unnecessary =
+            warning.kind != sema::errors::WarningKind::NullDereference;
+        diagnostics.push(Diagnostic {
+            range: range_of(&syntax),
+            message: warning.render(&text),
+            severity: Some(severity),
+            tags: unnecessary.then(|| vec![DiagnosticTag::UNNECESSARY]),
+            ..Diagnostic::default()
+        });
+    }
+    diagnostics
 }
 
-fn parse_module(text: &str) -> ModuleInfo {
-    let Output { root, errors } = parser::parse_str(text);
-    ModuleInfo::new(root, errors, text.to_string())
+fn parse_module(
+    text: String,
+    tokens: cst::lexer::Tokens,
+    path: &std::path::Path,
+) -> ModuleInfo {
+    let input = parser::Input::from_lexed(&text, tokens.clone());
+    let Output { root, errors } = parser::parse(input);
+    let mut info = ModuleInfo::new(root, errors, text, tokens);
+    if info.errs.is_empty() {
+        let root = std::panic::AssertUnwindSafe(&info.mod_);
+        let analyze = move || analyze_module(&root, path);
+        match std::panic::catch_unwind(analyze) {
+            Ok((bir, sema)) => {
+                info.bir = Some(bir);
+                info.sema = Some(sema);
+            }
+            Err(_) => log::debug!("semantic analysis of {path:?} crashed"),
+        }
+    }
+    info
+}
+
+fn analyze_module(
+    root: &cst::syntax::Node,
+    path: &std::path::Path,
+) -> (bir::Map, sema::Map) {
+    // Imports are resolved relative to the importing module
+    struct AstBuilder<'p>(&'p std::path::Path);
+    impl bir::translate::AstBuilder for AstBuilder<'_> {
+        type Error = ();
+        fn build(
+            &mut self,
+            module_name: &str,
+        ) -> Result<std::sync::Arc<ast::Module>, ()> {
+            let path = self.0.with_file_name(format!("{module_name}.ty"));
+            let text = std::fs::read_to_string(path).map_err(|_| ())?;
+            let Output { root, errors } = parser::parse_str(&text);
+            if !errors.is_empty() {
+                return Err(());
+            }
+            ast::Module::cast(root).ok_or(())
+        }
+    }
+    let ast = ast::Module::cast(root.clone()).unwrap();
+    let bir = bir::translate::ast(&ast, &mut AstBuilder(path));
+    let sema = sema::check::check(&bir);
+    (bir, sema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn annotate_inferred_let() {
+        let text = "fn main() -> i64 { let x = 42; x }";
+        let tokens = cst::lexer::lex(text);
+        let path = std::path::Path::new("main.ty");
+        let mut info = parse_module(text.to_string(), tokens, path);
+        let uri = lsp_types::Url::parse("file:///main.ty").unwrap();
+        let cursor = lsp_types::Position::new(0, 23);
+        let range = lsp_types::Range::new(cursor, cursor);
+
+        let actions = type_annotation_actions(&uri, &range, &mut info);
+        assert_eq!(actions.len(), 1);
+        let changes = actions[0].edit.as_ref().unwrap().changes.as_ref();
+        let edit = &changes.unwrap()[&uri][0];
+        let offset = offset_at_position(&edit.range.start, &mut info);
+        let mut fixed = text.to_string();
+        fixed.insert_str(offset, &edit.new_text);
+        assert_eq!(fixed, "fn main() -> i64 { let x: i64 = 42; x }");
+    }
+
+    #[test]
+    fn block_comment_token_per_line() {
+        let text = "/* a\n\n b */ // c";
+        let path = std::path::Path::new("main.ty");
+        let info = parse_module(text.to_string(), cst::lexer::lex(text), path);
+        let tokens: Vec<_> = semantic_tokens::compute_from_module(&info)
+            .iter()
+            .map(|t| (t.delta_line, t.delta_start, t.length))
+            .collect();
+        // The empty line in between gets no token
+        assert_eq!(tokens, [(0, 0, 4), (2, 0, 5), (0, 6, 4)]);
+    }
+
+    #[test]
+    fn definition_of_path() {
+        let text = "mod m { fn f() -> i64 { 1 } }\nfn main() -> i64 { m::f() }";
+        let path = std::path::Path::new("main.ty");
+        let mut info =
+            parse_module(text.to_string(), cst::lexer::lex(text), path);
+        let definition = |info: &mut ModuleInfo, offset| {
+            let pos = offset_to_position(text, offset);
+            let range = definition_at_position(&pos, info)?;
+            let start = offset_at_position(&range.start, info);
+            let end = offset_at_position(&range.end, info);
+            Some(text[start..end].to_string())
+        };
+        let call = text.find("m::f").unwrap();
+        assert_eq!(
+            definition(&mut info, call + 3).as_deref(),
+            Some("fn f() -> i64 { 1 }")
+        );
+        // `i64` is a builtin, not an item
+        assert_eq!(definition(&mut info, text.rfind("i64").unwrap()), None);
+    }
+
+    #[test]
+    fn positions_count_utf16_units() {
+        // `é` is 2 bytes but 1 UTF-16 unit, and `𝄞` is 4 bytes but 2 units
+        let text = "// é𝄞 x\nfn f() {}";
+        let position = lsp_types::Position::new;
+        assert_eq!(position_to_offset(text, position(0, 7)), 10);
+        assert_eq!(&text[position_to_offset(text, position(0, 7))..][..1], "x");
+        assert_eq!(offset_to_position(text, 10), position(0, 7));
+        // Past the end of the line clamps to before the newline
+        assert_eq!(position_to_offset(text, position(0, 100)), 11);
+        assert_eq!(position_to_offset(text, position(1, 3)), 15);
+        assert_eq!(position_to_offset(text, position(5, 0)), text.len());
+
+        let (text, _) = apply_changes(
+            text.to_string(),
+            cst::lexer::lex(text),
+            vec![lsp_types::TextDocumentContentChangeEvent {
+                range: Some(lsp_types::Range::new(
+                    position(0, 7),
+                    position(0, 8),
+                )),
+                range_length: None,
+                text: "y".to_string(),
+            }],
+        );
+        assert_eq!(text, "// é𝄞 y\nfn f() {}");
+
+        let tokens = cst::lexer::lex(&text);
+        let path = std::path::Path::new("main.ty");
+        let mut info = parse_module(text.clone(), tokens, path);
+        assert_eq!(offset_at_position(&position(0, 7), &mut info), 10);
+        assert_eq!(offset_at_position(&position(0, 100), &mut info), 11);
+    }
 }
@@ -69,25 +69,44 @@ pub(crate) fn compute_from_module(
     let TokenInfo { tokens, deltas } = collect_tokens(info);
 
     let mut semantic_tokens = Vec::new();
-    let (mut acc_delta_line, mut acc_delta_column) = (0, 0);
-    for i in 0..tokens.len() {
-        let token = &tokens[i];
-        let (delta_line, delta_column) = deltas[i];
+    // Where the current token starts, and where the last semantic token did
+    let (mut line, mut column) = (0, 0);
+    let (mut last_line, mut last_column) = (0, 0);
+    for (token, &(delta_line, delta_column)) in tokens.iter().zip(&deltas) {
         if delta_line > 0 {
-            acc_delta_line += delta_line;
-            acc_delta_column = delta_column;
+            line += delta_line;
+            column = delta_column;
         } else {
-            acc_delta_column += delta_column;
+            column += delta_column;
         }
-        if let Some(kind) = type_of_token(token) {
+        let Some(kind) = type_of_token(token) else {
+            continue;
+        };
+        // Clients needn't support tokens spanning lines, so a block comment
+        // gets a token for each of its lines
+        for (i, text) in token.text().split('\n').enumerate() {
+            let text = text.strip_suffix('\r').unwrap_or(text);
+            if text.is_empty() {
+                continue;
+            }
+            let (token_line, token_column) = if i == 0 {
+                (line, column)
+            } else {
+                (line + i, 0)
+            };
+            let delta_start = if token_line == last_line {
+                token_column - last_column
+            } else {
+                token_column
+            };
             semantic_tokens.push(lsp_types::SemanticToken {
-                delta_line: acc_delta_line as u32,
-                delta_start: acc_delta_column as u32,
-                length: token.text().len() as u32,
-                token_type: legend()[kind],
+                delta_line: (token_line - last_line) as u32,
+                delta_start: delta_start as u32,
+                length: text.len() as u32,
+                token_type: legend()[kind.clone()],
                 token_modifiers_bitset: 0,
             });
-            (acc_delta_line, acc_delta_column) = (0, 0);
+            (last_line, last_column) = (token_line, token_column);
         }
     }
 
@@ -116,15 +135,15 @@ fn collect_tokens(info: &ModuleInfo) -> TokenInfo {
     impl cst::syntax::traverse::Visitor for TokenInfo {
         fn visit(&mut self, node: cst::syntax::NodeOrToken) {
             if let Some(token) = node.into_token() {
-                self.tokens.push(token.clone());
                 self.deltas.push(delta_position(token.text()));
+                self.tokens.push(token);
             }
         }
     }
 
     let mut collector = TokenInfo::default();
     collector.deltas.push((0, 0));
-    cst::syntax::traverse::preorder(&mut collector, info.mod_.clone());
+    cst::syntax::traverse::walk_preorder(&mut collector, info.mod_.clone());
     assert_eq!(collector.deltas.len(), collector.tokens.len() + 1);
     collector
 }
@@ -133,7 +152,7 @@ fn type_of_token(token: &cst::syntax::Token) -> Option<SemanticTokenType> {
     match token.kind() {
         NUMBER => Some(SemanticTokenType::NUMBER),
         STRING => Some(SemanticTokenType::STRING),
-        COMMENT => Some(SemanticTokenType::COMMENT),
+        COMMENT | BLOCK_COMMENT => Some(SemanticTokenType::COMMENT),
         IDENT => type_of_ident(token),
         kind if kind.is_operator() => Some(SemanticTokenType::OPERATOR),
         kind if kind.is_keyword() => Some(SemanticTokenType::KEYWORD),
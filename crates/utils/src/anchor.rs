@@ -1,6 +1,82 @@
+/// A 1-based line and column in a source, along with its offset in chars.
+///
+/// Anchors order line-major, i.e. by line, then column.
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, PartialOrd, Ord, Hash)]
 pub struct Anchor {
     pub line: usize,
     pub column: usize,
     pub offset: usize,
 }
+
+impl Anchor {
+    /// The anchor just past `text`, if `text` starts at this anchor.
+    pub fn advance(self, text: &str) -> Anchor {
+        text.chars().fold(self, |mut anchor, c| {
+            if c == '\n' {
+                anchor.line += 1;
+                anchor.column = 1;
+            } else {
+                anchor.column += 1;
+            }
+            anchor.offset += 1;
+            anchor
+        })
+    }
+
+    /// The distance in bytes from `other` to this anchor in `source`.
+    ///
+    /// Only the line and column are used, so this also works for anchors
+    /// whose `offset` counts chars rather than bytes.
+    pub fn offset_from(self, other: Anchor, source: &str) -> usize {
+        debug_assert!(other <= self);
+        self.byte_offset_in(source) - other.byte_offset_in(source)
+    }
+
+    fn byte_offset_in(self, source: &str) -> usize {
+        let line_start: usize = source
+            .split_inclusive('\n')
+            .take(self.line - 1)
+            .map(str::len)
+            .sum();
+        let column: usize = source[line_start..]
+            .chars()
+            .take(self.column - 1)
+            .map(char::len_utf8)
+            .sum();
+        line_start + column
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(line: usize, column: usize, offset: usize) -> Anchor {
+        Anchor {
+            line,
+            column,
+            offset,
+        }
+    }
+
+    #[test]
+    fn line_major_ordering() {
+        assert!(at(1, 10, 9) < at(2, 1, 12));
+        assert!(at(2, 1, 12) < at(2, 3, 14));
+    }
+
+    #[test]
+    fn advance_over_lines() {
+        assert_eq!(at(1, 1, 0).advance("foo"), at(1, 4, 3));
+        assert_eq!(at(1, 4, 3).advance("\nba"), at(2, 3, 6));
+        assert_eq!(at(1, 1, 0).advance(""), at(1, 1, 0));
+    }
+
+    #[test]
+    fn offset_from_counts_bytes() {
+        let source = "foo\nbär baz\n";
+        assert_eq!(at(1, 3, 2).offset_from(at(1, 1, 0), source), 2);
+        assert_eq!(at(2, 1, 4).offset_from(at(1, 2, 1), source), 3);
+        assert_eq!(at(2, 5, 8).offset_from(at(2, 1, 4), source), 5);
+    }
+}
@@ -1,4 +1,4 @@
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Int {
     storage: IntStorage,
     bit_width: u64,
@@ -171,7 +171,7 @@ where
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 enum IntStorage {
     Small(u64),
     Big(Vec<u64>),
@@ -181,6 +181,408 @@ fn width(v: u64) -> u64 {
     64 - (v.leading_zeros() as u64)
 }
 
+fn parts_width(parts: &[u64]) -> u64 {
+    match parts.last() {
+        Some(&last) => (parts.len() as u64 - 1) * 64 + width(last),
+        None => 0,
+    }
+}
+
+impl Int {
+    /// Parses a run of digits in `radix`, e.g. `ff` in base 16, into an
+    /// `Int` exactly as wide as its value.
+    pub fn from_str_radix(s: &str, radix: u32) -> Option<Self> {
+        if s.is_empty() {
+            return None;
+        }
+        let mut parts = Vec::new();
+        for c in s.chars() {
+            let digit = c.to_digit(radix)?;
+            mul_add_small(&mut parts, radix as u64, digit as u64);
+        }
+        Some(Self::from_parts(parts))
+    }
+
+    /// The number of bits the value needs, e.g. 3 for `5`, however wide the
+    /// `Int` itself is.
+    pub fn width(&self) -> u64 {
+        parts_width(self.parts())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.parts().is_empty()
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        match *self.parts() {
+            [] => Some(0),
+            [part] => Some(part),
+            _ => None,
+        }
+    }
+
+    /// An `Int` is never negative, so this is `None` for anything larger
+    /// than `i64::MAX`.
+    pub fn to_i64(&self) -> Option<i64> {
+        self.to_u64().and_then(|v| i64::try_from(v).ok())
+    }
+
+    /// `self - rhs`, or `None` if `rhs` is larger, since an `Int` can't be
+    /// negative.
+    pub fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        if cmp_parts(self.parts(), rhs.parts()) == std::cmp::Ordering::Less {
+            return None;
+        }
+        Some(Int::from_parts(sub_parts(self.parts(), rhs.parts())))
+    }
+
+    /// An `Int` exactly as wide as the value of the little-endian `parts`.
+    fn from_parts(mut parts: Vec<u64>) -> Self {
+        while parts.last() == Some(&0) {
+            parts.pop();
+        }
+        match parts[..] {
+            [] => Int::small(0, 0),
+            [part] => Int::small(part, width(part)),
+            _ => {
+                let bit_width = parts_width(&parts);
+                Int::big(parts, bit_width)
+            }
+        }
+    }
+
+    /// The value as little-endian parts, without leading zeros.
+    fn parts(&self) -> &[u64] {
+        let parts = match &self.storage {
+            IntStorage::Small(v) => std::slice::from_ref(v),
+            IntStorage::Big(parts) => &parts[..],
+        };
+        let len = parts.iter().rposition(|&p| p != 0).map_or(0, |i| i + 1);
+        &parts[..len]
+    }
+}
+
+/// Panics if `rhs` is larger, as for `u64`. See [`Int::checked_sub`].
+impl std::ops::Sub for Int {
+    type Output = Int;
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(&rhs)
+            .expect("attempt to subtract with overflow")
+    }
+}
+
+impl std::ops::Mul for Int {
+    type Output = Int;
+    fn mul(self, rhs: Self) -> Self::Output {
+        let (lhs, rhs) = (self.parts(), rhs.parts());
+        let mut res = vec![0u64; lhs.len() + rhs.len()];
+        for (i, &a) in lhs.iter().enumerate() {
+            let mut carry = 0u128;
+            for (j, &b) in rhs.iter().enumerate() {
+                let acc =
+                    res[i + j] as u128 + (a as u128) * (b as u128) + carry;
+                res[i + j] = acc as u64;
+                carry = acc >> 64;
+            }
+            res[i + rhs.len()] = carry as u64;
+        }
+        Int::from_parts(res)
+    }
+}
+
+/// Truncating division, as for the builtin integer types.
+impl std::ops::Div for Int {
+    type Output = Int;
+    fn div(self, rhs: Self) -> Self::Output {
+        assert!(!rhs.is_zero(), "attempt to divide by zero");
+        let (lhs, rhs) = (self.parts(), rhs.parts());
+        let mut quotient = vec![0u64; lhs.len()];
+        let mut remainder = Vec::new();
+        for bit in (0..lhs.len() * 64).rev() {
+            // remainder = remainder * 2 + next bit of the dividend
+            let next = (lhs[bit / 64] >> (bit % 64)) & 1;
+            mul_add_small(&mut remainder, 2, next);
+            if cmp_parts(&remainder, rhs) != std::cmp::Ordering::Less {
+                remainder = sub_parts(&remainder, rhs);
+                while remainder.last() == Some(&0) {
+                    remainder.pop();
+                }
+                quotient[bit / 64] |= 1 << (bit % 64);
+            }
+        }
+        Int::from_parts(quotient)
+    }
+}
+
+impl std::fmt::Display for Int {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // Peel off 19 decimal digits at a time, least significant first
+        let mut chunks = Vec::new();
+        let mut rest = self.clone();
+        while !rest.is_zero() {
+            let quotient = rest.clone() / Int::from(CHUNK);
+            let product = quotient.clone() * Int::from(CHUNK);
+            chunks.push((rest - product).to_u64().unwrap());
+            rest = quotient;
+        }
+        write!(f, "{}", chunks.pop().unwrap())?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{chunk:019}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A rational number of any size, for exact arithmetic on constants. e.g.
+/// `3.14` is exactly `157/50`.
+///
+/// Always kept in lowest terms, and zero is never negative, so equal values
+/// compare equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ApRat {
+    negative: bool,
+    numerator: Int,
+    denominator: Int,
+}
+
+impl ApRat {
+    /// `numerator / denominator`, negated if `negative` is set.
+    pub fn new(negative: bool, numerator: Int, denominator: Int) -> Self {
+        assert!(!denominator.is_zero(), "attempt to divide by zero");
+        let gcd = gcd(numerator.clone(), denominator.clone());
+        let numerator = numerator / gcd.clone();
+        Self {
+            negative: negative && !numerator.is_zero(),
+            numerator,
+            denominator: denominator / gcd,
+        }
+    }
+
+    pub fn zero() -> Self {
+        Self::from(Int::from(0u64))
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn numerator(&self) -> &Int {
+        &self.numerator
+    }
+
+    pub fn denominator(&self) -> &Int {
+        &self.denominator
+    }
+
+    /// Parses an optionally `-`-prefixed decimal number, with or without a
+    /// fractional part, e.g. `-3.14`.
+    pub fn from_decimal_str(s: &str) -> Option<Self> {
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s),
+        };
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        if int.is_empty() {
+            return None;
+        }
+        let numerator = Int::from_str_radix(&format!("{int}{frac}"), 10)?;
+        let denominator =
+            Int::from_str_radix(&format!("1{}", "0".repeat(frac.len())), 10)?;
+        Some(Self::new(negative, numerator, denominator))
+    }
+
+    /// The nearest `f64`, rounding ties to even. Subnormal results are
+    /// rounded twice, so they may be off by one ulp.
+    pub fn to_f64(&self) -> f64 {
+        let (mantissa, exp) = self.to_mantissa_and_exp();
+        scale_by_pow2(mantissa as f64, exp)
+    }
+
+    /// The nearest `f32`, rounding ties to even. Subnormal results are
+    /// rounded twice, so they may be off by one ulp.
+    pub fn to_f32(&self) -> f32 {
+        let (mantissa, exp) = self.to_mantissa_and_exp();
+        // Exact, since `mantissa as f32` has at most 24 significant bits
+        scale_by_pow2(mantissa as f32 as f64, exp) as f32
+    }
+
+    /// A signed `mantissa` with more bits than any float has, along with
+    /// `exp` such that `mantissa * 2^exp` rounds the same way as `self`.
+    fn to_mantissa_and_exp(&self) -> (i128, i32) {
+        const BITS: i64 = 66;
+        if self.numerator.is_zero() {
+            return (0, 0);
+        }
+        // Shift so the quotient has `BITS` or `BITS + 1` bits
+        let shift = BITS
+            - (self.numerator.width() as i64 - self.denominator.width() as i64);
+        let (num, den) = if shift >= 0 {
+            (
+                self.numerator.clone() * pow2(shift as usize),
+                self.denominator.clone(),
+            )
+        } else {
+            (
+                self.numerator.clone(),
+                self.denominator.clone() * pow2(-shift as usize),
+            )
+        };
+        let quotient = num.clone() / den.clone();
+        // Any remainder makes the mantissa odd, so that it's never mistaken
+        // for a tie when rounding
+        let sticky = !(num - quotient.clone() * den).is_zero();
+        let parts = quotient.parts();
+        let mut mantissa = parts[0] as i128
+            | (parts.get(1).copied().unwrap_or(0) as i128) << 64;
+        mantissa |= sticky as i128;
+        if self.negative {
+            mantissa = -mantissa;
+        }
+        (mantissa, -shift as i32)
+    }
+
+    /// `self + rhs`, where `rhs` is negated if `negate_rhs` is set.
+    fn add_signed(self, rhs: Self, negate_rhs: bool) -> Self {
+        let rhs_negative = rhs.negative ^ negate_rhs;
+        let lhs_num = self.numerator * rhs.denominator.clone();
+        let rhs_num = rhs.numerator * self.denominator.clone();
+        let denominator = self.denominator * rhs.denominator;
+        if self.negative == rhs_negative {
+            return Self::new(self.negative, lhs_num + rhs_num, denominator);
+        }
+        match cmp_parts(lhs_num.parts(), rhs_num.parts()) {
+            std::cmp::Ordering::Less => {
+                Self::new(rhs_negative, rhs_num - lhs_num, denominator)
+            }
+            _ => Self::new(self.negative, lhs_num - rhs_num, denominator),
+        }
+    }
+}
+
+impl From<Int> for ApRat {
+    fn from(v: Int) -> Self {
+        Self::new(false, v, Int::from(1u64))
+    }
+}
+
+impl std::ops::Add for ApRat {
+    type Output = ApRat;
+    fn add(self, rhs: Self) -> Self::Output {
+        self.add_signed(rhs, false)
+    }
+}
+
+impl std::ops::Sub for ApRat {
+    type Output = ApRat;
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.add_signed(rhs, true)
+    }
+}
+
+impl std::ops::Mul for ApRat {
+    type Output = ApRat;
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.negative ^ rhs.negative,
+            self.numerator * rhs.numerator,
+            self.denominator * rhs.denominator,
+        )
+    }
+}
+
+impl std::ops::Div for ApRat {
+    type Output = ApRat;
+    fn div(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.negative ^ rhs.negative,
+            self.numerator * rhs.denominator,
+            self.denominator * rhs.numerator,
+        )
+    }
+}
+
+impl std::fmt::Display for ApRat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", self.numerator)?;
+        if self.denominator.to_u64() != Some(1) {
+            write!(f, "/{}", self.denominator)?;
+        }
+        Ok(())
+    }
+}
+
+/// The greatest common divisor of two integers.
+fn gcd(mut a: Int, mut b: Int) -> Int {
+    while !b.is_zero() {
+        let remainder = a.clone() - (a / b.clone()) * b.clone();
+        a = b;
+        b = remainder;
+    }
+    a
+}
+
+fn pow2(exp: usize) -> Int {
+    let mut parts = vec![0; exp / 64 + 1];
+    parts[exp / 64] = 1 << (exp % 64);
+    Int::from_parts(parts)
+}
+
+/// `v * 2^exp`, which is exact unless the result is subnormal or overflows.
+fn scale_by_pow2(mut v: f64, mut exp: i32) -> f64 {
+    // `2^exp` itself is only representable for `exp` in -1022..=1023
+    while exp > 1000 {
+        v *= 2f64.powi(1000);
+        exp -= 1000;
+    }
+    while exp < -1000 {
+        v *= 2f64.powi(-1000);
+        exp += 1000;
+    }
+    v * 2f64.powi(exp)
+}
+
+/// `parts = parts * mul + add`
+fn mul_add_small(parts: &mut Vec<u64>, mul: u64, add: u64) {
+    let mut carry = add as u128;
+    for part in parts.iter_mut() {
+        let acc = (*part as u128) * (mul as u128) + carry;
+        *part = acc as u64;
+        carry = acc >> 64;
+    }
+    if carry != 0 {
+        parts.push(carry as u64);
+    }
+}
+
+/// Compares parts without leading zeros.
+fn cmp_parts(a: &[u64], b: &[u64]) -> std::cmp::Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+/// `a - b`, where `a >= b`.
+fn sub_parts(a: &[u64], b: &[u64]) -> Vec<u64> {
+    debug_assert!(cmp_parts(a, b) != std::cmp::Ordering::Less);
+    let mut res = Vec::with_capacity(a.len());
+    let mut borrow = false;
+    for (i, &part) in a.iter().enumerate() {
+        let (diff, b1) = part.overflowing_sub(b.get(i).copied().unwrap_or(0));
+        let (diff, b2) = diff.overflowing_sub(borrow as u64);
+        res.push(diff);
+        borrow = b1 || b2;
+    }
+    debug_assert!(!borrow);
+    res
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -314,4 +716,118 @@ mod tests {
         //    Int::big(vec![1 << 62, 0, 0], 196),
         //);
     }
+
+    #[test]
+    fn test_parse() {
+        let int = |s| Int::from_str_radix(s, 10);
+        assert_eq!(int("0"), Some(Int::small(0, 0)));
+        assert_eq!(int("042"), Some(Int::small(42, 6)));
+        assert_eq!(Int::from_str_radix("ff", 16), Some(Int::small(255, 8)));
+        assert_eq!(Int::from_str_radix("FF", 16), Some(Int::small(255, 8)));
+        assert_eq!(int(""), None);
+        assert_eq!(int("-1"), None);
+        assert_eq!(int("12a"), None);
+        assert_eq!(int("18446744073709551616"), Some(Int::big(vec![0, 1], 65)));
+    }
+
+    #[test]
+    fn test_width_and_to_u64() {
+        assert_eq!(Int::small(5, 32).width(), 3);
+        assert_eq!(Int::small(0, 32).width(), 0);
+        assert_eq!(Int::big(vec![0, 1], 128).width(), 65);
+        assert_eq!(Int::from(u64::MAX).to_u64(), Some(u64::MAX));
+        assert_eq!(Int::big(vec![0, 1], 65).to_u64(), None);
+        assert_eq!(Int::big(vec![7, 0], 128).to_u64(), Some(7));
+        assert_eq!(Int::from(i64::MAX as u64).to_i64(), Some(i64::MAX));
+        assert_eq!(Int::from(i64::MAX as u64 + 1).to_i64(), None);
+        assert_eq!(Int::big(vec![0, 1], 65).to_i64(), None);
+    }
+
+    #[test]
+    fn test_sub_mul_div() {
+        let int = |s| Int::from_str_radix(s, 10).unwrap();
+        assert_eq!(int("10") - int("7"), int("3"));
+        assert_eq!(int("5") - int("5"), int("0"));
+        assert_eq!(
+            int("18446744073709551616") - int("1"),
+            int("18446744073709551615")
+        );
+        assert_eq!(
+            int("18446744073709551616") * int("18446744073709551616"),
+            int("340282366920938463463374607431768211456")
+        );
+        assert_eq!(
+            int("340282366920938463463374607431768211457")
+                / int("18446744073709551616"),
+            int("18446744073709551616")
+        );
+        assert_eq!(int("7") / int("2"), int("3"));
+    }
+
+    #[test]
+    #[should_panic(expected = "attempt to subtract with overflow")]
+    fn test_sub_overflow() {
+        let _ = Int::from(1u64) - Int::from(2u64);
+    }
+
+    #[test]
+    fn test_checked_sub() {
+        let int = |s| Int::from_str_radix(s, 10).unwrap();
+        assert_eq!(int("2").checked_sub(&int("1")), Some(int("1")));
+        assert_eq!(int("1").checked_sub(&int("2")), None);
+    }
+
+    #[test]
+    fn test_display() {
+        let s = "340282366920938463463374607431768211456";
+        assert_eq!(Int::from_str_radix(s, 10).unwrap().to_string(), s);
+        assert_eq!(
+            Int::from(1000000000000000000u64).to_string(),
+            "1000000000000000000"
+        );
+        assert_eq!(Int::small(0, 8).to_string(), "0");
+    }
+
+    #[test]
+    fn test_aprat_parse() {
+        let rat = |n: i64, d: i64| {
+            let (num, den) = (n.unsigned_abs(), d.unsigned_abs());
+            ApRat::new((n < 0) ^ (d < 0), Int::from(num), Int::from(den))
+        };
+        assert_eq!(ApRat::from_decimal_str("3.14"), Some(rat(157, 50)));
+        assert_eq!(ApRat::from_decimal_str("-0.5"), Some(rat(-1, 2)));
+        assert_eq!(ApRat::from_decimal_str("42"), Some(rat(42, 1)));
+        assert_eq!(ApRat::from_decimal_str("1."), Some(rat(1, 1)));
+        assert_eq!(ApRat::from_decimal_str(".5"), None);
+        assert_eq!(ApRat::from_decimal_str("1.2.3"), None);
+        assert_eq!(rat(2, -4), rat(-1, 2));
+        assert_eq!(rat(-1, 2).to_string(), "-1/2");
+        assert_eq!(rat(0, 5).to_string(), "0");
+    }
+
+    #[test]
+    fn test_aprat_arithmetic() {
+        let rat = |s| ApRat::from_decimal_str(s).unwrap();
+        let third = rat("1") / rat("3");
+        assert_eq!(third.clone() * rat("3"), rat("1"));
+        assert_eq!(third.clone() + third.clone() + third, rat("1"));
+        assert_eq!(rat("0.1") + rat("0.2"), rat("0.3"));
+        assert_eq!(rat("0.5") - rat("0.75"), rat("-0.25"));
+        assert_eq!(rat("-1.5") * rat("-2"), rat("3"));
+    }
+
+    #[test]
+    fn test_aprat_to_float() {
+        let rat = |s: &str| ApRat::from_decimal_str(s).unwrap();
+        for s in ["0", "1", "-2.5", "0.1", "3.14159"] {
+            let r = rat(s);
+            assert_eq!(r.to_f64(), s.parse::<f64>().unwrap(), "{s}");
+            assert_eq!(r.to_f32(), s.parse::<f32>().unwrap(), "{s}");
+        }
+        assert_eq!((rat("1") / rat("3")).to_f64(), 1.0 / 3.0);
+        assert_eq!((rat("2") / rat("3")).to_f32(), 2.0f32 / 3.0);
+        let huge = rat(&format!("17976931348623157{}", "0".repeat(292)));
+        assert_eq!(huge.to_f64(), f64::MAX);
+        assert_eq!((rat("1") / huge).to_f64(), 1.0 / f64::MAX);
+    }
 }
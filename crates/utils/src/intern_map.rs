@@ -39,6 +39,11 @@ where
     }
 
     pub fn add(&mut self, s: Value) -> Key {
+        self.get_or_insert(s)
+    }
+
+    /// The key for `s`, interning it first if no equal value is present.
+    pub fn get_or_insert(&mut self, s: Value) -> Key {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::Hasher;
         match self.inner.get_by_right(&s) {
@@ -60,7 +65,38 @@ where
         self.inner.get_by_left(id)
     }
 
+    pub fn remove(&mut self, id: &Key) -> Option<Value> {
+        self.inner.remove_by_left(id).map(|(_, value)| value)
+    }
+
     pub fn len(&self) -> usize {
         self.inner.len()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    declare_new_intern_id!(TestId);
+
+    #[test]
+    fn get_or_insert_dedups() {
+        let mut map = InternMap::<TestId, String>::new();
+        let foo = map.get_or_insert("foo".to_string());
+        let bar = map.get_or_insert("bar".to_string());
+        assert_ne!(foo, bar);
+        assert_eq!(map.get_or_insert("foo".to_string()), foo);
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn remove() {
+        let mut map = InternMap::<TestId, String>::new();
+        let foo = map.get_or_insert("foo".to_string());
+        assert_eq!(map.remove(&foo), Some("foo".to_string()));
+        assert_eq!(map.get(&foo), None);
+        assert_eq!(map.remove(&foo), None);
+        assert_eq!(map.get_or_insert("foo".to_string()), foo);
+    }
+}
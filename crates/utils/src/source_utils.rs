@@ -135,6 +135,22 @@ impl Source {
         self.line_ends.len() + last_line_has_no_line_end as usize
     }
 
+    /// The number of `\n`s in the source. Unlike `num_lines`, a last line
+    /// without a `\n` isn't counted.
+    pub fn line_count(&self) -> usize {
+        self.line_ends.len()
+    }
+
+    /// The number of `char`s (Unicode scalar values) in the source.
+    pub fn char_count(&self) -> usize {
+        self.chars.len()
+    }
+
+    /// The length of the source in bytes, when encoded as UTF-8.
+    pub fn byte_count(&self) -> usize {
+        self.chars.iter().map(|c| c.len_utf8()).sum()
+    }
+
     pub fn line(&self, number: usize) -> Option<String> {
         if number > self.num_lines() {
             return None;
@@ -396,4 +412,17 @@ mod tests {
         assert_eq!(Source::from_str("foo\nbar").num_lines(), 2);
         assert_eq!(Source::from_str("foo\nbar\n").num_lines(), 2);
     }
+
+    #[test]
+    fn counts() {
+        let source = Source::from_str("");
+        assert_eq!(source.line_count(), 0);
+        assert_eq!(source.char_count(), 0);
+        assert_eq!(source.byte_count(), 0);
+
+        let source = Source::from_str("héllo\nwörld");
+        assert_eq!(source.line_count(), 1);
+        assert_eq!(source.char_count(), 11);
+        assert_eq!(source.byte_count(), 13);
+    }
 }
@@ -30,4 +30,74 @@ impl Span {
     pub fn contains(&self, other: &Span) -> bool {
         self.start <= other.start && other.end <= self.end
     }
+
+    pub fn contains_span(&self, other: Span) -> bool {
+        self.contains(&other)
+    }
+
+    /// Spans are half-open, so `end` itself isn't contained.
+    pub fn contains_anchor(&self, pos: Anchor) -> bool {
+        self.start <= pos && pos < self.end
+    }
+
+    /// The smallest span covering both `self` and `other`.
+    pub fn union(&self, other: Span) -> Span {
+        Span {
+            start: std::cmp::min(self.start, other.start),
+            end: std::cmp::max(self.end, other.end),
+        }
+    }
+
+    /// The span covered by both `self` and `other`, if they overlap at all.
+    pub fn intersection(&self, other: Span) -> Option<Span> {
+        let start = std::cmp::max(self.start, other.start);
+        let end = std::cmp::min(self.end, other.end);
+        (start < end).then_some(Span { start, end })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(start: usize, end: usize) -> Span {
+        let at = |offset| Anchor {
+            line: 1,
+            column: offset + 1,
+            offset,
+        };
+        Span {
+            start: at(start),
+            end: at(end),
+        }
+    }
+
+    #[test]
+    fn contains_anchor() {
+        let s = span(2, 5);
+        assert!(!s.contains_anchor(span(1, 1).start));
+        assert!(s.contains_anchor(span(2, 2).start));
+        assert!(s.contains_anchor(span(4, 4).start));
+        assert!(!s.contains_anchor(span(5, 5).start));
+    }
+
+    #[test]
+    fn contains_span() {
+        assert!(span(2, 5).contains_span(span(2, 5)));
+        assert!(span(2, 5).contains_span(span(3, 4)));
+        assert!(!span(2, 5).contains_span(span(1, 4)));
+    }
+
+    #[test]
+    fn union() {
+        assert_eq!(span(2, 5).union(span(7, 9)), span(2, 9));
+        assert_eq!(span(2, 5).union(span(3, 4)), span(2, 5));
+    }
+
+    #[test]
+    fn intersection() {
+        assert_eq!(span(2, 5).intersection(span(4, 9)), Some(span(4, 5)));
+        assert_eq!(span(2, 5).intersection(span(3, 4)), Some(span(3, 4)));
+        assert_eq!(span(2, 5).intersection(span(5, 9)), None);
+    }
 }
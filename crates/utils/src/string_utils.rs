@@ -34,41 +34,115 @@ impl std::fmt::Display for ListSeparator {
     }
 }
 
-/// Trim off leading and trailing quotation marks '"', and handle escape
-/// sequences (e.g. '\n')
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnescapeError {
+    /// Byte offset of the `\` starting the bad escape sequence
+    pub offset: usize,
+    pub kind: UnescapeErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnescapeErrorKind {
+    UnknownEscape,
+    InvalidHexEscape,
+    InvalidUnicodeEscape,
+    TrailingBackslash,
+}
+
+impl std::fmt::Display for UnescapeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self.kind {
+            UnescapeErrorKind::UnknownEscape => "unknown escape sequence",
+            UnescapeErrorKind::InvalidHexEscape => "invalid `\\x` escape",
+            UnescapeErrorKind::InvalidUnicodeEscape => "invalid `\\u` escape",
+            UnescapeErrorKind::TrailingBackslash => "trailing `\\`",
+        };
+        write!(f, "{msg} at offset {}", self.offset)
+    }
+}
+
+/// Handle the escape sequences in the body of a string literal, i.e. without
+/// its quotation marks.
 /// ```
-/// assert_eq!(utils::trim_and_unescape(r#""foo""#), "foo");
-/// assert_eq!(utils::trim_and_unescape(r#""bar\n""#), "bar\n");
-/// assert_eq!(utils::trim_and_unescape(r#""baz\nbip\rbing""#), "baz\nbip\rbing");
-/// assert_eq!(utils::trim_and_unescape(r#""baz\\\rbip""#), "baz\\\rbip");
-/// assert_eq!(utils::trim_and_unescape(r#""baz\\\dbip""#), r"baz\\dbip");
-/// assert_eq!(utils::trim_and_unescape(r#""""#), "");
+/// use utils::{unescape, UnescapeErrorKind};
+/// assert_eq!(unescape(r#"a\tb\\c\"d\n"#).unwrap(), "a\tb\\c\"d\n");
+/// assert_eq!(unescape(r"\x41é").unwrap(), "Aé");
+/// assert_eq!(unescape(r"ab\d").unwrap_err().offset, 2);
+/// assert_eq!(
+///     unescape(r"\xff").unwrap_err().kind,
+///     UnescapeErrorKind::InvalidHexEscape
+/// );
+/// assert_eq!(
+///     unescape(r"\").unwrap_err().kind,
+///     UnescapeErrorKind::TrailingBackslash
+/// );
 /// ```
-pub fn trim_and_unescape(s: &str) -> String {
-    debug_assert!(s.len() >= 2);
-    debug_assert!(s.chars().next() == Some('"'));
-    debug_assert!(s.chars().last() == Some('"'));
-
+pub fn unescape(s: &str) -> Result<String, UnescapeError> {
     let mut res = String::with_capacity(s.len());
-    let mut in_escape = false;
-    for c in s[1..s.len() - 1].chars() {
-        if !in_escape && c == '\\' {
-            in_escape = true;
+    let mut chars = s.char_indices();
+    while let Some((offset, c)) = chars.next() {
+        if c != '\\' {
+            res.push(c);
             continue;
         }
-        match c {
-            'n' if in_escape => res.push('\n'),
-            'r' if in_escape => res.push('\r'),
-            '\\' if in_escape => res.push('\\'),
-            _ if in_escape => {
-                res.push('\\');
-                res.push(c);
+        let err = |kind| UnescapeError { offset, kind };
+        let escaped = match chars.next() {
+            Some((_, 'n')) => '\n',
+            Some((_, 't')) => '\t',
+            Some((_, 'r')) => '\r',
+            Some((_, '\\')) => '\\',
+            Some((_, '"')) => '"',
+            Some((_, 'x')) => {
+                let kind = UnescapeErrorKind::InvalidHexEscape;
+                // Only ASCII, so that every escape produces exactly one byte
+                hex_digits(&mut chars, 2)
+                    .and_then(char::from_u32)
+                    .filter(char::is_ascii)
+                    .ok_or(err(kind))?
+            }
+            Some((_, 'u')) => {
+                let kind = UnescapeErrorKind::InvalidUnicodeEscape;
+                hex_digits(&mut chars, 4)
+                    .and_then(char::from_u32)
+                    .ok_or(err(kind))?
             }
-            _ => {
-                res.push(c);
+            Some(_) => return Err(err(UnescapeErrorKind::UnknownEscape)),
+            None => return Err(err(UnescapeErrorKind::TrailingBackslash)),
+        };
+        res.push(escaped);
+    }
+    Ok(res)
+}
+
+fn hex_digits(chars: &mut std::str::CharIndices, n: usize) -> Option<u32> {
+    let digits = chars.as_str().get(..n)?;
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    chars.nth(n - 1);
+    u32::from_str_radix(digits, 16).ok()
+}
+
+/// The inverse of `unescape`: a string literal body that evaluates to `s`.
+/// ```
+/// use utils::escape;
+/// assert_eq!(escape("a\tb\\c\"d\n"), r#"a\tb\\c\"d\n"#);
+/// assert_eq!(escape("\u{1b}[0mé"), r"\x1b[0mé");
+/// ```
+pub fn escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\n' => res.push_str(r"\n"),
+            '\t' => res.push_str(r"\t"),
+            '\r' => res.push_str(r"\r"),
+            '\\' => res.push_str(r"\\"),
+            '"' => res.push_str(r#"\""#),
+            _ if c.is_ascii_control() => {
+                res.push_str(&format!(r"\x{:02x}", c as u32))
             }
+            _ => res.push(c),
         }
-        in_escape = false;
     }
     res
 }
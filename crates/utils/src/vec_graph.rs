@@ -84,8 +84,8 @@ impl<T> VecGraph<T> {
 
     #[inline]
     pub fn add_edge(&mut self, from: Vertex<T>, to: Vertex<T>) -> bool {
-        if self.predecessors[from.idx].contains(&to) {
-            debug_assert!(self.successors[to.idx].contains(&from));
+        if self.successors[from.idx].contains(&to) {
+            debug_assert!(self.predecessors[to.idx].contains(&from));
             return false;
         }
         self.successors[from.idx].push(to);
@@ -304,7 +304,7 @@ impl<'graph, T: 'graph> Vertex<T> {
 
 pub mod traversal {
     use super::*;
-    use std::collections::HashSet;
+    use std::collections::{HashMap, HashSet};
 
     fn pre_post_order_impl<T, Pre, Post, const REVERSE: bool>(
         graph: &VecGraph<T>,
@@ -387,6 +387,80 @@ pub mod traversal {
         });
         vertices.iter().rev().for_each(|v| f(*v));
     }
+
+    /// Every strongly connected component of the graph, i.e. each maximal
+    /// set of vertices that can all reach each other, found with Tarjan's
+    /// algorithm. Unlike the other traversals, this covers every vertex, not
+    /// just those reachable from the start.
+    ///
+    /// A component comes before any component that can reach it, so e.g.
+    /// in a call graph, callees come before their callers.
+    pub fn strongly_connected_components<T>(
+        graph: &VecGraph<T>,
+    ) -> Vec<Vec<Vertex<T>>> {
+        struct Tarjan<'g, T> {
+            graph: &'g VecGraph<T>,
+            next_index: usize,
+            // The order each vertex was first visited in, and the earliest
+            // visited vertex on the stack it can reach
+            index: HashMap<Vertex<T>, usize>,
+            low_link: HashMap<Vertex<T>, usize>,
+            stack: Vec<Vertex<T>>,
+            on_stack: HashSet<Vertex<T>>,
+            components: Vec<Vec<Vertex<T>>>,
+        }
+
+        impl<T> Tarjan<'_, T> {
+            fn visit(&mut self, v: Vertex<T>) {
+                self.index.insert(v, self.next_index);
+                self.low_link.insert(v, self.next_index);
+                self.next_index += 1;
+                self.stack.push(v);
+                self.on_stack.insert(v);
+
+                for &succ in v.successors(self.graph) {
+                    if !self.index.contains_key(&succ) {
+                        self.visit(succ);
+                        let low = self.low_link[&v].min(self.low_link[&succ]);
+                        self.low_link.insert(v, low);
+                    } else if self.on_stack.contains(&succ) {
+                        let low = self.low_link[&v].min(self.index[&succ]);
+                        self.low_link.insert(v, low);
+                    }
+                }
+
+                if self.low_link[&v] == self.index[&v] {
+                    let mut component = Vec::new();
+                    loop {
+                        let w = self.stack.pop().unwrap();
+                        self.on_stack.remove(&w);
+                        component.push(w);
+                        if w == v {
+                            break;
+                        }
+                    }
+                    component.reverse();
+                    self.components.push(component);
+                }
+            }
+        }
+
+        let mut tarjan = Tarjan {
+            graph,
+            next_index: 0,
+            index: HashMap::new(),
+            low_link: HashMap::new(),
+            stack: Vec::new(),
+            on_stack: HashSet::new(),
+            components: Vec::new(),
+        };
+        for v in graph.vertices() {
+            if !graph.is_unlinked(&v) && !tarjan.index.contains_key(&v) {
+                tarjan.visit(v);
+            }
+        }
+        tarjan.components
+    }
 }
 
 #[cfg(test)]
@@ -611,6 +685,45 @@ mod tests {
         debug_assert_eq!(rpo_str, "A, B, C, D");
     }
 
+    #[test]
+    fn add_edge_both_ways() {
+        let mut g = VecGraph::new();
+        let a = g.add_vertex("a");
+        let b = g.add_vertex("b");
+        assert!(g.add_edge(a, b));
+        assert!(g.add_edge(b, a));
+        assert!(!g.add_edge(a, b));
+        assert_eq!(succ_string(&g, a), "b");
+        assert_eq!(succ_string(&g, b), "a");
+    }
+
+    #[test]
+    fn strongly_connected_components() {
+        let g = {
+            let mut g = VecGraph::new();
+            let a = g.add_vertex("A");
+            let b = g.add_successor(a, "B");
+            let c = g.add_successor(b, "C");
+            g.add_edge(c, b);
+            let d = g.add_successor(c, "D");
+            g.add_edge(d, d);
+            g.add_vertex("E");
+            g
+        };
+        let components: Vec<_> =
+            super::traversal::strongly_connected_components(&g)
+                .iter()
+                .map(|component| {
+                    component
+                        .iter()
+                        .map(|v| v.data(&g).to_string())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect();
+        assert_eq!(components, ["D", "B C", "A", "E"]);
+    }
+
     #[test]
     fn find_first() {
         let (g, b, b2, c) = {
@@ -1,9 +1,17 @@
 type Error = Box<dyn std::error::Error>;
 
+const TEST_USAGE: &str =
+    "USAGE: xtask test [--unit] [--integration] [--filter <pattern>]";
+
 fn main() -> Result<(), Error> {
-    let task = std::env::args().nth(1);
+    let mut args = std::env::args().skip(1);
+    let task = args.next();
+    let args: Vec<_> = args.collect();
     match task.as_ref().map(String::as_str) {
         Some("ci") => ci(),
+        Some("test") => test(&TestOptions::parse(&args)?),
+        Some("coverage") => coverage(),
+        Some("bench") => bench(&args),
         _ => Ok(()),
     }
 }
@@ -23,18 +31,229 @@ fn build_package(proj: &str) -> Result<(), Error> {
 }
 
 fn ci() -> Result<(), Error> {
-    build_package("testc")?;
-    build_package("tyc")?;
+    test(&TestOptions::default())
+}
+
+#[derive(Default)]
+struct TestOptions {
+    /// Only run `cargo test`
+    unit: bool,
+    /// Only run the `testc` tests
+    integration: bool,
+    /// Forwarded to both `cargo test` and `testc`
+    filter: Option<String>,
+}
+
+impl TestOptions {
+    fn parse(args: &[String]) -> Result<Self, Error> {
+        let mut options = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--unit" => options.unit = true,
+                "--integration" => options.integration = true,
+                "--filter" => match args.next() {
+                    Some(filter) => options.filter = Some(filter.clone()),
+                    None => Err("`--filter` expects a pattern")?,
+                },
+                _ => Err(format!("unknown argument `{arg}`\n{TEST_USAGE}"))?,
+            }
+        }
+        // Asking for neither is the same as asking for both
+        if !options.unit && !options.integration {
+            options.unit = true;
+            options.integration = true;
+        }
+        Ok(options)
+    }
+}
+
+fn test(options: &TestOptions) -> Result<(), Error> {
+    let mut all_pass = true;
+
+    if options.unit {
+        let mut cargo_test = std::process::Command::new(cargo());
+        cargo_test.arg("test");
+        if let Some(filter) = &options.filter {
+            cargo_test.arg(filter);
+        }
+        all_pass &= cargo_test.status()?.success();
+    }
 
-    let unit_tests_pass = std::process::Command::new(cargo())
-        .args(&["test"])
+    if options.integration {
+        build_package("testc")?;
+        build_package("tyc")?;
+
+        let testc = |args: &[&str]| {
+            let mut testc = std::process::Command::new("target/debug/testc");
+            if let Some(filter) = &options.filter {
+                testc.args(["--filter", filter]);
+            }
+            testc.args(args).status()
+        };
+        all_pass &= testc(&["target/debug/tyc", "tests/"])?.success();
+        all_pass &= testc(&["--run-tests", "target/debug/tyc", "tests/unit/"])?
+            .success();
+    }
+
+    if !all_pass {
+        std::process::exit(-1)
+    }
+    Ok(())
+}
+
+/// Write an HTML coverage report for the unit tests to
+/// `target/llvm-cov/html`.
+fn coverage() -> Result<(), Error> {
+    let has_llvm_cov = std::process::Command::new(cargo())
+        .args(["llvm-cov", "--version"])
+        .output()
+        .is_ok_and(|output| output.status.success());
+    if !has_llvm_cov {
+        Err("coverage needs `cargo llvm-cov`; install it with \
+             `cargo install cargo-llvm-cov`")?;
+    }
+    let report = std::process::Command::new(cargo())
+        .args(["llvm-cov", "--workspace", "--html"])
         .status()?;
-    let testc_tests_pass = std::process::Command::new("target/debug/testc")
-        .args(&["target/debug/tyc", "tests/"])
+    if !report.success() {
+        Err("cargo llvm-cov failed!")?;
+    }
+    Ok(())
+}
+
+const BENCH_DIR: &str = "benches";
+const BENCH_BASELINE: &str = "benches/baseline.json";
+const BENCH_RUNS: usize = 100;
+/// How much slower than the baseline a phase can get before we fail
+const BENCH_TOLERANCE: f64 = 1.10;
+/// The phases `tyc --print-stats` times. It also prints the size of the
+/// input, e.g. `"lines"`, which isn't a timing and so isn't benchmarked.
+const BENCH_PHASES: &[&str] =
+    &["lex", "parse", "bir", "sema", "lir", "passes", "codegen"];
+
+/// Compile each program in `benches/` repeatedly, reporting the average,
+/// median and p99 time of each phase. With `--save-baseline`, the medians
+/// are written out as the new baseline; otherwise, they're compared against
+/// it.
+fn bench(args: &[String]) -> Result<(), Error> {
+    let save_baseline = match args {
+        [] => false,
+        [arg] if arg == "--save-baseline" => true,
+        _ => Err("USAGE: xtask bench [--save-baseline]")?,
+    };
+    // Read before benchmarking, so that a missing baseline fails fast
+    let baseline = if save_baseline {
+        None
+    } else {
+        let baseline =
+            std::fs::read_to_string(BENCH_BASELINE).map_err(|err| {
+                format!(
+                    "can't read {BENCH_BASELINE} ({err}); save one with \
+                    `xtask bench --save-baseline`"
+                )
+            })?;
+        Some(parse_stats(&baseline)?)
+    };
+
+    let build = std::process::Command::new(cargo())
+        .args(["build", "--release", "--package", "tyc"])
         .status()?;
+    if !build.success() {
+        Err("cargo build 'tyc' failed!")?;
+    }
 
-    if !unit_tests_pass.success() || !testc_tests_pass.success() {
+    let mut programs: Vec<_> = std::fs::read_dir(BENCH_DIR)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    programs.retain(|path| path.extension().is_some_and(|ext| ext == "ty"));
+    programs.sort();
+
+    let output = std::env::temp_dir().join("xtask-bench.o");
+    let mut medians = Vec::new();
+    for program in &programs {
+        let mut timings: Vec<(String, Vec<u64>)> = Vec::new();
+        for _ in 0..BENCH_RUNS {
+            let run = std::process::Command::new("target/release/tyc")
+                .arg("--print-stats")
+                .arg("--action=obj")
+                .arg(program)
+                .arg("-o")
+                .arg(&output)
+                .output()?;
+            if !run.status.success() {
+                Err(format!("failed to compile {}", program.display()))?;
+            }
+            let stderr = String::from_utf8(run.stderr)?;
+            let stats = stderr.lines().last().unwrap_or_default();
+            for (phase, time) in parse_stats(stats)? {
+                if !BENCH_PHASES.contains(&phase.as_str()) {
+                    continue;
+                }
+                match timings.iter_mut().find(|(p, _)| *p == phase) {
+                    Some((_, times)) => times.push(time),
+                    None => timings.push((phase, vec![time])),
+                }
+            }
+        }
+
+        println!("{}:", program.display());
+        for (phase, mut times) in timings {
+            times.sort();
+            let average = times.iter().sum::<u64>() / times.len() as u64;
+            let median = times[times.len() / 2];
+            let p99 = times[(times.len() * 99 / 100).min(times.len() - 1)];
+            println!(
+                "  {phase:>8}: avg {average}us, median {median}us, p99 {p99}us"
+            );
+            let name = program.file_name().unwrap().to_string_lossy();
+            medians.push((format!("{name}/{phase}"), median));
+        }
+    }
+
+    let Some(baseline) = baseline else {
+        let fields: Vec<_> = medians
+            .iter()
+            .map(|(key, time)| format!("  \"{key}\": {time}"))
+            .collect();
+        std::fs::write(
+            BENCH_BASELINE,
+            format!("{{\n{}\n}}\n", fields.join(",\n")),
+        )?;
+        println!("saved baseline to {BENCH_BASELINE}");
+        return Ok(());
+    };
+
+    let mut regressed = false;
+    for (key, median) in &medians {
+        let Some((_, base)) = baseline.iter().find(|(k, _)| k == key) else {
+            continue;
+        };
+        if *median as f64 > *base as f64 * BENCH_TOLERANCE {
+            println!("regression: {key} took {median}us (baseline {base}us)");
+            regressed = true;
+        }
+    }
+    if regressed {
         std::process::exit(-1)
     }
     Ok(())
 }
+
+/// Parse a flat JSON object of integers, like `{"lex": 12, "parse": 40}`.
+fn parse_stats(json: &str) -> Result<Vec<(String, u64)>, Error> {
+    let body = json
+        .trim()
+        .strip_prefix('{')
+        .and_then(|json| json.strip_suffix('}'))
+        .ok_or_else(|| format!("expected a JSON object, found `{json}`"))?;
+    let mut stats = Vec::new();
+    for field in body.split(',').filter(|field| !field.trim().is_empty()) {
+        let (key, value) = field
+            .split_once(':')
+            .ok_or_else(|| format!("bad JSON field `{field}`"))?;
+        let key = key.trim().trim_matches('"').to_string();
+        stats.push((key, value.trim().parse()?));
+    }
+    Ok(stats)
+}